@@ -0,0 +1,95 @@
+//! Command line argument helpers shared by the Canasta tools
+//!
+//! Only the binaries use this, the libraries never print or exit.
+
+use std::{env, process, str::FromStr, vec::IntoIter};
+
+/// Command line arguments of the Canasta tools
+/// # Overview
+/// Arguments are read in order as an iterator. Reading the value of an option that is
+/// missing or invalid prints the problem and the usage of the tool, then exits.
+/// # Example
+/// ```
+/// use args::Args;
+/// let mut args = Args::from_list(["--games", "5", "random"].map(String::from), "Usage: example [--games N] <agent>");
+/// assert_eq!(args.next().as_deref(), Some("--games"));
+/// let games: u32 = args.number();
+/// assert_eq!(games, 5);
+/// assert_eq!(args.next().as_deref(), Some("random"));
+/// assert_eq!(args.next(), None);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Args {
+    args: IntoIter<String>,
+    usage: &'static str,
+}
+
+impl Args {
+    /// The arguments the program was run with, after its name
+    pub fn new(usage: &'static str) -> Self {
+        Self::from_list(env::args().skip(1), usage)
+    }
+
+    pub fn from_list(args: impl IntoIterator<Item = String>, usage: &'static str) -> Self {
+        Self { args: args.into_iter().collect::<Vec<_>>().into_iter(), usage }
+    }
+
+    /// Reads the next argument as a number, exiting if it is not one
+    pub fn number<T: FromStr>(&mut self) -> T {
+        match self.args.next().and_then(|a| a.parse().ok()) {
+            Some(n) => n,
+            None => self.exit("Expected a number"),
+        }
+    }
+
+    /// Reads the next argument, exiting if there is none
+    /// # Parameters
+    /// - `expected` - What the argument is, such as `a file`
+    pub fn value(&mut self, expected: &str) -> String {
+        match self.args.next() {
+            Some(value) => value,
+            None => self.exit(&format!("Expected {expected}")),
+        }
+    }
+
+    /// Prints the message and the usage then exits, with success if the message is empty
+    pub fn exit(&self, message: &str) -> ! {
+        if !message.is_empty() { eprintln!("{message}") }
+        eprintln!("{}", self.usage);
+        process::exit(if message.is_empty() { 0 } else { 1 })
+    }
+}
+
+impl Iterator for Args {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.args.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Args {
+        Args::from_list(list.iter().map(|a| a.to_string()), "Usage: test")
+    }
+
+    #[test]
+    fn reads_values_and_numbers_in_order() {
+        let mut args = args(&["--seed", "18446744073709551615", "--file", "game.canasta", "-3"]);
+        assert_eq!(args.next().as_deref(), Some("--seed"));
+        assert_eq!(args.number::<u64>(), u64::MAX);
+        assert_eq!(args.next().as_deref(), Some("--file"));
+        assert_eq!(args.value("a file"), "game.canasta");
+        assert_eq!(args.number::<i32>(), -3);
+        assert_eq!(args.next(), None);
+    }
+
+    #[test]
+    fn remaining_arguments_can_be_collected() {
+        let args = args(&["random", "heuristic"]);
+        assert_eq!(args.collect::<Vec<_>>(), ["random", "heuristic"]);
+    }
+}
@@ -0,0 +1,122 @@
+use game_lib::card::Rank;
+
+/// Something the player typed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    Draw,
+    /// Take the pile using these cards from the hand
+    Take(Vec<String>),
+    /// Stage these cards from the hand into a meld of the rank
+    Meld(Rank, Vec<String>),
+    /// Return the cards staged for a rank to the hand
+    Unmeld(Rank),
+    Clear,
+    Commit,
+    /// Discard this card from the hand
+    Discard(String),
+    Hint,
+    Undo,
+    Redo,
+    Help,
+    Quit,
+}
+
+pub const HELP: &str = "\
+Commands, cards are given by their number in your hand or as notation like KH, 10D or JK:
+  draw | d                    draw a card from the stock
+  take [cards] | t            take the pile, using the cards to meld the top card
+  meld <rank> <cards> | m     stage cards into a meld, e.g. `meld K 3 4 5` or `meld K KH KS 2C`
+  unmeld <rank> | u           return the cards staged for a rank to your hand
+  clear                       return every staged card to your hand
+  commit | c                  lay your staged melds on the table
+  discard <card> | x          discard a card and end your turn
+  hint | h                    suggest what to do
+  undo, redo                  take back or replay an action, if enabled
+  help | ?                    show this help
+  quit | q                    leave the game";
+
+/// Parse a line typed by the player
+pub fn parse(line: &str) -> Result<Command, String> {
+    let mut words = line.split_whitespace();
+    let Some(name) = words.next() else { return Err("Type a command, or `help` to list them".to_string()) };
+    let args: Vec<&str> = words.collect();
+    let command = match name.to_lowercase().as_str() {
+        "draw" | "d" => Command::Draw,
+        "take" | "t" => Command::Take(strings(&args)),
+        "meld" | "m" => {
+            let (rank, cards) = args.split_first().ok_or("Give the rank to meld, e.g. `meld K 3 4 5`")?;
+            Command::Meld(parse_rank(rank)?, strings(cards))
+        }
+        "unmeld" | "u" => Command::Unmeld(parse_rank(args.first().ok_or("Give the rank to unmeld")?)?),
+        "clear" => Command::Clear,
+        "commit" | "c" => Command::Commit,
+        "discard" | "x" => match args[..] {
+            [card] => Command::Discard(card.to_string()),
+            _ => return Err("Give one card to discard".to_string()),
+        },
+        "hint" | "h" => Command::Hint,
+        "undo" => Command::Undo,
+        "redo" => Command::Redo,
+        "help" | "?" => Command::Help,
+        "quit" | "q" | "exit" => Command::Quit,
+        other => return Err(format!("Unknown command `{other}`, type `help` to list commands")),
+    };
+    Ok(command)
+}
+
+fn strings(args: &[&str]) -> Vec<String> {
+    args.iter().map(|a| a.to_string()).collect()
+}
+
+/// Reads a rank to meld, names may be plural such as `Kings`
+fn parse_rank(text: &str) -> Result<Rank, String> {
+    let singular = text.strip_suffix("es").filter(|t| t.eq_ignore_ascii_case("six"))
+        .or_else(|| text.strip_suffix(['s', 'S']).filter(|t| t.len() > 2));
+    text.parse().ok()
+        .or_else(|| singular.and_then(|t| t.parse().ok()))
+        .ok_or_else(|| format!("`{text}` is not a rank"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_and_shortcuts_parse() {
+        assert_eq!(parse("draw"), Ok(Command::Draw));
+        assert_eq!(parse("  D "), Ok(Command::Draw));
+        assert_eq!(parse("t 1 2"), Ok(Command::Take(vec!["1".to_string(), "2".to_string()])));
+        assert_eq!(parse("take"), Ok(Command::Take(vec![])));
+        assert_eq!(parse("meld K 3 KS"), Ok(Command::Meld(Rank::King, vec!["3".to_string(), "KS".to_string()])));
+        assert_eq!(parse("u 7"), Ok(Command::Unmeld(Rank::Seven)));
+        assert_eq!(parse("x 10D"), Ok(Command::Discard("10D".to_string())));
+        assert_eq!(parse("commit"), Ok(Command::Commit));
+        assert_eq!(parse("?"), Ok(Command::Help));
+        assert_eq!(parse("exit"), Ok(Command::Quit));
+    }
+
+    #[test]
+    fn bad_commands_are_explained() {
+        assert!(parse("").is_err());
+        assert!(parse("fly").unwrap_err().contains("`fly`"));
+        assert!(parse("meld").is_err());
+        assert!(parse("meld Z 1").unwrap_err().contains("`Z` is not a rank"));
+        assert!(parse("discard").is_err());
+        assert!(parse("discard 1 2").is_err());
+        assert!(parse("unmeld").is_err());
+    }
+
+    #[test]
+    fn ranks_may_be_plural() {
+        assert_eq!(parse_rank("Sixes"), Ok(Rank::Six));
+        assert_eq!(parse_rank("sixes"), Ok(Rank::Six));
+        assert_eq!(parse_rank("Kings"), Ok(Rank::King));
+        assert_eq!(parse_rank("ACES"), Ok(Rank::Ace));
+        assert_eq!(parse_rank("Tens"), Ok(Rank::Ten));
+        assert_eq!(parse_rank("10"), Ok(Rank::Ten));
+        assert_eq!(parse_rank("q"), Ok(Rank::Queen));
+        // single letters are not plurals of anything
+        assert!(parse_rank("Ks").is_err());
+        assert!(parse_rank("Kingss").is_err());
+    }
+}
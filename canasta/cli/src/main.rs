@@ -0,0 +1,227 @@
+//! Play Canasta in the terminal against bots or other people at the same computer
+//!
+//! Usage: `canasta [--canastas N] [--full | --hand] [--seed N] [--undo] [seat...]`
+//!
+//! Each seat is `human` or a bot: `random`, `heuristic`, `ismcts` or `ismcts:<iterations>`.
+//! Games are to 5000 points needing 2 canastas to go out unless changed. With no seats
+//! given you play against the heuristic bot.
+
+mod command;
+mod render;
+
+use std::{io::{self, BufRead, Write}, process, sync::mpsc::Receiver};
+
+use args::Args;
+use game_lib::{
+    game::{CanastaGame, PLAYERS},
+    action_log::Action,
+    agents::{Agent, AgentSpec, Driver, DriveResult},
+    events::{GameEvent, Viewer},
+    card::PlayCard,
+    errors::{player_action_error::PlayerActionError, notation_error::NotationError},
+};
+
+use command::{Command, HELP};
+
+const USAGE: &str = "Usage: canasta [--canastas N] [--full | --hand] [--seed N] [--undo] [human | random | heuristic | ismcts[:N]]...";
+
+fn main() {
+    let mut args = Args::new(USAGE);
+    let mut seats: Vec<Option<AgentSpec>> = vec![];
+    let mut builder = CanastaGame::builder().canastas(2).full_game();
+    while let Some(arg) = args.next() {
+        builder = match arg.as_str() {
+            "--canastas" => builder.canastas(args.number()),
+            "--full" => builder.full_game(),
+            "--hand" => builder.hand(),
+            "--seed" => builder.seed(args.number()),
+            "--undo" => builder.undo(),
+            "-h" | "--help" => args.exit(""),
+            "human" => { seats.push(None); builder }
+            bot => match bot.parse() {
+                Ok(spec) => { seats.push(Some(spec)); builder }
+                Err(err) => args.exit(&err.to_string()),
+            },
+        };
+    }
+    if seats.is_empty() { seats = vec![None, "heuristic".parse().ok()] }
+    if !u8::try_from(seats.len()).is_ok_and(|p| PLAYERS.contains(&p)) {
+        args.exit(&format!("Canasta is played by {} to {} players", PLAYERS.start(), PLAYERS.end()))
+    }
+
+    let Some(mut game) = builder.players(seats.len() as u8).build() else { args.exit("Could not create the game") };
+    let humans = seats.iter().filter(|s| s.is_none()).count();
+    let agents = seats.iter().enumerate()
+        .map(|(seat, spec)| spec.as_ref().map(|s| s.build(game.seed().wrapping_add(seat as u64))))
+        .collect::<Vec<Option<Box<dyn Agent>>>>();
+    let mut driver = Driver::new(agents);
+    let events = game.subscribe(Viewer::Spectator);
+    println!("Canasta, seed {}. Type `help` for commands.", game.seed());
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut last_human = None;
+    loop {
+        let result = driver.run(&mut game);
+        show_events(&events);
+        let player = match result {
+            Ok(DriveResult::HumanTurn(player)) => player,
+            Ok(DriveResult::GameOver) => break,
+            Err(err) => args.exit(&format!("A bot made an illegal move: {err}")),
+        };
+        // hide the last players hand before passing to the next person
+        if humans > 1 && last_human != Some(player) {
+            print!("\nPass to player {player} and press Enter");
+            let _ = io::stdout().flush();
+            if lines.next().is_none() { return }
+        }
+        last_human = Some(player);
+        println!("{}", render::table(&game.player_view(player).expect("Current player is valid")));
+
+        loop {
+            print!("player {player}> ");
+            let _ = io::stdout().flush();
+            let Some(Ok(line)) = lines.next() else { return };
+            let command = match command::parse(&line) {
+                Ok(command) => command,
+                Err(err) => { println!("{err}"); continue }
+            };
+            match run(&mut game, player, command) {
+                Ok(true) => { show_events(&events); break }
+                Ok(false) => {}
+                Err(err) => println!("{err}"),
+            }
+        }
+    }
+
+    println!("\nGame over");
+    let scores = game.scores();
+    let best = scores.iter().max().copied().unwrap_or(0);
+    for (player, score) in scores.iter().enumerate() {
+        println!("Player {player}: {score}{}", if *score == best { " - winner" } else { "" });
+    }
+}
+
+/// Carries out a command for the player
+///
+/// Returns `Ok(true)` once the game has changed and the table should be shown again.
+fn run(game: &mut CanastaGame, player: u8, command: Command) -> Result<bool, String> {
+    let view = game.player_view(player).map_err(|e| e.to_string())?;
+    let hand = render::sorted_hand(&view.hand);
+    let cards = |args: &[String]| find_cards(&hand, args);
+    let action = match command {
+        Command::Draw => Action::Draw,
+        Command::Take(args) => Action::TakeDiscard(cards(&args)?),
+        Command::Meld(rank, args) => Action::Meld { cards: cards(&args)?, rank },
+        Command::Unmeld(rank) => {
+            let staged = game.get_staged(player, rank).map_err(|e| e.to_string())?;
+            Action::Unmeld(staged.iter().map(|c| c.id()).collect())
+        }
+        Command::Clear => Action::ClearMeld,
+        Command::Commit => Action::CommitMeld,
+        Command::Discard(arg) => Action::Discard(cards(&[arg])?[0]),
+        Command::Undo => Action::Undo,
+        Command::Redo => Action::Redo,
+        Command::Hint => {
+            match game.hint(player).map_err(|e| e.to_string())? {
+                Some(hint) => println!("Hint: {}", hint.explanation),
+                None => println!("There is nothing to do"),
+            }
+            return Ok(false)
+        }
+        Command::Help => { println!("{HELP}"); return Ok(false) }
+        Command::Quit => process::exit(0),
+    };
+    game.apply(player, action).map_err(|err| {
+        // the game refers to cards by ID, show the card instead
+        let name = |id: u8| view.hand.iter().find(|c| c.id() == id).map_or(id.to_string(), |c| c.to_string());
+        match err {
+            PlayerActionError::CannotMeldCard(id) => format!("{} cannot be melded", name(id)),
+            PlayerActionError::IncorrectRank(id) => format!("{} is not of the meld rank", name(id)),
+            err => err.to_string(),
+        }
+    })?;
+    Ok(true)
+}
+
+/// Finds the IDs of cards given by their number in the sorted hand or by notation
+fn find_cards(hand: &[&PlayCard], args: &[String]) -> Result<Vec<u8>, String> {
+    let mut ids = vec![];
+    for arg in args {
+        let card = match arg.parse::<usize>() {
+            Ok(position) => position.checked_sub(1).and_then(|p| hand.get(p)).ok_or(format!("You have no card {arg}"))?,
+            Err(_) => {
+                let face: PlayCard = arg.parse().map_err(|e: NotationError| e.to_string())?;
+                hand.iter()
+                    .find(|c| c.same_face(&face) && !ids.contains(&c.id()))
+                    .ok_or(format!("You have no {face}"))?
+            }
+        };
+        if ids.contains(&card.id()) { return Err(format!("{card} is given twice")) }
+        ids.push(card.id());
+    }
+    Ok(ids)
+}
+
+fn show_events(events: &Receiver<GameEvent>) {
+    for event in events.try_iter() {
+        if let Some(text) = render::event(&event) { println!("{text}") }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The first game dealt with two cards of one face to player 0, and one of the pair
+    fn hand_with_twins() -> (CanastaGame, PlayCard) {
+        (0..).find_map(|seed| {
+            let game = CanastaGame::builder().players(2).canastas(1).hand().seed(seed).build().unwrap();
+            let hand = game.get_hand(0).unwrap();
+            let twin = hand.iter().find(|c| hand.iter().any(|o| o.id() != c.id() && o.same_face(c)))?.clone();
+            Some((game, twin))
+        }).unwrap()
+    }
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn positions_count_from_one() {
+        let (game, _) = hand_with_twins();
+        let hand = render::sorted_hand(game.get_hand(0).unwrap());
+        assert_eq!(find_cards(&hand, &strings(&["1", "3"])), Ok(vec![hand[0].id(), hand[2].id()]));
+        let last = hand.len().to_string();
+        assert_eq!(find_cards(&hand, &[last]), Ok(vec![hand[hand.len() - 1].id()]));
+        assert!(find_cards(&hand, &strings(&["0"])).is_err());
+        assert!(find_cards(&hand, &[(hand.len() + 1).to_string()]).is_err());
+    }
+
+    #[test]
+    fn notation_finds_each_twin_once() {
+        let (game, twin) = hand_with_twins();
+        let hand = render::sorted_hand(game.get_hand(0).unwrap());
+        let face = twin.to_string();
+        let ids = find_cards(&hand, &[face.clone(), face.clone()]).unwrap();
+        assert_ne!(ids[0], ids[1]);
+        assert!(ids.iter().all(|id| hand.iter().any(|c| c.id() == *id && c.same_face(&twin))));
+        assert_eq!(find_cards(&hand, &[face.clone(), face.clone(), face.clone()]), Err(format!("You have no {face}")));
+        assert!(find_cards(&hand, &strings(&["ZZ"])).is_err());
+    }
+
+    #[test]
+    fn positions_and_notation_name_different_cards() {
+        let (game, twin) = hand_with_twins();
+        let hand = render::sorted_hand(game.get_hand(0).unwrap());
+        let position = (hand.iter().position(|c| c.same_face(&twin)).unwrap() + 1).to_string();
+        let face = twin.to_string();
+        // notation takes the first matching card not yet given, here the twin
+        let ids = find_cards(&hand, &[position.clone(), face.clone()]).unwrap();
+        assert_eq!(ids[0], hand[position.parse::<usize>().unwrap() - 1].id());
+        assert_ne!(ids[0], ids[1]);
+        // the other way round the position names the card notation already took
+        assert_eq!(find_cards(&hand, &[face.clone(), position.clone()]), Err(format!("{face} is given twice")));
+        assert_eq!(find_cards(&hand, &[position.clone(), position]), Err(format!("{face} is given twice")));
+    }
+}
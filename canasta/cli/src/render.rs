@@ -0,0 +1,124 @@
+use game_lib::{
+    card::{PlayCard, Rank},
+    events::GameEvent,
+    game::TurnPhase,
+    view::{PlayerView, MeldView},
+};
+
+/// Hand sorted by rank with wild cards last, the order cards are numbered in
+pub fn sorted_hand(hand: &[PlayCard]) -> Vec<&PlayCard> {
+    let mut cards: Vec<&PlayCard> = hand.iter().collect();
+    cards.sort_by_key(|c| (matches!(c.rank(), Rank::Two | Rank::Joker), *c.rank(), c.id()));
+    cards
+}
+
+fn cards(cards: &[PlayCard]) -> String {
+    cards.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+fn meld(meld: &MeldView) -> String {
+    let kind = match (meld.canasta, meld.natural) {
+        (true, true) => " natural canasta",
+        (true, false) => " mixed canasta",
+        _ => "",
+    };
+    format!("[{}{}]", cards(&meld.cards), kind)
+}
+
+/// Draws the table as seen by the player whose turn it is
+pub fn table(view: &PlayerView) -> String {
+    let table = &view.table;
+    let mut out = String::new();
+    let pile = match &table.discard_top {
+        Some(top) => format!("{} cards, {} on top{}", table.discard_size, top, if table.pile_frozen { ", frozen" } else { "" }),
+        None => "empty".to_string(),
+    };
+    out += &format!("\n=== Hand {} | Stock {} | Pile {} ===\n", table.hand_number + 1, table.stock_count, pile);
+    for seat in &table.seats {
+        let you = if seat.player == view.player { " (you)" } else { "" };
+        out += &format!("Player {}{}: {} points, {} cards in hand", seat.player, you, seat.score, seat.hand_size);
+        if !seat.red_threes.is_empty() { out += &format!(", red threes {}", cards(&seat.red_threes)) }
+        if seat.player != view.player && !seat.known_cards.is_empty() {
+            out += &format!(", known to hold {}", cards(&seat.known_cards));
+        }
+        out += "\n";
+        if !seat.melds.is_empty() {
+            out += &format!("  melds: {}\n", seat.melds.iter().map(meld).collect::<Vec<_>>().join(" "));
+        }
+    }
+    for (rank, staged) in &view.staged {
+        out += &format!("Staged {}: {}\n", rank, cards(staged));
+    }
+    out += "Your hand:";
+    for (i, card) in sorted_hand(&view.hand).iter().enumerate() {
+        out += &format!(" {}:{}", i + 1, card);
+    }
+    out += "\n";
+    out += match table.phase {
+        TurnPhase::Draw => "Draw a card or take the pile",
+        TurnPhase::Meld => "Meld, then discard to end your turn",
+        TurnPhase::TurnOver => "Your turn is over, play passes to the next player",
+        TurnPhase::GameOver => "The game is over",
+    };
+    out
+}
+
+/// Describes an event in a sentence, or `None` for events not worth showing
+pub fn event(event: &GameEvent) -> Option<String> {
+    let text = match event {
+        GameEvent::CardDrawn { player, card: Some(card) } => format!("Player {player} drew {}", card),
+        GameEvent::CardDrawn { player, card: None } => format!("Player {player} drew a card"),
+        GameEvent::RedThreeLaid { player, card } => format!("Player {player} laid the red three {}", card),
+        GameEvent::PileTaken { player, cards: pile } => format!("Player {player} took the pile: {}", cards(pile)),
+        GameEvent::MeldCommitted { player, cards: melded, .. } => format!("Player {player} melded {}", cards(melded)),
+        GameEvent::CanastaCompleted { player, rank, natural } => {
+            format!("Player {player} completed a {} canasta of {}", if *natural { "natural" } else { "mixed" }, rank.name())
+        }
+        GameEvent::CardDiscarded { player, card } => format!("Player {player} discarded {}", card),
+        GameEvent::PileFrozen => "The pile is frozen".to_string(),
+        GameEvent::HandEnded { hand, went_out, hand_scores, scores } => {
+            let out = match went_out {
+                Some(player) => format!("player {player} went out"),
+                None => "the stock ran out".to_string(),
+            };
+            format!("Hand {} is over, {out}. Hand scores {:?}, totals {:?}", hand + 1, hand_scores, scores)
+        }
+        GameEvent::ActionUndone { player } => format!("Player {player} undid an action"),
+        GameEvent::ActionRedone { player } => format!("Player {player} redid an action"),
+        GameEvent::TurnTimedOut { player } => format!("Player {player} ran out of time"),
+        GameEvent::TurnEnded { .. } => return None,
+    };
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use game_lib::game::CanastaGame;
+
+    use super::*;
+
+    #[test]
+    fn phase_is_described() {
+        let game = CanastaGame::builder().players(2).canastas(1).hand().seed(1).build().unwrap();
+        let mut view = game.player_view(game.get_current_player()).unwrap();
+        for (phase, text) in [
+            (TurnPhase::Draw, "Draw a card or take the pile"),
+            (TurnPhase::Meld, "Meld, then discard to end your turn"),
+            (TurnPhase::TurnOver, "Your turn is over, play passes to the next player"),
+            (TurnPhase::GameOver, "The game is over"),
+        ] {
+            view.table.phase = phase;
+            assert!(table(&view).ends_with(text), "{phase:?}");
+        }
+    }
+
+    #[test]
+    fn hand_is_sorted_with_wilds_last() {
+        let game = CanastaGame::builder().players(2).canastas(1).hand().seed(1).build().unwrap();
+        let hand = sorted_hand(game.get_hand(0).unwrap());
+        let wild = |c: &PlayCard| matches!(c.rank(), Rank::Two | Rank::Joker);
+        let first_wild = hand.iter().position(|c| wild(c)).unwrap_or(hand.len());
+        assert!(hand[first_wild..].iter().all(|c| wild(c)));
+        assert!(hand[..first_wild].windows(2).all(|w| w[0].rank() <= w[1].rank()));
+    }
+}
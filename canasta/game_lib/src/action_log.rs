@@ -0,0 +1,161 @@
+use crate::card::Rank;
+
+/// An action a player can take on their turn
+///
+/// Each variant mirrors one of the action methods on `CanastaGame` and can be
+/// applied with `CanastaGame::apply`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Action {
+    /// Draw a card from the deck, see `CanastaGame::draw`
+    Draw,
+    /// Take the discard pile using the listed hand cards, see `CanastaGame::take_discard`
+    TakeDiscard(Vec<u8>),
+    /// Stage cards from hand into a meld of the rank, see `CanastaGame::meld`
+    Meld { cards: Vec<u8>, rank: Rank },
+    /// Return staged cards to the hand, see `CanastaGame::unmeld`
+    Unmeld(Vec<u8>),
+    /// Return all staged cards to the hand, see `CanastaGame::clear_meld`
+    ClearMeld,
+    /// Commit all staged melds, see `CanastaGame::commit_meld`
+    CommitMeld,
+    /// Discard the card with the ID, see `CanastaGame::discard`
+    Discard(u8),
+    /// Undo the last action of the player, see `CanastaGame::undo`
+    Undo,
+    /// Redo the last undone action of the player, see `CanastaGame::redo`
+    Redo,
+}
+
+/// What happened as a result of an applied action
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ActionOutcome {
+    /// A card was drawn, `None` if the deck ran out while replacing red threes
+    Drew { card: Option<u8>, red_threes: Vec<u8> },
+    /// The discard pile was taken, lists every card that was in the pile
+    TookDiscard { cards: Vec<u8> },
+    /// Cards were staged
+    Melded,
+    /// Cards were returned from staging
+    Unmelded,
+    /// All staged cards were returned
+    ClearedMeld,
+    /// Staged melds were committed, lists the ranks of any completed canastas
+    Committed { canastas: Vec<Rank> },
+    /// A card was discarded
+    Discarded { card: u8 },
+    /// The last action of the player was undone
+    Undone,
+    /// The last undone action of the player was redone
+    Redone,
+}
+
+/// A single applied action in the log
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LogEntry {
+    pub player: u8,
+    pub action: Action,
+    pub outcome: ActionOutcome,
+    /// Played for the player when their time ran out, see `CanastaGame::time_out`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub timed_out: bool,
+}
+
+/// Append only record of every action applied to a game
+///
+/// Only actions that succeeded are recorded. Undo and redo are recorded as actions
+/// of their own rather than removing entries. Together with the seed of the game
+/// this is enough to rebuild any state of the game, see `Replayer`.
+#[derive(Clone, Debug, Default)]
+pub struct ActionLog {
+    entries: Vec<LogEntry>,
+}
+
+impl ActionLog {
+    pub(crate) fn new() -> Self {
+        Self { entries: vec![] }
+    }
+
+    pub(crate) fn push(&mut self, player: u8, action: Action, outcome: ActionOutcome) {
+        self.entries.push(LogEntry { player, action, outcome, timed_out: false })
+    }
+
+    /// Marks the last entry as played on timeout
+    pub(crate) fn mark_timed_out(&mut self) {
+        if let Some(entry) = self.entries.last_mut() { entry.timed_out = true }
+    }
+
+    /// All entries in the order they were applied
+    pub fn entries(&self) -> &[LogEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn last(&self) -> Option<&LogEntry> {
+        self.entries.last()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{game::CanastaGame, errors::player_action_error::PlayerActionError};
+
+    use super::*;
+
+    #[test]
+    fn only_applied_actions_are_logged() {
+        let mut game = CanastaGame::builder().players(2).canastas(1).hand().seed(5).build().unwrap();
+        let player = game.get_current_player();
+        let other = (player + 1) % 2;
+        assert!(game.log().is_empty());
+        assert_eq!(game.draw(other).err(), Some(PlayerActionError::NotPlayerTurn(player)));
+        assert!(game.log().is_empty());
+
+        let drawn = game.draw(player).unwrap().unwrap().id();
+        let card = game.get_hand(player).unwrap()[0].id();
+        game.discard(player, card).unwrap();
+        assert_eq!(game.log().len(), 2);
+        let entries = game.log().entries();
+        assert_eq!(entries[0].player, player);
+        assert_eq!(entries[0].action, Action::Draw);
+        assert!(matches!(&entries[0].outcome, ActionOutcome::Drew { card: Some(id), .. } if *id == drawn));
+        assert_eq!(entries[1].action, Action::Discard(card));
+        assert_eq!(entries[1].outcome, ActionOutcome::Discarded { card });
+        assert_eq!(game.log().last(), Some(&entries[1]));
+        assert!(entries.iter().all(|e| !e.timed_out));
+    }
+
+    #[test]
+    fn apply_returns_the_logged_outcome() {
+        let mut game = CanastaGame::builder().players(3).canastas(1).hand().seed(8).build().unwrap();
+        for _ in 0..6 {
+            let player = game.get_current_player();
+            let drew = game.apply(player, Action::Draw).unwrap();
+            assert_eq!(drew, game.log().last().unwrap().outcome);
+            let card = game.get_hand(player).unwrap()[0].id();
+            assert_eq!(game.apply(player, Action::Discard(card)), Ok(ActionOutcome::Discarded { card }));
+        }
+        assert_eq!(game.log().len(), 12);
+    }
+
+    #[test]
+    fn only_the_last_entry_is_marked_timed_out() {
+        let mut log = ActionLog::new();
+        log.mark_timed_out();
+        assert!(log.is_empty());
+        log.push(0, Action::Draw, ActionOutcome::Drew { card: Some(1), red_threes: vec![] });
+        log.push(0, Action::Discard(1), ActionOutcome::Discarded { card: 1 });
+        log.mark_timed_out();
+        assert!(!log.entries()[0].timed_out);
+        assert!(log.entries()[1].timed_out);
+    }
+}
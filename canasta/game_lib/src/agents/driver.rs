@@ -0,0 +1,74 @@
+use crate::{game::{CanastaGame, TurnPhase}, errors::player_action_error::PlayerActionError};
+
+use super::Agent;
+
+/// Why a driver stopped advancing a game
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DriveResult {
+    /// It is the turn of the human player in this seat
+    HumanTurn(u8),
+    /// The game has finished
+    GameOver,
+}
+
+/// Plays the turns of computer seats in a game
+/// # Overview
+/// Each seat is either an agent or `None` for a human. Running the driver asks
+/// agents for actions until a human is to act or the game ends.
+/// # Example
+/// ```
+/// use game_lib::{game::CanastaGame, agents::{Agent, Driver, DriveResult, RandomAgent}};
+/// let mut game = CanastaGame::builder().players(2).canastas(1).hand().seed(3).build().unwrap();
+/// // a game between two bots plays through to the end
+/// let seats: Vec<Option<Box<dyn Agent>>> = vec![
+///     Some(Box::new(RandomAgent::new(1))),
+///     Some(Box::new(RandomAgent::new(2))),
+/// ];
+/// let mut driver = Driver::new(seats);
+/// assert_eq!(driver.run(&mut game).unwrap(), DriveResult::GameOver);
+/// ```
+pub struct Driver {
+    seats: Vec<Option<Box<dyn Agent>>>,
+}
+
+impl Driver {
+    pub fn new(seats: Vec<Option<Box<dyn Agent>>>) -> Self {
+        Self { seats }
+    }
+
+    /// If the seat is played by a human
+    pub fn is_human(&self, player: u8) -> bool {
+        matches!(self.seats.get(player as usize), Some(None) | None)
+    }
+
+    /// Replace what plays a seat, `None` for a human
+    pub fn set_seat(&mut self, player: u8, agent: Option<Box<dyn Agent>>) {
+        self.seats[player as usize] = agent;
+    }
+
+    /// Ask the agent in the current seat for a single action and apply it
+    ///
+    /// Returns `Ok(false)` without acting if the seat is human or the game is over
+    pub fn step(&mut self, game: &mut CanastaGame) -> Result<bool, PlayerActionError> {
+        if game.get_phase() == TurnPhase::GameOver { return Ok(false) }
+        let player = game.get_current_player();
+        let Some(Some(agent)) = self.seats.get_mut(player as usize) else { return Ok(false) };
+        let view = game.player_view(player).expect("Current player is valid");
+        let legal = game.legal_actions();
+        let action = agent.choose(&view, &legal);
+        game.apply(player, action)?;
+        Ok(true)
+    }
+
+    /// Play agent turns until a human seat is to act or the game ends
+    /// # Returns
+    /// - `Ok(DriveResult)` - Why the driver stopped
+    /// - `Err(PlayerActionError)` - An agent chose an action that was not legal
+    pub fn run(&mut self, game: &mut CanastaGame) -> Result<DriveResult, PlayerActionError> {
+        while self.step(game)? {}
+        match game.get_phase() {
+            TurnPhase::GameOver => Ok(DriveResult::GameOver),
+            _ => Ok(DriveResult::HumanTurn(game.get_current_player())),
+        }
+    }
+}
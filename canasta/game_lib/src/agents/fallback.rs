@@ -0,0 +1,98 @@
+use std::cmp::Reverse;
+
+use crate::{action_log::Action, card::PlayCard, game::TurnPhase, view::PlayerView};
+
+use super::Agent;
+
+/// Agent that ends a turn as safely as it can, without melding
+/// # Overview
+/// Used to play for a player whose time has run out, see `CanastaGame::time_out`.
+/// - Returns any staged cards to the hand
+/// - Draws from the deck, never taking the discard pile
+/// - Discards the highest scoring card that is free of risk
+///
+/// A card is free of risk if no opponent could use it to take the pile: black threes,
+/// and natural cards of ranks no opponent has melded or is known to hold. Wild cards
+/// are only discarded when nothing else can be. With no card free of risk the lowest
+/// scoring card is given away instead.
+/// # Example
+/// ```
+/// use game_lib::{game::CanastaGame, action_log::Action, agents::{Agent, FallbackAgent}};
+/// let mut game = CanastaGame::builder().players(2).canastas(1).hand().seed(5).build().unwrap();
+/// let player = game.get_current_player();
+/// let mut agent = FallbackAgent;
+/// let view = game.player_view(player).unwrap();
+/// assert_eq!(agent.choose(&view, &game.legal_actions()), Action::Draw);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FallbackAgent;
+
+impl FallbackAgent {
+    /// The card to discard from the legal discards
+    fn discard(view: &PlayerView, legal: &[Action]) -> Option<Action> {
+        let cards = legal.iter().filter_map(|a| match a {
+            Action::Discard(id) => view.hand.iter().find(|c| c.id() == *id),
+            _ => None,
+        });
+        let safe = |card: &PlayCard| card.is_black_three() || (!card.is_wild() && !wanted(view, card));
+        let card = cards.clone().filter(|c| safe(c)).max_by_key(|c| (c.value(), c.id()))
+            .or_else(|| cards.min_by_key(|c| (c.is_wild(), c.value(), Reverse(c.id()))))?;
+        Some(Action::Discard(card.id()))
+    }
+}
+
+/// If an opponent has melded the rank of the card or is known to hold one
+fn wanted(view: &PlayerView, card: &PlayCard) -> bool {
+    view.opponents().any(|o| {
+        o.melds.iter().any(|m| m.rank == *card.rank()) || o.known_cards.iter().any(|c| c.rank() == card.rank())
+    })
+}
+
+impl Agent for FallbackAgent {
+    fn choose(&mut self, view: &PlayerView, legal: &[Action]) -> Action {
+        if !view.staged.is_empty() && legal.contains(&Action::ClearMeld) { return Action::ClearMeld }
+        if view.table.phase == TurnPhase::Draw && legal.contains(&Action::Draw) { return Action::Draw }
+        Self::discard(view, legal).unwrap_or_else(|| legal[0].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{game::CanastaGame, agents::RandomAgent};
+
+    use super::*;
+
+    #[test]
+    fn plays_safely_at_every_decision() {
+        for seed in 0..10 {
+            let mut game = CanastaGame::builder().players(2).canastas(1).hand().seed(seed).build().unwrap();
+            let mut random = RandomAgent::new(seed);
+            while game.get_phase() != TurnPhase::GameOver {
+                let player = game.get_current_player();
+                let view = game.player_view(player).unwrap();
+                let legal = game.legal_actions();
+                let action = FallbackAgent.choose(&view, &legal);
+                assert!(legal.contains(&action));
+                match view.table.phase {
+                    TurnPhase::Draw if view.staged.is_empty() => assert_eq!(action, Action::Draw),
+                    _ if !view.staged.is_empty() => assert_eq!(action, Action::ClearMeld),
+                    _ => assert!(matches!(action, Action::Discard(_))),
+                }
+                game.apply(player, random.choose(&view, &legal)).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_wild_cards() {
+        for seed in 0..20 {
+            let mut game = CanastaGame::builder().players(2).canastas(1).hand().seed(seed).build().unwrap();
+            let player = game.get_current_player();
+            game.draw(player).unwrap();
+            let view = game.player_view(player).unwrap();
+            let Action::Discard(id) = FallbackAgent.choose(&view, &game.legal_actions()) else { panic!("Expected a discard") };
+            let card = view.hand.iter().find(|c| c.id() == id).unwrap();
+            if view.hand.iter().any(|c| !c.is_wild()) { assert!(!card.is_wild()) }
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use crate::{action_log::Action, card::{PlayCard, Rank}, game::TurnPhase, view::PlayerView};
+
+use super::Agent;
+
+/// Weights used by `HeuristicAgent` to score actions
+///
+/// Positive weights make an action more attractive, penalties are subtracted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeuristicWeights {
+    /// Value of each card gained by taking the discard pile
+    pub pile_card: f64,
+    /// Value of each natural card put into a meld
+    pub meld_natural: f64,
+    /// Value of each card a meld grows towards a canasta
+    pub meld_length: f64,
+    /// Value of completing a canasta
+    pub canasta: f64,
+    /// Value of each point melded while the initial meld is still needed
+    pub initial_points: f64,
+    /// Penalty for each wild card used in a meld or to take the pile
+    pub wild_use: f64,
+    /// Penalty for discarding a wild card
+    pub hold_wild: f64,
+    /// Penalty for each other card of the same rank kept in hand when discarding
+    pub keep_pair: f64,
+    /// Penalty for discarding a rank an opponent has melded
+    pub opponent_meld: f64,
+    /// Penalty for each card of the rank an opponent is known to hold
+    pub opponent_known: f64,
+    /// Penalty for each point the discarded card is worth
+    pub discard_value: f64,
+    /// Value of each pile card when discarding a wild to freeze a large pile
+    pub freeze_pile: f64,
+    /// Size the pile must reach before freezing it is considered
+    pub freeze_threshold: usize,
+    /// Value of each pile card when blocking the next player with a black three
+    pub black_three_block: f64,
+    /// Pile size or opponent hand size at which discarding a black three is considered
+    pub threat_threshold: usize,
+}
+
+impl Default for HeuristicWeights {
+    fn default() -> Self {
+        Self {
+            pile_card: 1.0,
+            meld_natural: 2.0,
+            meld_length: 1.0,
+            canasta: 20.0,
+            initial_points: 0.1,
+            wild_use: 4.0,
+            hold_wild: 15.0,
+            keep_pair: 3.0,
+            opponent_meld: 6.0,
+            opponent_known: 4.0,
+            discard_value: 0.05,
+            freeze_pile: 1.0,
+            freeze_threshold: 10,
+            black_three_block: 0.5,
+            threat_threshold: 8,
+        }
+    }
+}
+
+/// Rule based agent that plays sensible classic Canasta
+/// # Overview
+/// - Takes the discard pile whenever it can, using as few wild cards as possible
+/// - Melds natural cards, building on its melds towards canastas
+/// - Holds wild cards unless they complete a canasta or make the initial meld
+/// - Avoids discarding ranks opponents have melded or are known to hold
+/// - Freezes a large pile with a wild and blocks with black threes when threatened
+///
+/// How much each of these matters is set by `HeuristicWeights`.
+pub struct HeuristicAgent {
+    weights: HeuristicWeights,
+    /// Set when a commit failed this turn so melding is not retried
+    gave_up: bool,
+}
+
+impl Default for HeuristicAgent {
+    fn default() -> Self {
+        Self::new(HeuristicWeights::default())
+    }
+}
+
+impl HeuristicAgent {
+    pub fn new(weights: HeuristicWeights) -> Self {
+        Self { weights, gave_up: false }
+    }
+
+    pub fn weights(&self) -> &HeuristicWeights {
+        &self.weights
+    }
+
+    /// Scores how good an action is for the viewing player, higher is better
+    ///
+    /// Actions that do not progress the game score zero.
+    pub fn score(&self, view: &PlayerView, action: &Action) -> f64 {
+        match action {
+            Action::TakeDiscard(cards) => self.score_take(view, cards),
+            Action::Meld { cards, rank } => self.score_meld(view, cards, *rank),
+            Action::Discard(card_id) => self.score_discard(view, *card_id),
+            _ => 0.0,
+        }
+    }
+
+    fn score_take(&self, view: &PlayerView, cards: &[u8]) -> f64 {
+        let w = &self.weights;
+        let wilds = cards_of(view, cards).filter(|c| c.is_wild()).count();
+        view.table.discard_size as f64 * w.pile_card - wilds as f64 * w.wild_use - cards.len() as f64 * 0.1
+    }
+
+    fn score_meld(&self, view: &PlayerView, cards: &[u8], rank: Rank) -> f64 {
+        let w = &self.weights;
+        let melded = view.seat().melds.iter().find(|m| m.rank == rank);
+        let staged = view.staged.iter().find(|(r, _)| *r == rank).map_or(0, |(_, c)| c.len());
+        let existing = melded.map_or(0, |m| m.cards.len()) + staged;
+        let new_len = existing + cards.len();
+        let cards: Vec<&PlayCard> = cards_of(view, cards).collect();
+        let wilds = cards.iter().filter(|c| c.is_wild()).count();
+        let naturals = cards.len() - wilds;
+        let completes = existing < 7 && new_len >= 7;
+        let mut score = naturals as f64 * w.meld_natural + new_len.min(7) as f64 * w.meld_length;
+        if completes { score += w.canasta }
+        // wilds are held unless they finish a canasta
+        if !completes { score -= wilds as f64 * w.wild_use }
+        if view.seat().melds.is_empty() {
+            let points: u32 = cards.iter().map(|c| c.value() as u32).sum();
+            score += points as f64 * w.initial_points;
+        }
+        score
+    }
+
+    fn score_discard(&self, view: &PlayerView, card_id: u8) -> f64 {
+        let w = &self.weights;
+        let Some(card) = view.hand.iter().find(|c| c.id() == card_id) else { return f64::MIN };
+        let pile = view.table.discard_size;
+        let mut score = -(card.value() as f64) * w.discard_value;
+        if card.is_wild() {
+            score -= w.hold_wild;
+            if !view.table.pile_frozen && pile >= w.freeze_threshold {
+                score += pile as f64 * w.freeze_pile;
+            }
+            return score
+        }
+        if card.is_black_three() {
+            let threatened = pile >= w.threat_threshold
+                || view.opponents().any(|o| o.hand_size <= w.threat_threshold / 2);
+            if threatened { score += pile as f64 * w.black_three_block }
+            return score
+        }
+        let rank = *card.rank();
+        let pairs = view.hand.iter().filter(|c| c.id() != card_id && *c.rank() == rank).count();
+        score -= pairs as f64 * w.keep_pair;
+        for opponent in view.opponents() {
+            if opponent.melds.iter().any(|m| m.rank == rank) { score -= w.opponent_meld }
+            let known = opponent.known_cards.iter().filter(|c| *c.rank() == rank).count();
+            score -= known as f64 * w.opponent_known;
+        }
+        score
+    }
+
+    /// Best action of the given kind with a score above zero
+    fn best<'a>(&self, view: &PlayerView, actions: impl Iterator<Item = &'a Action>) -> Option<(&'a Action, f64)> {
+        actions
+            .map(|a| (a, self.score(view, a)))
+            .filter(|(_, score)| *score > 0.0)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    /// Picks the next staging action, leaving at least two cards in hand unless able to go out
+    fn next_meld<'a>(&self, view: &PlayerView, legal: &'a [Action]) -> Option<&'a Action> {
+        let can_go_out = view.seat().melds.iter().filter(|m| m.canasta).count() >= view.table.canastas_to_go_out as usize;
+        let candidates = legal.iter().filter(|a| match a {
+            Action::Meld { cards, rank } => {
+                let already = view.staged.iter().any(|(r, _)| r == rank);
+                let threes = *rank == Rank::Three && !can_go_out;
+                !already && !threes && (can_go_out || view.hand.len() - cards.len() >= 2)
+            }
+            _ => false,
+        });
+        self.best(view, candidates).map(|(a, _)| a)
+    }
+}
+
+/// Cards in the viewers hand with the given IDs
+fn cards_of<'a>(view: &'a PlayerView, ids: &'a [u8]) -> impl Iterator<Item = &'a PlayCard> {
+    view.hand.iter().filter(|c| ids.contains(&c.id()))
+}
+
+impl Agent for HeuristicAgent {
+    fn choose(&mut self, view: &PlayerView, legal: &[Action]) -> Action {
+        if view.table.phase == TurnPhase::Draw {
+            self.gave_up = false;
+            if !view.staged.is_empty() { return Action::ClearMeld }
+            let takes = legal.iter().filter(|a| matches!(a, Action::TakeDiscard(_)));
+            if let Some((action, _)) = self.best(view, takes) { return action.clone() }
+            return Action::Draw
+        }
+        if !self.gave_up {
+            if let Some(action) = self.next_meld(view, legal) { return action.clone() }
+            if !view.staged.is_empty() {
+                if legal.contains(&Action::CommitMeld) { return Action::CommitMeld }
+                self.gave_up = true;
+                return Action::ClearMeld
+            }
+        }
+        if !view.staged.is_empty() { return Action::ClearMeld }
+        let discards = legal.iter().filter(|a| matches!(a, Action::Discard(_)));
+        discards
+            .map(|a| (a, self.score(view, a)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(a, _)| a.clone())
+            .unwrap_or_else(|| legal[0].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::CanastaGame;
+
+    use super::*;
+
+    /// Weights that never meld, so the agent goes straight to discarding
+    fn no_melding() -> HeuristicWeights {
+        HeuristicWeights { meld_natural: 0.0, meld_length: 0.0, canasta: 0.0, initial_points: 0.0, wild_use: 100.0, ..Default::default() }
+    }
+
+    /// The first game where the current player holds a wild card after drawing
+    fn drawn_with_wild() -> CanastaGame {
+        (0..).find_map(|seed| {
+            let mut game = CanastaGame::builder().players(2).canastas(1).hand().seed(seed).build().unwrap();
+            let player = game.get_current_player();
+            game.apply(player, Action::Draw).unwrap();
+            game.get_hand(player).unwrap().iter().any(|c| c.is_wild()).then_some(game)
+        }).unwrap()
+    }
+
+    #[test]
+    fn chosen_actions_are_legal() {
+        for seed in 0..6 {
+            let players = 2 + (seed % 3) as u8;
+            let mut game = CanastaGame::builder().players(players).canastas(1).hand().seed(seed).build().unwrap();
+            let mut agents: Vec<HeuristicAgent> = (0..players).map(|_| HeuristicAgent::default()).collect();
+            let mut turns = 0;
+            while game.get_phase() != TurnPhase::GameOver {
+                let player = game.get_current_player();
+                let legal = game.legal_actions();
+                let action = agents[player as usize].choose(&game.player_view(player).unwrap(), &legal);
+                assert!(legal.contains(&action), "{action:?} is not legal");
+                game.apply(player, action).unwrap();
+                turns += 1;
+                assert!(turns < 10_000, "the game never ended");
+            }
+        }
+    }
+
+    #[test]
+    fn wild_discard_follows_weights() {
+        let game = drawn_with_wild();
+        let player = game.get_current_player();
+        let view = game.player_view(player).unwrap();
+        let legal = game.legal_actions();
+        let discarded = |weights: HeuristicWeights| match HeuristicAgent::new(weights).choose(&view, &legal) {
+            Action::Discard(id) => view.hand.iter().find(|c| c.id() == id).unwrap().clone(),
+            other => panic!("expected a discard, got {other:?}"),
+        };
+        assert!(!discarded(no_melding()).is_wild());
+        assert!(discarded(HeuristicWeights { hold_wild: -1000.0, ..no_melding() }).is_wild());
+    }
+
+    #[test]
+    fn taking_the_pile_follows_weights() {
+        // a position where the pile can be taken
+        let (view, legal) = (0..).find_map(|seed| {
+            let game = CanastaGame::builder().players(2).canastas(1).hand().seed(seed).build().unwrap();
+            let legal = game.legal_actions();
+            legal.iter().any(|a| matches!(a, Action::TakeDiscard(_)))
+                .then(|| (game.player_view(game.get_current_player()).unwrap(), legal))
+        }).unwrap();
+        let keen = HeuristicWeights { pile_card: 100.0, ..Default::default() };
+        assert!(matches!(HeuristicAgent::new(keen).choose(&view, &legal), Action::TakeDiscard(_)));
+        let unwilling = HeuristicWeights { pile_card: -1.0, ..Default::default() };
+        assert_eq!(HeuristicAgent::new(unwilling).choose(&view, &legal), Action::Draw);
+    }
+}
@@ -0,0 +1,216 @@
+use rand::{SeedableRng, seq::SliceRandom};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{action_log::Action, game::{CanastaGame, TurnPhase}, view::PlayerView};
+
+use super::{Agent, HeuristicAgent};
+
+/// Settings for `IsmctsAgent`
+#[derive(Clone, Debug, PartialEq)]
+pub struct IsmctsConfig {
+    /// Number of determinized games searched per decision
+    pub iterations: u32,
+    /// Exploration constant used when selecting actions
+    pub exploration: f64,
+    /// Most actions played out after leaving the tree before the position is scored
+    pub rollout_depth: u32,
+    /// Score difference that maps to a reward of about 0.76
+    pub reward_scale: f64,
+    pub seed: u64,
+}
+
+impl Default for IsmctsConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 200,
+            exploration: 0.7,
+            rollout_depth: 60,
+            reward_scale: 500.0,
+            seed: 0,
+        }
+    }
+}
+
+/// A node in the search tree, reached by a player taking an action
+struct Node {
+    action: Option<Action>,
+    /// Player that took the action leading to this node
+    player: u8,
+    children: Vec<usize>,
+    visits: u32,
+    /// Times the node was available to be selected
+    available: u32,
+    reward: f64,
+}
+
+impl Node {
+    fn new(action: Option<Action>, player: u8) -> Self {
+        Self { action, player, children: vec![], visits: 0, available: 1, reward: 0.0 }
+    }
+}
+
+/// Information set Monte Carlo tree search agent
+/// # Overview
+/// Each iteration deals the cards the agent cannot see at random, consistent with its
+/// view of the game, and searches a single tree shared between all of these
+/// determinized games. Actions are only selected where they are legal in the current
+/// determinization.
+///
+/// Play outs after the tree are made by a `HeuristicAgent` until the hand ends or
+/// `rollout_depth` actions have been taken, then every players hand is scored. Rewards
+/// are the change in score from the cards dealt in the same determinization.
+/// # Example
+/// ```
+/// use game_lib::{game::CanastaGame, agents::{Agent, IsmctsAgent, IsmctsConfig}};
+/// let game = CanastaGame::builder().players(2).canastas(1).hand().seed(1).build().unwrap();
+/// let mut agent = IsmctsAgent::new(IsmctsConfig { iterations: 20, ..Default::default() });
+/// let player = game.get_current_player();
+/// let legal = game.legal_actions();
+/// let action = agent.choose(&game.player_view(player).unwrap(), &legal);
+/// assert!(legal.contains(&action));
+/// ```
+pub struct IsmctsAgent {
+    config: IsmctsConfig,
+    rng: ChaCha8Rng,
+}
+
+impl IsmctsAgent {
+    pub fn new(config: IsmctsConfig) -> Self {
+        let rng = ChaCha8Rng::seed_from_u64(config.seed);
+        Self { config, rng }
+    }
+
+    /// Runs one iteration of the search on a new determinization
+    fn iterate(&mut self, view: &PlayerView, legal: &[Action], tree: &mut Vec<Node>) {
+        let Ok(mut game) = CanastaGame::determinize(view, &mut self.rng) else { return };
+        // rewards measure the change from the hands dealt in this determinization
+        let start = values(&game);
+        let mut path = vec![0];
+        let mut node = 0;
+        // selection and expansion
+        while game.get_phase() != TurnPhase::GameOver {
+            let actions = if node == 0 { legal.to_vec() } else { game.legal_actions() };
+            if actions.is_empty() { break }
+            let player = game.get_current_player();
+            let mut untried = vec![];
+            for action in actions {
+                match tree[node].children.iter().find(|&&c| tree[c].action.as_ref() == Some(&action)) {
+                    Some(&child) => tree[child].available += 1,
+                    None => untried.push(action),
+                }
+            }
+            if let Some(action) = untried.choose(&mut self.rng).cloned() {
+                if game.apply(player, action.clone()).is_err() { break }
+                tree.push(Node::new(Some(action), player));
+                let child = tree.len() - 1;
+                tree[node].children.push(child);
+                path.push(child);
+                break
+            }
+            let exploration = self.config.exploration;
+            let best = tree[node].children.iter()
+                .copied()
+                .filter(|&c| tree[c].action.as_ref().is_some_and(|a| game.validate(player, a).is_ok()))
+                .max_by(|&a, &b| ucb(&tree[a], exploration).total_cmp(&ucb(&tree[b], exploration)));
+            let Some(best) = best else { break };
+            let action = tree[best].action.clone().unwrap();
+            if game.apply(player, action).is_err() { break }
+            path.push(best);
+            node = best;
+        }
+        // play out
+        let mut policy = HeuristicAgent::default();
+        for _ in 0..self.config.rollout_depth {
+            if game.get_phase() == TurnPhase::GameOver { break }
+            let player = game.get_current_player();
+            let legal = game.legal_actions();
+            if legal.is_empty() { break }
+            let action = policy.choose(&game.player_view(player).unwrap(), &legal);
+            if game.apply(player, action).is_err() { break }
+        }
+        let rewards = self.rewards(&game, &start);
+        for &index in &path {
+            let node = &mut tree[index];
+            node.visits += 1;
+            node.reward += rewards[node.player as usize];
+        }
+    }
+
+    /// Reward for each player, their gain over the best opponent since the search began
+    fn rewards(&self, game: &CanastaGame, start: &[i32]) -> Vec<f64> {
+        let scores = game.scores();
+        let ended = game.get_phase() == TurnPhase::GameOver;
+        let gains: Vec<f64> = (0..game.num_players()).map(|p| {
+            let hand = if ended { 0 } else { game.hand_value(p) };
+            (scores[p as usize] + hand - start[p as usize]) as f64
+        }).collect();
+        gains.iter().enumerate().map(|(p, gain)| {
+            let best_other = gains.iter().enumerate()
+                .filter(|(q, _)| *q != p)
+                .map(|(_, g)| *g)
+                .fold(f64::MIN, f64::max);
+            ((gain - best_other) / self.config.reward_scale).tanh()
+        }).collect()
+    }
+}
+
+/// Score of each player counting the cards left in their hand
+fn values(game: &CanastaGame) -> Vec<i32> {
+    (0..game.num_players()).map(|p| game.scores()[p as usize] + game.hand_value(p)).collect()
+}
+
+/// Upper confidence bound of a node, using how often it was available rather than
+/// how often its parent was visited
+fn ucb(node: &Node, exploration: f64) -> f64 {
+    if node.visits == 0 { return f64::INFINITY }
+    let visits = node.visits as f64;
+    node.reward / visits + exploration * ((node.available as f64).ln() / visits).sqrt()
+}
+
+impl Agent for IsmctsAgent {
+    fn choose(&mut self, view: &PlayerView, legal: &[Action]) -> Action {
+        if legal.len() == 1 { return legal[0].clone() }
+        let mut tree = vec![Node::new(None, view.player)];
+        for _ in 0..self.config.iterations {
+            self.iterate(view, legal, &mut tree);
+        }
+        tree[0].children.iter()
+            .max_by_key(|&&c| tree[c].visits)
+            .and_then(|&c| tree[c].action.clone())
+            .filter(|a| legal.contains(a))
+            .unwrap_or_else(|| legal[0].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent(iterations: u32) -> IsmctsAgent {
+        IsmctsAgent::new(IsmctsConfig { iterations, rollout_depth: 20, ..Default::default() })
+    }
+
+    #[test]
+    fn no_reward_without_change() {
+        let game = CanastaGame::builder().players(3).canastas(1).hand().seed(4).build().unwrap();
+        let view = game.player_view(game.get_current_player()).unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        // each determinization deals opponents different hands, but is its own baseline
+        for _ in 0..10 {
+            let sampled = CanastaGame::determinize(&view, &mut rng).unwrap();
+            assert_eq!(agent(1).rewards(&sampled, &values(&sampled)), vec![0.0; 3]);
+        }
+    }
+
+    #[test]
+    fn same_seed_same_choice() {
+        let mut game = CanastaGame::builder().players(2).canastas(1).hand().seed(8).build().unwrap();
+        let player = game.get_current_player();
+        game.draw(player).unwrap();
+        let view = game.player_view(player).unwrap();
+        let legal = game.legal_actions();
+        let action = agent(30).choose(&view, &legal);
+        assert!(legal.contains(&action));
+        assert_eq!(agent(30).choose(&view, &legal), action);
+    }
+}
@@ -0,0 +1,26 @@
+pub mod random;
+pub mod heuristic;
+pub mod driver;
+pub mod ismcts;
+pub mod spec;
+pub mod fallback;
+
+pub use random::RandomAgent;
+pub use heuristic::{HeuristicAgent, HeuristicWeights};
+pub use driver::{Driver, DriveResult};
+pub use ismcts::{IsmctsAgent, IsmctsConfig};
+pub use spec::AgentSpec;
+pub use fallback::FallbackAgent;
+
+use crate::{action_log::Action, view::PlayerView};
+
+/// A computer player
+///
+/// Agents only see what their seat is allowed to know through a `PlayerView`,
+/// along with every legal action they can take.
+pub trait Agent: Send {
+    /// Choose the next action to take
+    ///
+    /// `legal` is never empty and the chosen action should be one of its entries.
+    fn choose(&mut self, view: &PlayerView, legal: &[Action]) -> Action;
+}
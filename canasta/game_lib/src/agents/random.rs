@@ -0,0 +1,25 @@
+use rand::{SeedableRng, seq::SliceRandom};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{action_log::Action, view::PlayerView};
+
+use super::Agent;
+
+/// Agent that picks a legal action at random
+///
+/// Useful as a baseline to measure other agents against.
+pub struct RandomAgent {
+    rng: ChaCha8Rng,
+}
+
+impl RandomAgent {
+    pub fn new(seed: u64) -> Self {
+        Self { rng: ChaCha8Rng::seed_from_u64(seed) }
+    }
+}
+
+impl Agent for RandomAgent {
+    fn choose(&mut self, _view: &PlayerView, legal: &[Action]) -> Action {
+        legal.choose(&mut self.rng).expect("Agent given no legal actions").clone()
+    }
+}
@@ -0,0 +1,120 @@
+use std::{fmt, str::FromStr};
+
+use crate::errors::agent_error::AgentError;
+
+use super::{Agent, RandomAgent, HeuristicAgent, HeuristicWeights, IsmctsAgent, IsmctsConfig};
+
+/// Settings describing an agent, used to create new agents for each game
+/// # Overview
+/// Agents keep state between decisions so cannot be shared between games or threads,
+/// a spec can be used to create as many as are needed.
+///
+/// Specs can be parsed from text, as used by the command line tools:
+/// - `random`
+/// - `heuristic`
+/// - `ismcts` or `ismcts:<iterations>`
+///
+/// Specs are written the same way. Settings that cannot be given as text and differ
+/// from their defaults are written as a fingerprint after `@`, such as
+/// `heuristic@1f0c93a7`, so agents with different settings never share a name. These
+/// cannot be parsed back.
+/// # Example
+/// ```
+/// use game_lib::agents::AgentSpec;
+/// let spec: AgentSpec = "ismcts:50".parse().unwrap();
+/// assert_eq!(spec.to_string(), "ismcts:50");
+/// let agent = spec.build(7);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum AgentSpec {
+    Random,
+    Heuristic(HeuristicWeights),
+    Ismcts(IsmctsConfig),
+}
+
+impl AgentSpec {
+    /// Create a new agent, `seed` is used for any randomness in its decisions
+    pub fn build(&self, seed: u64) -> Box<dyn Agent> {
+        match self {
+            AgentSpec::Random => Box::new(RandomAgent::new(seed)),
+            AgentSpec::Heuristic(weights) => Box::new(HeuristicAgent::new(weights.clone())),
+            AgentSpec::Ismcts(config) => Box::new(IsmctsAgent::new(IsmctsConfig { seed, ..config.clone() })),
+        }
+    }
+}
+
+impl fmt::Display for AgentSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AgentSpec::Random => write!(f, "random"),
+            AgentSpec::Heuristic(weights) => {
+                write!(f, "heuristic")?;
+                if *weights != HeuristicWeights::default() { write!(f, "@{}", fingerprint(weights))? }
+                Ok(())
+            }
+            AgentSpec::Ismcts(config) => {
+                write!(f, "ismcts:{}", config.iterations)?;
+                // the seed is replaced when building and the iterations are written
+                let rest = IsmctsConfig { iterations: 0, seed: 0, ..config.clone() };
+                if rest != (IsmctsConfig { iterations: 0, ..Default::default() }) { write!(f, "@{}", fingerprint(&rest))? }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Short hash of settings, the same on every platform and run
+fn fingerprint(settings: &impl fmt::Debug) -> String {
+    // 64 bit FNV-1a
+    let hash = format!("{settings:?}").bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100_0000_01b3));
+    format!("{:08x}", hash >> 32)
+}
+
+impl FromStr for AgentSpec {
+    type Err = AgentError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, setting) = match s.split_once(':') {
+            Some((name, setting)) => (name, Some(setting)),
+            None => (s, None),
+        };
+        match (name.to_lowercase().as_str(), setting) {
+            ("random", None) => Ok(AgentSpec::Random),
+            ("heuristic", None) => Ok(AgentSpec::Heuristic(HeuristicWeights::default())),
+            ("ismcts", None) => Ok(AgentSpec::Ismcts(IsmctsConfig::default())),
+            ("ismcts", Some(iterations)) => {
+                let iterations = iterations.parse().map_err(|_| AgentError::InvalidSetting(iterations.to_string()))?;
+                Ok(AgentSpec::Ismcts(IsmctsConfig { iterations, ..Default::default() }))
+            }
+            ("random" | "heuristic", Some(setting)) => Err(AgentError::InvalidSetting(setting.to_string())),
+            _ => Err(AgentError::UnknownAgent(name.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_specs_round_trip() {
+        for text in ["random", "heuristic", "ismcts:200", "ismcts:50"] {
+            assert_eq!(text.parse::<AgentSpec>().unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn settings_change_the_name() {
+        let heuristic = |canasta| AgentSpec::Heuristic(HeuristicWeights { canasta, ..Default::default() });
+        let names = [heuristic(100.0).to_string(), heuristic(200.0).to_string()];
+        assert!(names[0].starts_with("heuristic@") && names[1].starts_with("heuristic@"));
+        assert_ne!(names[0], names[1]);
+        assert_eq!(heuristic(100.0).to_string(), names[0]);
+
+        let ismcts = |exploration, seed| AgentSpec::Ismcts(IsmctsConfig { exploration, seed, ..Default::default() });
+        assert_eq!(ismcts(0.7, 9).to_string(), "ismcts:200");
+        assert_ne!(ismcts(1.5, 0).to_string(), "ismcts:200");
+        assert_eq!(ismcts(1.5, 0).to_string(), ismcts(1.5, 9).to_string());
+    }
+}
@@ -0,0 +1,42 @@
+//! Plays games between bots and prints how well each did
+//!
+//! Usage: `simulate [--games N] [--seed N] [--threads N] [--canastas N] [--full] <agent> <agent> [agent] [agent]`
+//!
+//! Agents are `random`, `heuristic`, `ismcts` or `ismcts:<iterations>`.
+
+use args::Args;
+use game_lib::{simulation::Simulation, agents::AgentSpec};
+
+const USAGE: &str = "Usage: simulate [--games N] [--seed N] [--threads N] [--canastas N] [--full] <agent> <agent> [agent] [agent]";
+
+fn main() {
+    let mut args = Args::new(USAGE);
+    let mut agents = vec![];
+    let mut games = 100;
+    let mut seed = 0;
+    let mut threads = None;
+    let mut canastas = 1;
+    let mut full_game = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => games = args.number(),
+            "--seed" => seed = args.number(),
+            "--threads" => threads = Some(args.number()),
+            "--canastas" => canastas = args.number(),
+            "--full" => full_game = true,
+            "-h" | "--help" => args.exit(""),
+            agent => match agent.parse::<AgentSpec>() {
+                Ok(spec) => agents.push(spec),
+                Err(err) => args.exit(&err.to_string()),
+            },
+        }
+    }
+
+    let mut simulation = Simulation::new(agents).games(games).seed(seed).canastas(canastas);
+    if let Some(threads) = threads { simulation = simulation.threads(threads) }
+    if full_game { simulation = simulation.full_game() }
+    match simulation.run() {
+        Ok(report) => print!("{report}"),
+        Err(err) => args.exit(&err.to_string()),
+    }
+}
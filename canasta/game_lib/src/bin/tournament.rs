@@ -0,0 +1,46 @@
+//! Plays a round robin duplicate tournament between bots and prints the standings
+//!
+//! Usage: `tournament [--deals N] [--seed N] [--threads N] [--canastas N] [--full] [--ratings FILE] <agent> <agent> [agent...]`
+//!
+//! Agents are `random`, `heuristic`, `ismcts` or `ismcts:<iterations>`. Ratings are
+//! read from and written back to the ratings file if one is given.
+
+use args::Args;
+use game_lib::{tournament::Tournament, agents::AgentSpec};
+
+const USAGE: &str = "Usage: tournament [--deals N] [--seed N] [--threads N] [--canastas N] [--full] [--ratings FILE] <agent> <agent> [agent...]";
+
+fn main() {
+    let mut args = Args::new(USAGE);
+    let mut agents = vec![];
+    let mut deals = 50;
+    let mut seed = 0;
+    let mut threads = None;
+    let mut canastas = 1;
+    let mut full_game = false;
+    let mut ratings = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--deals" => deals = args.number(),
+            "--seed" => seed = args.number(),
+            "--threads" => threads = Some(args.number()),
+            "--canastas" => canastas = args.number(),
+            "--full" => full_game = true,
+            "--ratings" => ratings = Some(args.value("a file")),
+            "-h" | "--help" => args.exit(""),
+            agent => match agent.parse::<AgentSpec>() {
+                Ok(spec) => agents.push(spec),
+                Err(err) => args.exit(&err.to_string()),
+            },
+        }
+    }
+
+    let mut tournament = Tournament::new(agents).deals(deals).seed(seed).canastas(canastas);
+    if let Some(threads) = threads { tournament = tournament.threads(threads) }
+    if full_game { tournament = tournament.full_game() }
+    if let Some(ratings) = ratings { tournament = tournament.ratings_file(ratings) }
+    match tournament.run() {
+        Ok(report) => print!("{report}"),
+        Err(err) => args.exit(&err.to_string()),
+    }
+}
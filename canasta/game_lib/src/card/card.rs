@@ -1,6 +1,15 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::errors::notation_error::NotationError;
+
 use super::{suit::Suit, rank::Rank};
 
+/// Cards of each suit in a full deck, two of each rank then a joker
+const SUIT_CARDS: u8 = 27;
+/// Cards in a full double deck with jokers
+pub(crate) const DECK_SIZE: u8 = SUIT_CARDS * 4;
 
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct PlayCard {
     id: u8,
     suit: Suit,
@@ -14,6 +23,28 @@ impl PlayCard {
         Self { id, suit, rank, value }
     }
 
+    /// The card with an ID, IDs are the position of the card in a full deck
+    /// # Returns
+    /// - `Some(PlayCard)` - The card
+    /// - `None` - The ID is not below `DECK_SIZE`
+    pub(crate) fn from_id(id: u8) -> Option<Self> {
+        let suit = Suit::try_from(id / SUIT_CARDS).ok()?;
+        let rank = match id % SUIT_CARDS {
+            n if n == SUIT_CARDS - 1 => Rank::Joker,
+            n => Rank::from(n % 13 + 1),
+        };
+        Some(Self::new(id, suit, rank))
+    }
+
+    /// The lower ID of the two cards with the suit and rank
+    fn first_id(suit: Suit, rank: Rank) -> u8 {
+        let offset = match rank {
+            Rank::Joker => SUIT_CARDS - 1,
+            rank => u8::from(&rank) - 1,
+        };
+        suit as u8 * SUIT_CARDS + offset
+    }
+
     pub(crate) fn is_red_three(&self) -> bool {
         (self.suit == Suit::Diamonds || self.suit == Suit::Hearts) && self.rank == Rank::Three
     }
@@ -37,20 +68,141 @@ impl PlayCard {
     pub fn rank(&self) -> &Rank {
         &self.rank  
     }
+
+    /// Points the card is worth when melded or left in hand
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    /// If the cards are written the same in card notation
+    ///
+    /// Cards from the two decks match, as do all jokers.
+    pub fn same_face(&self, other: &PlayCard) -> bool {
+        self.rank == other.rank && (self.rank == Rank::Joker || self.suit == other.suit)
+    }
+}
+
+/// Writes the card in notation such as `KH`, `10D` or `JK`
+///
+/// The alternate flag `{:#}` writes unicode suits such as `K♥`.
+impl Display for PlayCard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self.rank {
+            Rank::Joker => self.rank.to_string(),
+            rank if f.alternate() => format!("{rank}{:#}", self.suit),
+            rank => format!("{rank}{}", self.suit),
+        };
+        f.pad(&text)
+    }
+}
+
+/// Reads card notation such as `KH`, `10d`, `K♥` or `JK`
+///
+/// There are two of every card, the one with the lowest ID is given. Use
+/// `notation::resolve` to find a card in a hand.
+/// # Example
+/// ```
+/// use game_lib::card::{PlayCard, Rank, Suit};
+/// let card: PlayCard = "10♦".parse().unwrap();
+/// assert_eq!((*card.rank(), *card.suit()), (Rank::Ten, Suit::Diamonds));
+/// assert_eq!(card.to_string(), "10D");
+/// assert_eq!(format!("{card:#}"), "10♦");
+/// assert_eq!("jk".parse::<PlayCard>().unwrap().to_string(), "JK");
+/// ```
+impl FromStr for PlayCard {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim();
+        let invalid = || NotationError::InvalidCard(s.to_string());
+        let card = |suit: Suit, rank: Rank| PlayCard::from_id(PlayCard::first_id(suit, rank)).ok_or_else(invalid);
+        if text.parse::<Rank>() == Ok(Rank::Joker) { return card(Suit::Hearts, Rank::Joker) }
+        let split = text.char_indices().last().map(|(i, _)| i).ok_or_else(invalid)?;
+        let (rank, suit) = text.split_at(split);
+        let rank: Rank = rank.parse().map_err(|_| invalid())?;
+        let suit: Suit = suit.parse().map_err(|_| invalid())?;
+        card(suit, rank)
+    }
+}
+
+/// Cards are written as their ID, which decides the rest of the card
+#[cfg(feature = "serde")]
+impl serde::Serialize for PlayCard {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.id)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PlayCard {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = u8::deserialize(deserializer)?;
+        PlayCard::from_id(id).ok_or_else(|| serde::de::Error::custom(format!("{id} is not a card ID")))
+    }
 }
 
 fn calculate_card_value(suit: &Suit, rank: &Rank) -> u8 {
     let num: u8 = rank.into(); 
-    if num <= 2 { return 20 }
+    if num <= 2 { 20 }
     else if num == 3 {
         match suit {
-            Suit::Spades | Suit::Clubs => return 5,
-            Suit::Hearts | Suit::Diamonds => return 100,
+            Suit::Spades | Suit::Clubs => 5,
+            Suit::Hearts | Suit::Diamonds => 100,
         }
     }
-    else if num <= 7 { return 5 }
-    else if num <= 13 { return 10 }
-    else { return 50 }
+    else if num <= 7 { 5 }
+    else if num <= 13 { 10 }
+    else { 50 }
 }
 
 
+
+#[cfg(test)]
+mod tests {
+    use crate::card_collections::deck::Deck;
+
+    use super::*;
+
+    #[test]
+    fn ids_map_to_the_full_deck() {
+        let deck = Deck::full();
+        assert_eq!(deck.len(), DECK_SIZE as usize);
+        for (id, card) in deck.iter().enumerate() {
+            assert_eq!(card.id() as usize, id);
+        }
+        assert_eq!(deck.iter().filter(|c| c.rank == Rank::Joker).count(), 4);
+        assert_eq!(deck.iter().filter(|c| c.rank == Rank::King && c.suit == Suit::Hearts).count(), 2);
+        assert!(PlayCard::from_id(DECK_SIZE).is_none());
+        assert!(PlayCard::from_id(u8::MAX).is_none());
+    }
+
+    #[test]
+    fn notation_round_trips() {
+        for card in Deck::full() {
+            let parsed: PlayCard = card.to_string().parse().unwrap();
+            assert!(parsed.same_face(&card), "{card}");
+            assert!(parsed.id() <= card.id());
+            let parsed: PlayCard = format!("{card:#}").parse().unwrap();
+            assert!(parsed.same_face(&card), "{card:#}");
+        }
+    }
+
+    #[test]
+    fn tens_and_jokers_parse() {
+        for text in ["10H", "TH", "th", " 10♥ "] {
+            let card: PlayCard = text.parse().unwrap();
+            assert_eq!((card.rank, card.suit), (Rank::Ten, Suit::Hearts), "{text}");
+        }
+        for text in ["JK", "jk", "JKS", "Joker"] {
+            assert_eq!(text.parse::<PlayCard>().unwrap().rank, Rank::Joker, "{text}");
+        }
+        assert_eq!("JKS".parse::<PlayCard>().unwrap().suit, Suit::Spades);
+    }
+
+    #[test]
+    fn bad_cards_are_refused() {
+        for text in ["", "K", "1H", "KX", "11H", "KHH", "H"] {
+            assert_eq!(text.parse::<PlayCard>(), Err(NotationError::InvalidCard(text.to_string())), "{text}");
+        }
+    }
+}
@@ -1,6 +1,8 @@
+#[allow(clippy::module_inception)]
 pub(crate) mod card;
 pub(crate) mod suit;
 pub(crate) mod rank;
+pub mod notation;
 
 pub use suit::Suit;
 pub use rank::Rank;
@@ -0,0 +1,74 @@
+use crate::errors::notation_error::NotationError;
+
+use super::PlayCard;
+
+/// Finds the ID of a card in a hand from its notation
+/// # Overview
+/// There are two of each card, and four jokers, so notation such as `KH` can match
+/// more than one card. The first matching card in the hand is given.
+/// # Returns
+/// - `Ok(u8)` - ID of a matching card
+/// - `Err(NotationError::InvalidCard)` - The notation is not a card
+/// - `Err(NotationError::NotInHand)` - No card in the hand matches
+/// # Example
+/// ```
+/// use game_lib::{game::CanastaGame, card::notation::resolve};
+/// let game = CanastaGame::builder().players(2).canastas(1).hand().seed(1).build().unwrap();
+/// let hand = game.get_hand(0).unwrap();
+/// let notation = hand[0].to_string();
+/// let id = resolve(hand, &notation).unwrap();
+/// assert_eq!(hand.iter().find(|c| c.id() == id).unwrap().to_string(), notation);
+/// ```
+pub fn resolve(hand: &[PlayCard], notation: &str) -> Result<u8, NotationError> {
+    resolve_all(hand, [notation]).map(|ids| ids[0])
+}
+
+/// Finds the IDs of several cards in a hand from their notation
+///
+/// Each card in the hand is used at most once, so `KH KH` gives both Kings of Hearts.
+/// Notation may be separate items or separated by whitespace or commas.
+pub fn resolve_all<'a>(hand: &[PlayCard], notation: impl IntoIterator<Item = &'a str>) -> Result<Vec<u8>, NotationError> {
+    let mut ids = vec![];
+    let items = notation.into_iter()
+        .flat_map(|n| n.split(|c: char| c.is_whitespace() || c == ','))
+        .filter(|n| !n.is_empty());
+    for item in items {
+        let card: PlayCard = item.parse()?;
+        let found = hand.iter()
+            .find(|c| c.same_face(&card) && !ids.contains(&c.id()))
+            .ok_or_else(|| NotationError::NotInHand(item.to_string()))?;
+        ids.push(found.id());
+    }
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hand() -> Vec<PlayCard> {
+        // both Kings of Hearts, a Ten of Diamonds and two jokers
+        [12, 25, 36, 26, 107].into_iter().map(|id| PlayCard::from_id(id).unwrap()).collect()
+    }
+
+    #[test]
+    fn resolve_finds_cards() {
+        let hand = hand();
+        assert_eq!(resolve(&hand, "KH"), Ok(12));
+        assert_eq!(resolve(&hand, "td"), Ok(36));
+        assert_eq!(resolve(&hand, "10♦"), Ok(36));
+        // any joker matches, whatever its suit
+        assert_eq!(resolve(&hand, "JKS"), Ok(26));
+        assert_eq!(resolve(&hand, "QH"), Err(NotationError::NotInHand("QH".to_string())));
+        assert_eq!(resolve(&hand, "ZZ"), Err(NotationError::InvalidCard("ZZ".to_string())));
+    }
+
+    #[test]
+    fn resolve_all_uses_each_card_once() {
+        let hand = hand();
+        assert_eq!(resolve_all(&hand, ["KH KH"]), Ok(vec![12, 25]));
+        assert_eq!(resolve_all(&hand, ["KH,JK", "JK"]), Ok(vec![12, 26, 107]));
+        assert_eq!(resolve_all(&hand, ["KH", "KH", "KH"]), Err(NotationError::NotInHand("KH".to_string())));
+        assert_eq!(resolve_all(&hand, [" , "]), Ok(vec![]));
+    }
+}
@@ -1,6 +1,9 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+use crate::errors::notation_error::NotationError;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Rank {
     Ace,
     Two,
@@ -68,7 +71,7 @@ impl From<u8> for Rank {
 
 impl PartialOrd for Rank {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(Into::<u8>::into(self).cmp(&Into::<u8>::into(other))) 
+        Some(self.cmp(other))
     }
 }
 
@@ -78,9 +81,10 @@ impl Ord for Rank {
     }
 }
 
-impl Display for Rank {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", match self {
+impl Rank {
+    /// Full name of the rank, such as `King`
+    pub fn name(&self) -> &'static str {
+        match self {
             Rank::Ace => "Ace",
             Rank::Two => "Two",
             Rank::Three => "Three",
@@ -95,6 +99,77 @@ impl Display for Rank {
             Rank::Queen => "Queen",
             Rank::King => "King",
             Rank::Joker => "Joker",
-        })
+        }
+    }
+
+    /// Rank as written in card notation, such as `K`, `10` or `JK`
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Rank::Ace => "A",
+            Rank::Two => "2",
+            Rank::Three => "3",
+            Rank::Four => "4",
+            Rank::Five => "5",
+            Rank::Six => "6",
+            Rank::Seven => "7",
+            Rank::Eight => "8",
+            Rank::Nine => "9",
+            Rank::Ten => "10",
+            Rank::Jack => "J",
+            Rank::Queen => "Q",
+            Rank::King => "K",
+            Rank::Joker => "JK",
+        }
+    }
+}
+
+/// Writes the rank in card notation, see `Rank::symbol`
+impl Display for Rank {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(self.symbol())
+    }
+}
+
+/// Reads a rank from its symbol or name in any case, `T` is also accepted for Ten
+impl FromStr for Rank {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim().to_uppercase();
+        (1..=14u8)
+            .map(Rank::from)
+            .find(|r| r.symbol() == text || r.name().to_uppercase() == text)
+            .or(if text == "T" { Some(Rank::Ten) } else { None })
+            .ok_or_else(|| NotationError::InvalidRank(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranks_round_trip() {
+        for rank in (1..=14u8).map(Rank::from) {
+            assert_eq!(rank.symbol().parse::<Rank>(), Ok(rank));
+            assert_eq!(rank.name().parse::<Rank>(), Ok(rank));
+            assert_eq!(rank.name().to_lowercase().parse::<Rank>(), Ok(rank));
+            assert_eq!(Rank::from(u8::from(&rank)), rank);
+        }
+    }
+
+    #[test]
+    fn ten_is_read_both_ways() {
+        assert_eq!("10".parse::<Rank>(), Ok(Rank::Ten));
+        assert_eq!("T".parse::<Rank>(), Ok(Rank::Ten));
+        assert_eq!("t".parse::<Rank>(), Ok(Rank::Ten));
+        assert_eq!("jk".parse::<Rank>(), Ok(Rank::Joker));
+    }
+
+    #[test]
+    fn bad_ranks_are_refused() {
+        for text in ["", "1", "0", "11", "X", "Kings"] {
+            assert_eq!(text.parse::<Rank>(), Err(NotationError::InvalidRank(text.to_string())), "{text}");
+        }
     }
 }
@@ -1,6 +1,9 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+use crate::errors::notation_error::NotationError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Suit {
     Hearts,
     Diamonds,
@@ -22,14 +25,89 @@ impl TryFrom<u8> for Suit {
     }
 }
 
-impl Display for Suit {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let suit = match self {
+impl Suit {
+    /// Full name of the suit, such as `Hearts`
+    pub fn name(&self) -> &'static str {
+        match self {
             Suit::Hearts => "Hearts",
             Suit::Diamonds => "Diamonds",
             Suit::Clubs => "Clubs",
             Suit::Spades => "Spades",
+        }
+    }
+
+    /// Letter for the suit in card notation
+    pub fn letter(&self) -> char {
+        match self {
+            Suit::Hearts => 'H',
+            Suit::Diamonds => 'D',
+            Suit::Clubs => 'C',
+            Suit::Spades => 'S',
+        }
+    }
+
+    /// Unicode symbol for the suit
+    pub fn symbol(&self) -> char {
+        match self {
+            Suit::Hearts => '♥',
+            Suit::Diamonds => '♦',
+            Suit::Clubs => '♣',
+            Suit::Spades => '♠',
+        }
+    }
+}
+
+/// Writes the suit letter, or the unicode symbol with the alternate flag `{:#}`
+impl Display for Suit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = if f.alternate() { self.symbol() } else { self.letter() };
+        f.pad(symbol.encode_utf8(&mut [0; 4]))
+    }
+}
+
+/// Reads a suit from its letter, unicode symbol or name in any case
+impl FromStr for Suit {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim().to_uppercase();
+        let outline = match text.as_str() {
+            "♡" => "♥",
+            "♢" => "♦",
+            "♧" => "♣",
+            "♤" => "♠",
+            text => text,
         };
-        write!(f, "{}", suit)
+        [Suit::Hearts, Suit::Diamonds, Suit::Clubs, Suit::Spades].into_iter()
+            .find(|suit| {
+                outline == suit.letter().to_string()
+                    || outline == suit.symbol().to_string()
+                    || outline == suit.name().to_uppercase()
+            })
+            .ok_or_else(|| NotationError::InvalidSuit(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suits_round_trip() {
+        for suit in (0..4u8).map(|n| Suit::try_from(n).unwrap()) {
+            assert_eq!(suit.to_string().parse::<Suit>(), Ok(suit));
+            assert_eq!(format!("{suit:#}").parse::<Suit>(), Ok(suit));
+            assert_eq!(suit.name().to_lowercase().parse::<Suit>(), Ok(suit));
+        }
+        assert_eq!("♡".parse::<Suit>(), Ok(Suit::Hearts));
+        assert_eq!("s".parse::<Suit>(), Ok(Suit::Spades));
+        assert!(Suit::try_from(4).is_err());
+    }
+
+    #[test]
+    fn bad_suits_are_refused() {
+        for text in ["", "X", "HH", "Heart"] {
+            assert_eq!(text.parse::<Suit>(), Err(NotationError::InvalidSuit(text.to_string())), "{text}");
+        }
     }
 }
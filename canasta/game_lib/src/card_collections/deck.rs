@@ -1,33 +1,32 @@
-use rand::seq::SliceRandom;
+use rand::{seq::SliceRandom, Rng};
 
-use crate::card::{PlayCard, Rank, Suit};
+use crate::card::{PlayCard, card::DECK_SIZE};
 
+#[derive(Clone)]
 pub(crate) struct Deck {
     cards: Vec<PlayCard>,
 }
 
 impl Deck {
-    pub(crate) fn new() -> Self {
-        let mut cards = Vec::new();
-        for suit_num in 0..4 {
-            for _ in 0..2 {
-                for rank_num in 1..14 {
-                    let id = cards.len() as u8;
-                    let card = PlayCard::new(id, suit_num.try_into().unwrap(), rank_num.into());
-                    cards.push(card);
-                }
-            }
-            let card = PlayCard::new(cards.len() as u8, suit_num.try_into().unwrap(), Rank::Joker);
-            cards.push(card);
-        }
-        let mut cards = Self { cards }; 
-        cards.shuffle();
+    /// Creates a full double deck with jokers, shuffled with the given rng
+    pub(crate) fn new<R: Rng>(rng: &mut R) -> Self {
+        let mut cards = Self { cards: Self::full() };
+        cards.shuffle(rng);
         cards
     }
 
-    fn shuffle(&mut self) {
-        let mut rng = rand::thread_rng();
-        self.cards.shuffle(&mut rng);
+    /// Creates a deck holding the given cards, the last card is drawn first
+    pub(crate) fn from_cards(cards: Vec<PlayCard>) -> Self {
+        Self { cards }
+    }
+
+    /// Every card in a double deck with jokers, in ID order
+    pub(crate) fn full() -> Vec<PlayCard> {
+        (0..DECK_SIZE).filter_map(PlayCard::from_id).collect()
+    }
+
+    fn shuffle<R: Rng>(&mut self, rng: &mut R) {
+        self.cards.shuffle(rng);
     }
 
     pub(crate) fn draw(&mut self) -> Option<PlayCard> {
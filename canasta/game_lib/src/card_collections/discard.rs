@@ -1,6 +1,7 @@
 use crate::card::PlayCard;
 
 
+#[derive(Clone)]
 pub(crate) struct Discard {
     cards: Vec<PlayCard>,
     frozen: bool,
@@ -11,21 +12,36 @@ impl Discard {
         Self { cards: vec![], frozen: false }
     }
 
+    /// Creates a pile holding the given cards, the last card is on top
+    pub(crate) fn from_cards(cards: Vec<PlayCard>, frozen: bool) -> Self {
+        Self { cards, frozen }
+    }
+
     pub(crate) fn top(&self) -> Option<&PlayCard> {
         self.cards.last() 
     }
 
     pub(crate) fn throw(&mut self, card: PlayCard) -> &PlayCard {
-        if card.is_wild() { self.frozen = true }
+        if card.is_wild() || card.is_red_three() { self.frozen = true }
         self.cards.push(card);
         self.cards.last().unwrap()
     }
 
+    /// Takes the whole pile, unfreezing it
     pub(crate) fn take(&mut self) -> Vec<PlayCard> {
+        self.frozen = false;
         std::mem::take(&mut self.cards)   
     }
 
     pub(crate) fn is_frozen(&self) -> bool {
         self.frozen
     }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &PlayCard> {
+        self.cards.iter()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.cards.len()
+    }
 }
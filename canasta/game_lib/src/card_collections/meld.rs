@@ -1,5 +1,11 @@
-use crate::card::{PlayCard, Rank};
+use crate::{card::{PlayCard, Rank}, errors::internal_meld_error::InternalMeldError};
 
+/// Most wild cards any single meld may hold
+pub(crate) const MAX_WILDS: usize = 3;
+/// Number of cards needed for a meld to become a canasta
+pub(crate) const CANASTA_SIZE: usize = 7;
+
+#[derive(Clone)]
 pub(crate) struct Meld {
     rank: Rank,
     cards: Vec<PlayCard>,
@@ -11,6 +17,10 @@ impl Meld {
         Meld { rank, cards: vec![], wilds: vec![] }
     }
 
+    pub(crate) fn rank(&self) -> &Rank {
+        &self.rank
+    }
+
     pub(crate) fn len(&self) -> usize {
         self.cards.len() + self.wilds.len()
     }
@@ -27,25 +37,57 @@ impl Meld {
         self.wilds.is_empty()
     }
 
-    pub(crate) fn can_add(&self, cards: &Vec<PlayCard>) -> Result<(), ()> {
-        let mut wild_count = 0;
+    pub(crate) fn is_canasta(&self) -> bool {
+        self.len() >= CANASTA_SIZE
+    }
+
+    /// Iterates the natural cards followed by the wild cards of the meld
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &PlayCard> {
+        self.cards.iter().chain(self.wilds.iter())
+    }
+
+    /// Checks that the given cards could be added to this meld
+    ///
+    /// The cards must all be of the meld rank or wild and the resulting meld
+    /// must still be valid.
+    pub(crate) fn can_add(&self, cards: &[PlayCard]) -> Result<(), InternalMeldError> {
+        let mut naturals = self.normal_count();
+        let mut wilds = self.wild_count();
         for card in cards {
-            if *card.rank() != self.rank && !card.is_wild() {
-                return Err(());
-            }
-            if card.is_wild() { wild_count += 1; }
+            if card.is_wild() { wilds += 1; }
+            else if *card.rank() != self.rank { return Err(InternalMeldError::IncorrectRank(card.id())) }
+            else { naturals += 1; }
         }
-        if wild_count + self.wilds.len() >= self.cards.len() + cards.len() - wild_count {
-            // too many wilds 
-            return Err(())
-        }
-        todo!("Check this covers all logic");
-        Ok(())
+        check_composition(&self.rank, naturals, wilds)
+    }
+
+    /// Adds cards to the meld without checking them, see `can_add`
+    pub(crate) fn push(&mut self, card: PlayCard) {
+        if card.is_wild() { self.wilds.push(card) }
+        else { self.cards.push(card) }
     }
 
-    pub(crate) fn push(&self, card: PlayCard) -> Result<(), PlayCard> {
-        if *card.rank() != self.rank { return Err(card); }
+    /// Point value of the cards in the meld, excluding any canasta bonus
+    pub(crate) fn card_value(&self) -> i32 {
+        self.iter().map(|c| c.value() as i32).sum()
+    }
 
-        Ok(())
+    /// Bonus for the meld being a canasta
+    pub(crate) fn bonus(&self) -> i32 {
+        if !self.is_canasta() { 0 }
+        else if self.is_natural() { 500 }
+        else { 300 }
     }
 }
+
+/// Checks a meld of some rank with the given number of natural and wild cards is valid
+///
+/// A meld needs at least three cards, at least two naturals, more naturals than wilds
+/// and no more than three wilds. Black threes can never be melded with wilds.
+pub(crate) fn check_composition(rank: &Rank, naturals: usize, wilds: usize) -> Result<(), InternalMeldError> {
+    if naturals + wilds < 3 { return Err(InternalMeldError::TooFewCards(*rank)) }
+    if naturals < 2 { return Err(InternalMeldError::NotEnoughNaturals(*rank)) }
+    if *rank == Rank::Three && wilds > 0 { return Err(InternalMeldError::TooManyWilds(*rank)) }
+    if wilds > MAX_WILDS || wilds >= naturals { return Err(InternalMeldError::TooManyWilds(*rank)) }
+    Ok(())
+}
@@ -0,0 +1,227 @@
+use std::time::{Duration, Instant};
+
+/// How long players may take to play
+/// # Overview
+/// - Turn limit - time each turn may take
+/// - Game limit - time each player has for all of their turns in the game, like a
+///   chess clock
+///
+/// Either or both may be set, with neither players have as long as they like. When a
+/// player runs out of time their turn is played for them, see `CanastaGame::time_out`.
+/// Once a players game time has run out every later turn of theirs is played for them.
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use game_lib::clock::TimeControl;
+/// let control = TimeControl::new().turn(Duration::from_secs(30)).game(Duration::from_secs(600));
+/// assert_eq!(control.turn_limit(), Some(Duration::from_secs(30)));
+/// assert!(TimeControl::new().is_unlimited());
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimeControl {
+    turn: Option<Duration>,
+    game: Option<Duration>,
+}
+
+impl TimeControl {
+    /// No limits
+    pub fn new() -> Self {
+        Self { turn: None, game: None }
+    }
+
+    /// Time each turn may take
+    pub fn turn(mut self, limit: Duration) -> Self {
+        self.turn = Some(limit);
+        self
+    }
+
+    /// Time each player has for the whole game
+    pub fn game(mut self, limit: Duration) -> Self {
+        self.game = Some(limit);
+        self
+    }
+
+    pub fn turn_limit(&self) -> Option<Duration> {
+        self.turn
+    }
+
+    pub fn game_limit(&self) -> Option<Duration> {
+        self.game
+    }
+
+    /// If neither a turn nor a game limit is set
+    pub fn is_unlimited(&self) -> bool {
+        self.turn.is_none() && self.game.is_none()
+    }
+}
+
+/// Keeps the time of each player in a game
+/// # Overview
+/// The clock runs for one player at a time. `start_turn` is called whenever the turn
+/// passes, which charges the time the last turn took to that players game time. The
+/// clock can be paused, such as while waiting for a player to reconnect, and paused
+/// time is not charged to anyone.
+///
+/// Times are given by the caller so the clock can be driven by any source of time.
+/// # Example
+/// ```
+/// use std::time::{Duration, Instant};
+/// use game_lib::clock::{TimeControl, GameClock};
+/// let control = TimeControl::new().turn(Duration::from_secs(30)).game(Duration::from_secs(60));
+/// let start = Instant::now();
+/// let mut clock = GameClock::new(control, 2, 0, start);
+/// let later = start + Duration::from_secs(20);
+/// assert_eq!(clock.turn_remaining(later), Some(Duration::from_secs(10)));
+///
+/// // time spent paused is not charged
+/// clock.pause(later);
+/// clock.resume(later + Duration::from_secs(100));
+/// assert!(!clock.expired(later + Duration::from_secs(105)));
+/// assert!(clock.expired(later + Duration::from_secs(110)));
+///
+/// // the turn took 30 seconds of player 0's game time
+/// clock.start_turn(1, later + Duration::from_secs(110));
+/// assert_eq!(clock.game_remaining(0, later + Duration::from_secs(110)), Some(Duration::from_secs(30)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct GameClock {
+    control: TimeControl,
+    /// Game time left for each player, not counting the turn being played
+    remaining: Vec<Duration>,
+    player: u8,
+    started: Instant,
+    paused: Option<Instant>,
+}
+
+impl GameClock {
+    /// Starts the clock for the turn of `player`
+    pub fn new(control: TimeControl, players: u8, player: u8, now: Instant) -> Self {
+        let remaining = vec![control.game.unwrap_or_default(); players as usize];
+        Self { control, remaining, player, started: now, paused: None }
+    }
+
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    /// The player the clock is running for
+    pub fn player(&self) -> u8 {
+        self.player
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.is_some()
+    }
+
+    /// Ends the current turn, charging its time to its player, and starts the turn of
+    /// `player`
+    pub fn start_turn(&mut self, player: u8, now: Instant) {
+        let taken = self.elapsed(now);
+        if let Some(left) = self.remaining.get_mut(self.player as usize) {
+            *left = left.saturating_sub(taken);
+        }
+        self.player = player;
+        self.started = self.paused.unwrap_or(now);
+    }
+
+    /// Stops the clock, does nothing if it is already stopped
+    pub fn pause(&mut self, now: Instant) {
+        self.paused.get_or_insert(now);
+    }
+
+    /// Starts the clock again, does nothing if it is running
+    pub fn resume(&mut self, now: Instant) {
+        let Some(paused) = self.paused.take() else { return };
+        self.started += now.saturating_duration_since(paused);
+    }
+
+    /// Time the current turn has taken
+    fn elapsed(&self, now: Instant) -> Duration {
+        self.paused.unwrap_or(now).saturating_duration_since(self.started)
+    }
+
+    /// Game time a player has left
+    /// # Returns
+    /// `None` if there is no game limit
+    pub fn game_remaining(&self, player: u8, now: Instant) -> Option<Duration> {
+        self.control.game?;
+        let left = self.remaining.get(player as usize).copied().unwrap_or_default();
+        if player == self.player { Some(left.saturating_sub(self.elapsed(now))) } else { Some(left) }
+    }
+
+    /// Time left for the current turn, the lesser of the turn and game time left
+    /// # Returns
+    /// `None` if the time control has no limits
+    pub fn turn_remaining(&self, now: Instant) -> Option<Duration> {
+        let turn = self.control.turn.map(|limit| limit.saturating_sub(self.elapsed(now)));
+        let game = self.game_remaining(self.player, now);
+        match (turn, game) {
+            (Some(turn), Some(game)) => Some(turn.min(game)),
+            (turn, game) => turn.or(game),
+        }
+    }
+
+    /// If the current player has run out of time
+    pub fn expired(&self, now: Instant) -> bool {
+        self.turn_remaining(now) == Some(Duration::ZERO)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn game_time_runs_out_over_turns() {
+        let control = TimeControl::new().game(10 * SECOND);
+        let start = Instant::now();
+        let mut clock = GameClock::new(control, 2, 0, start);
+        // player 0 spends 4 seconds on each of their turns, player 1 only 1
+        let mut now = start;
+        for _ in 0..2 {
+            now += 4 * SECOND;
+            clock.start_turn(1, now);
+            now += SECOND;
+            clock.start_turn(0, now);
+        }
+        assert_eq!(clock.game_remaining(0, now), Some(2 * SECOND));
+        assert_eq!(clock.game_remaining(1, now), Some(8 * SECOND));
+        assert!(!clock.expired(now + SECOND));
+        assert!(clock.expired(now + 2 * SECOND));
+    }
+
+    #[test]
+    fn turn_limit_is_capped_by_game_time() {
+        let control = TimeControl::new().turn(5 * SECOND).game(3 * SECOND);
+        let start = Instant::now();
+        let clock = GameClock::new(control, 2, 1, start);
+        assert_eq!(clock.turn_remaining(start), Some(3 * SECOND));
+        let clock = GameClock::new(TimeControl::new().turn(5 * SECOND), 2, 1, start);
+        assert_eq!(clock.turn_remaining(start + SECOND), Some(4 * SECOND));
+        assert_eq!(clock.game_remaining(1, start), None);
+    }
+
+    #[test]
+    fn turn_started_while_paused() {
+        let control = TimeControl::new().turn(5 * SECOND).game(60 * SECOND);
+        let start = Instant::now();
+        let mut clock = GameClock::new(control, 2, 0, start);
+        clock.pause(start + 2 * SECOND);
+        // time paused is charged to neither turn
+        clock.start_turn(1, start + 30 * SECOND);
+        assert_eq!(clock.game_remaining(0, start + 30 * SECOND), Some(58 * SECOND));
+        clock.resume(start + 40 * SECOND);
+        assert_eq!(clock.turn_remaining(start + 41 * SECOND), Some(4 * SECOND));
+    }
+
+    #[test]
+    fn unlimited_clock_never_expires() {
+        let start = Instant::now();
+        let clock = GameClock::new(TimeControl::new(), 3, 2, start);
+        assert_eq!(clock.turn_remaining(start + 1000 * SECOND), None);
+        assert!(!clock.expired(start + 1000 * SECOND));
+    }
+}
@@ -0,0 +1,171 @@
+use rand::{Rng, seq::SliceRandom};
+
+use crate::{
+    game::CanastaGame,
+    view::PlayerView,
+    player::Player,
+    card::PlayCard,
+    card_collections::{deck::Deck, discard::Discard, meld::Meld},
+    errors::determinize_error::DeterminizeError,
+};
+
+impl CanastaGame {
+    /// Creates a game matching everything a player can see, with the cards they cannot
+    /// see dealt at random
+    /// # Overview
+    /// Every fact in the view is kept:
+    /// - The viewers hand and staged cards
+    /// - Every meld, red three and the top of the discard pile
+    /// - Cards each opponent is known to hold from taking the pile
+    /// - The number of cards in each hand and the stock
+    ///
+    /// The remaining cards are shuffled, used to fill the opponents hands around their
+    /// known cards, and the rest become the stock and the discard pile below its top in a
+    /// random order. Red threes are laid as soon as they are drawn, so unseen red threes
+    /// are never put in a hand and go in the stock before the pile.
+    ///
+    /// The game created is a single hand with no log, it ends when the hand does.
+    /// # Parameters
+    /// - `view` - What the player can see, from `CanastaGame::player_view`
+    /// - `rng` - Source of randomness for dealing the unseen cards
+    /// # Returns
+    /// - `Ok(CanastaGame)` - A game consistent with the view
+    /// - `Err(DeterminizeError)` - The view does not describe a possible game
+    /// # Example
+    /// ```
+    /// use game_lib::game::CanastaGame;
+    /// use rand::SeedableRng;
+    /// let game = CanastaGame::builder().players(2).canastas(1).hand().seed(3).build().unwrap();
+    /// let player = game.get_current_player();
+    /// let view = game.player_view(player).unwrap();
+    /// let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(1);
+    /// let sampled = CanastaGame::determinize(&view, &mut rng).unwrap();
+    /// assert_eq!(sampled.get_hand(player).unwrap(), game.get_hand(player).unwrap());
+    /// assert_eq!(sampled.player_view(player).unwrap(), view);
+    /// ```
+    pub fn determinize<R: Rng>(view: &PlayerView, rng: &mut R) -> Result<CanastaGame, DeterminizeError> {
+        let table = &view.table;
+        let seat = view.seat();
+        let staged = view.staged.iter().map(|(_, cards)| cards.len()).sum::<usize>();
+        if view.hand.len() + staged != seat.hand_size {
+            return Err(DeterminizeError::HandSizeMismatch { expected: seat.hand_size, found: view.hand.len() + staged });
+        }
+        let opponent_known = table.seats.iter()
+            .filter(|s| s.player != view.player)
+            .flat_map(|s| &s.known_cards);
+        let seen: Vec<u8> = view.hand.iter()
+            .chain(view.staged.iter().flat_map(|(_, cards)| cards))
+            .chain(table.discard_top.iter())
+            .chain(table.seats.iter().flat_map(|s| s.melds.iter().flat_map(|m| &m.cards)))
+            .chain(table.seats.iter().flat_map(|s| &s.red_threes))
+            .chain(opponent_known)
+            .map(|c| c.id())
+            .collect();
+        for (i, id) in seen.iter().enumerate() {
+            if seen[..i].contains(id) { return Err(DeterminizeError::DuplicateCard(*id)) }
+        }
+
+        // every card of the pile but its top is hidden
+        let buried = table.discard_size.saturating_sub(1);
+        let hidden = table.stock_count + buried;
+        let mut needed = hidden;
+        for seat in table.seats.iter().filter(|s| s.player != view.player) {
+            let known = seat.known_cards.len();
+            if known > seat.hand_size {
+                return Err(DeterminizeError::TooManyKnownCards { player: seat.player, known, hand_size: seat.hand_size });
+            }
+            needed += seat.hand_size - known;
+        }
+        let (mut red_threes, mut unseen): (Vec<PlayCard>, Vec<PlayCard>) = Deck::full().into_iter()
+            .filter(|c| !seen.contains(&c.id()))
+            .partition(|c| c.is_red_three());
+        if red_threes.len() + unseen.len() != needed {
+            return Err(DeterminizeError::CardCountMismatch { expected: needed, unseen: red_threes.len() + unseen.len() });
+        }
+        if red_threes.len() > hidden {
+            return Err(DeterminizeError::RedThreesOutsideStock { red_threes: red_threes.len(), stock: hidden });
+        }
+        unseen.shuffle(rng);
+
+        let players = table.seats.iter().map(|seat| {
+            let hand = if seat.player == view.player {
+                view.hand.clone()
+            } else {
+                let mut hand = seat.known_cards.clone();
+                hand.append(&mut unseen.split_off(unseen.len() - (seat.hand_size - seat.known_cards.len())));
+                hand
+            };
+            let melds = seat.melds.iter().map(|m| {
+                let mut meld = Meld::new(m.rank);
+                m.cards.iter().cloned().for_each(|c| meld.push(c));
+                meld
+            }).collect();
+            let known = seat.known_cards.iter().map(|c| c.id()).collect();
+            let mut player = Player::from_parts(seat.player, hand, melds, seat.red_threes.clone(), seat.score, known);
+            if seat.player == view.player {
+                for (rank, cards) in &view.staged {
+                    cards.iter().cloned().for_each(|c| player.stage_card(c, *rank));
+                }
+            }
+            player
+        }).collect();
+        red_threes.append(&mut unseen);
+        let mut pile = red_threes.split_off(red_threes.len().min(table.stock_count));
+        let mut stock = red_threes;
+        stock.shuffle(rng);
+        pile.shuffle(rng);
+        pile.extend(table.discard_top.clone());
+
+        Ok(CanastaGame::from_parts(
+            players,
+            Deck::from_cards(stock),
+            Discard::from_cards(pile, table.pile_frozen),
+            table.canastas_to_go_out,
+            table.current_player,
+            table.phase,
+            table.hand_number,
+            rng.gen(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use crate::{agents::{Agent, RandomAgent}, game::TurnPhase};
+
+    use super::*;
+
+    #[test]
+    fn opponents_never_hold_red_threes() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        for seed in 0..10 {
+            let mut game = CanastaGame::builder().players(3).canastas(1).hand().seed(seed).build().unwrap();
+            let mut agent = RandomAgent::new(seed);
+            while game.get_phase() != TurnPhase::GameOver {
+                let player = game.get_current_player();
+                let view = game.player_view(player).unwrap();
+                for _ in 0..3 {
+                    let sampled = CanastaGame::determinize(&view, &mut rng).unwrap();
+                    assert_eq!(sampled.player_view(player).unwrap(), view);
+                    for opponent in (0..3).filter(|p| *p != player) {
+                        assert!(!sampled.player(opponent).get_hand().iter().any(|c| c.is_red_three()));
+                    }
+                }
+                let action = agent.choose(&view, &game.legal_actions());
+                game.apply(player, action).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn hand_size_must_match() {
+        let game = CanastaGame::builder().players(2).canastas(1).hand().seed(3).build().unwrap();
+        let mut view = game.player_view(game.get_current_player()).unwrap();
+        view.hand.pop();
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        assert!(matches!(CanastaGame::determinize(&view, &mut rng), Err(DeterminizeError::HandSizeMismatch { .. })));
+    }
+}
@@ -0,0 +1,495 @@
+use std::cmp::Ordering;
+
+use crate::{
+    game::{CanastaGame, TurnPhase, PLAYERS},
+    action_log::Action,
+    agents::{Agent, AgentSpec},
+    card::{PlayCard, Rank},
+    view::{PlayerView, SeatView},
+    legal_moves::MELD_RANKS,
+    errors::{env_error::EnvError, player_action_error::PlayerActionError},
+};
+
+/// Kinds of card told apart by observations, Ace to King then Joker
+const KINDS: usize = 14;
+/// Most seats at a table
+const SEATS: usize = 4;
+/// Most natural cards of one rank in a set of cards, there are 8 of each rank
+const MAX_NATURALS: usize = 8;
+/// Combinations of up to 3 wild cards split between twos and jokers
+const WILD_SETS: usize = 10;
+/// Distinct sets of cards of one rank
+const CARD_SETS: usize = (MAX_NATURALS + 1) * WILD_SETS;
+
+const TAKE_OFFSET: usize = 3;
+const MELD_OFFSET: usize = TAKE_OFFSET + CARD_SETS;
+const DISCARD_OFFSET: usize = MELD_OFFSET + MELD_RANKS.len() * CARD_SETS;
+
+/// Size of the action space
+/// # Overview
+/// Cards of the same kind are interchangeable so actions are described by how many
+/// cards of each kind they use rather than by card IDs:
+/// - `0` - Draw
+/// - `1` - Commit staged melds
+/// - `2` - Clear staged melds
+/// - Take the pile with each set of naturals, twos and jokers
+/// - Stage each set of naturals, twos and jokers for each meld rank
+/// - Discard a card of each kind
+pub const ACTION_COUNT: usize = DISCARD_OFFSET + KINDS;
+
+/// Values describing each seat in an observation
+const SEAT_SIZE: usize = 4 + MELD_RANKS.len() * 2 + KINDS;
+/// Size of an observation
+/// # Overview
+/// Seats are given in turn order starting with the observing player, empty seats
+/// are all zero.
+/// - Cards of each kind in hand, then staged
+/// - For each seat: if it is filled, hand size, red threes, score in thousands,
+///   naturals and wilds in each meld, and cards of each kind known to be in hand
+/// - Pile size, kind of the top card and if it is frozen
+/// - Stock size, turn phase and canastas needed to go out
+pub const OBSERVATION_SIZE: usize = KINDS * 2 + SEAT_SIZE * SEATS + 2 + KINDS + 4;
+
+/// Rewards given to the learning agent
+///
+/// The game result is always rewarded when the game ends, the other rewards are given
+/// as they happen to encourage progress during the game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RewardShaping {
+    /// Reward for winning, the negative is given for losing
+    pub win: f64,
+    /// Reward per point of final score ahead of the best opponent
+    pub score_difference: f64,
+    /// Reward per point added to the agents melds
+    pub meld_points: f64,
+    /// Reward per canasta completed
+    pub canasta: f64,
+    /// Reward per card gained from taking the pile
+    pub pile_card: f64,
+}
+
+impl Default for RewardShaping {
+    fn default() -> Self {
+        Self { win: 1.0, score_difference: 0.001, meld_points: 0.0, canasta: 0.0, pile_card: 0.0 }
+    }
+}
+
+/// The result of taking a step
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub observation: Vec<f32>,
+    pub reward: f64,
+    /// If the game has ended, `reset` must be called before the next step
+    pub done: bool,
+    /// Which actions are legal on the next step
+    pub mask: Vec<bool>,
+}
+
+/// Reinforcement learning environment for one seat of a game
+/// # Overview
+/// The learning agent plays one seat and the other seats are played by opponent
+/// agents. Opponents act between the agents actions, so every observation is of
+/// the agents turn.
+///
+/// Actions are indices into a fixed action space of `ACTION_COUNT` actions, see
+/// `action_mask` for which are legal. Observations are `OBSERVATION_SIZE` numbers
+/// describing the agents `PlayerView`.
+/// # Example
+/// ```
+/// use game_lib::{env::{CanastaEnv, ACTION_COUNT, OBSERVATION_SIZE}, agents::AgentSpec};
+/// let mut env = CanastaEnv::new(vec![AgentSpec::Random]).unwrap();
+/// let observation = env.reset(3).unwrap();
+/// assert_eq!(observation.len(), OBSERVATION_SIZE);
+/// loop {
+///     // draw if possible, otherwise discard the highest card
+///     let mask = env.action_mask();
+///     let action = if mask[0] { 0 } else { mask.iter().rposition(|&legal| legal).unwrap() };
+///     let step = env.step(action).unwrap();
+///     if step.done { break }
+/// }
+/// assert_eq!(env.action_mask(), vec![false; ACTION_COUNT]);
+/// ```
+pub struct CanastaEnv {
+    opponents: Vec<AgentSpec>,
+    seat: u8,
+    canastas: u8,
+    full_game: bool,
+    shaping: RewardShaping,
+    game: Option<CanastaGame>,
+    agents: Vec<Option<Box<dyn Agent>>>,
+    /// Legal action for each index of the action space
+    legal: Vec<Option<Action>>,
+}
+
+impl CanastaEnv {
+    /// Create an environment for single hands against the opponents, one seat each
+    /// # Returns
+    /// - `Ok(CanastaEnv)` - The environment, ready to be reset
+    /// - `Err(EnvError::InvalidOpponents)` - The opponents and the agent are not a number
+    ///   of players in `PLAYERS`
+    pub fn new(opponents: Vec<AgentSpec>) -> Result<Self, EnvError> {
+        let players = u8::try_from(opponents.len() + 1).ok().filter(|p| PLAYERS.contains(p));
+        if players.is_none() { return Err(EnvError::InvalidOpponents(opponents.len())) }
+        Ok(Self {
+            opponents,
+            seat: 0,
+            canastas: 1,
+            full_game: false,
+            shaping: RewardShaping::default(),
+            game: None,
+            agents: vec![],
+            legal: vec![None; ACTION_COUNT],
+        })
+    }
+
+    /// Seat of the learning agent, wrapped to the number of players
+    pub fn seat(mut self, seat: u8) -> Self {
+        self.seat = seat;
+        self
+    }
+
+    pub fn canastas(mut self, canastas: u8) -> Self {
+        self.canastas = canastas;
+        self
+    }
+
+    /// Play full games to 5000 points rather than single hands
+    pub fn full_game(mut self) -> Self {
+        self.full_game = true;
+        self
+    }
+
+    pub fn hand(mut self) -> Self {
+        self.full_game = false;
+        self
+    }
+
+    pub fn reward(mut self, shaping: RewardShaping) -> Self {
+        self.shaping = shaping;
+        self
+    }
+
+    /// The game being played, if `reset` has been called
+    pub fn game(&self) -> Option<&CanastaGame> {
+        self.game.as_ref()
+    }
+
+    /// Start a new game dealt from the seed and return the first observation
+    ///
+    /// Opponents seated before the agent take their turns first.
+    /// # Returns
+    /// - `Ok(Vec<f32>)` - The observation of the agents first turn
+    /// - `Err(EnvError::OpponentFailed)` - An opponent chose an illegal action
+    pub fn reset(&mut self, seed: u64) -> Result<Vec<f32>, EnvError> {
+        // the number of opponents was checked by `new`
+        let players = self.opponents.len() as u8 + 1;
+        self.game = None;
+        self.update_legal();
+        let seat = self.seat % players;
+        let builder = CanastaGame::builder().players(players).canastas(self.canastas).seed(seed);
+        let mut builder = if self.full_game { builder.full_game() } else { builder.hand() };
+        let mut game = builder.build().ok_or(EnvError::InvalidOpponents(self.opponents.len()))?;
+        let mut opponents = self.opponents.iter();
+        self.agents = (0..players).map(|p| {
+            if p == seat { None } else { opponents.next().map(|spec| spec.build(seed.wrapping_add(p as u64))) }
+        }).collect();
+        self.seat = seat;
+        self.play_opponents(&mut game).map_err(EnvError::OpponentFailed)?;
+        self.game = Some(game);
+        self.update_legal();
+        Ok(self.observation())
+    }
+
+    /// Take the action with the given index for the agent
+    /// # Returns
+    /// - `Ok(Step)` - The observation and reward after the opponents have replied
+    /// - `Err(EnvError::NotStarted)` - `reset` has not been called
+    /// - `Err(EnvError::GameOver)` - The game has ended
+    /// - `Err(EnvError::IllegalAction)` - The action is not legal, see `action_mask`
+    /// - `Err(EnvError::OpponentFailed)` - An opponent chose an illegal action
+    pub fn step(&mut self, action: usize) -> Result<Step, EnvError> {
+        let mut game = self.game.take().ok_or(EnvError::NotStarted)?;
+        let result = self.advance(&mut game, action);
+        self.game = Some(game);
+        let reward = result?;
+        self.update_legal();
+        let done = self.game.as_ref().is_some_and(|g| g.get_phase() == TurnPhase::GameOver);
+        Ok(Step { observation: self.observation(), reward, done, mask: self.action_mask() })
+    }
+
+    /// Applies the action and opponent replies, giving the reward
+    fn advance(&mut self, game: &mut CanastaGame, action: usize) -> Result<f64, EnvError> {
+        if game.get_phase() == TurnPhase::GameOver { return Err(EnvError::GameOver) }
+        let chosen = self.legal.get(action).cloned().flatten().ok_or(EnvError::IllegalAction(action))?;
+        let before = self.progress(game);
+        let pile_cards = match chosen {
+            Action::TakeDiscard(_) => game.player_view(self.seat).expect("Seat is valid").table.discard_size,
+            _ => 0,
+        };
+        game.apply(self.seat, chosen).map_err(|_| EnvError::IllegalAction(action))?;
+        self.play_opponents(game).map_err(EnvError::OpponentFailed)?;
+        let after = self.progress(game);
+        let s = &self.shaping;
+        let mut reward = (after.meld_points - before.meld_points) as f64 * s.meld_points
+            + (after.canastas - before.canastas) as f64 * s.canasta
+            + pile_cards as f64 * s.pile_card;
+        if game.get_phase() == TurnPhase::GameOver {
+            let scores = game.scores();
+            let own = scores[self.seat as usize];
+            let best = scores.iter().enumerate().filter(|(p, _)| *p != self.seat as usize).map(|(_, s)| *s).max().unwrap_or(0);
+            reward += (own - best) as f64 * s.score_difference;
+            reward += match own.cmp(&best) {
+                Ordering::Greater => s.win,
+                Ordering::Less => -s.win,
+                Ordering::Equal => 0.0,
+            };
+        }
+        Ok(reward)
+    }
+
+    /// Lets opponents act until it is the agents turn or the game ends
+    fn play_opponents(&mut self, game: &mut CanastaGame) -> Result<(), PlayerActionError> {
+        while game.get_phase() != TurnPhase::GameOver && game.get_current_player() != self.seat {
+            let player = game.get_current_player();
+            let Some(Some(agent)) = self.agents.get_mut(player as usize) else { break };
+            let view = game.player_view(player).expect("Current player is valid");
+            let action = agent.choose(&view, &game.legal_actions());
+            game.apply(player, action)?;
+        }
+        Ok(())
+    }
+
+    /// Running totals used for shaped rewards
+    fn progress(&self, game: &CanastaGame) -> Progress {
+        let seat = &game.player_view(self.seat).expect("Seat is valid").table.seats[self.seat as usize];
+        Progress {
+            meld_points: seat.melds.iter().flat_map(|m| &m.cards).map(|c| c.value() as i32).sum(),
+            canastas: seat.melds.iter().filter(|m| m.canasta).count() as i32,
+        }
+    }
+
+    fn update_legal(&mut self) {
+        self.legal = vec![None; ACTION_COUNT];
+        let Some(game) = &self.game else { return };
+        if game.get_phase() == TurnPhase::GameOver || game.get_current_player() != self.seat { return }
+        let hand = game.get_hand(self.seat).expect("Seat is valid");
+        for action in game.legal_actions() {
+            if let Some(index) = action_index(&action, hand) {
+                self.legal[index].get_or_insert(action);
+            }
+        }
+    }
+
+    /// Which actions in the action space are legal, all false once the game has ended
+    pub fn action_mask(&self) -> Vec<bool> {
+        self.legal.iter().map(|a| a.is_some()).collect()
+    }
+
+    /// The action an index stands for, if it is currently legal
+    pub fn action(&self, index: usize) -> Option<&Action> {
+        self.legal.get(index)?.as_ref()
+    }
+
+    /// Observation of the agents current view, all zero before `reset`
+    pub fn observation(&self) -> Vec<f32> {
+        match &self.game {
+            Some(game) => encode_view(&game.player_view(self.seat).expect("Seat is valid")),
+            None => vec![0.0; OBSERVATION_SIZE],
+        }
+    }
+}
+
+struct Progress {
+    meld_points: i32,
+    canastas: i32,
+}
+
+fn kind(card: &PlayCard) -> usize {
+    usize::from(card.rank()) - 1
+}
+
+/// Index of a set of cards of one rank among the `CARD_SETS` sets
+fn card_set(cards: &[&PlayCard]) -> usize {
+    let twos = cards.iter().filter(|c| *c.rank() == Rank::Two).count();
+    let jokers = cards.iter().filter(|c| *c.rank() == Rank::Joker).count();
+    let naturals = (cards.len() - twos - jokers).min(MAX_NATURALS);
+    // wild sets are ordered by twos then jokers, with at most 3 wilds
+    let wilds = (0..twos).map(|t| 4 - t).sum::<usize>() + jokers;
+    naturals * WILD_SETS + wilds
+}
+
+/// Index in the action space of an action for a player holding the hand
+pub fn action_index(action: &Action, hand: &[PlayCard]) -> Option<usize> {
+    let cards = |ids: &[u8]| hand.iter().filter(|c| ids.contains(&c.id())).collect::<Vec<_>>();
+    match action {
+        Action::Draw => Some(0),
+        Action::CommitMeld => Some(1),
+        Action::ClearMeld => Some(2),
+        Action::TakeDiscard(ids) => Some(TAKE_OFFSET + card_set(&cards(ids))),
+        Action::Meld { cards: ids, rank } => {
+            let rank = MELD_RANKS.iter().position(|r| r == rank)?;
+            Some(MELD_OFFSET + rank * CARD_SETS + card_set(&cards(ids)))
+        }
+        Action::Discard(id) => hand.iter().find(|c| c.id() == *id).map(|c| DISCARD_OFFSET + kind(c)),
+        Action::Unmeld(_) | Action::Undo | Action::Redo => None,
+    }
+}
+
+/// Encodes a players view as `OBSERVATION_SIZE` numbers
+pub fn encode_view(view: &PlayerView) -> Vec<f32> {
+    let mut obs = Vec::with_capacity(OBSERVATION_SIZE);
+    obs.extend(kind_counts(view.hand.iter()));
+    obs.extend(kind_counts(view.staged.iter().flat_map(|(_, cards)| cards)));
+
+    let seats = &view.table.seats;
+    for offset in 0..SEATS {
+        match seats.get((view.player as usize + offset) % seats.len()).filter(|_| offset < seats.len()) {
+            Some(seat) => encode_seat(&mut obs, seat),
+            None => obs.extend([0.0; SEAT_SIZE]),
+        }
+    }
+
+    let table = &view.table;
+    obs.push(table.discard_size as f32);
+    let mut top = [0.0; KINDS];
+    if let Some(card) = &table.discard_top { top[kind(card)] = 1.0 }
+    obs.extend(top);
+    obs.push(if table.pile_frozen { 1.0 } else { 0.0 });
+
+    obs.push(table.stock_count as f32);
+    obs.push(if table.phase == TurnPhase::Draw { 1.0 } else { 0.0 });
+    obs.push(if table.phase == TurnPhase::Meld { 1.0 } else { 0.0 });
+    obs.push(table.canastas_to_go_out as f32);
+    debug_assert_eq!(obs.len(), OBSERVATION_SIZE);
+    obs
+}
+
+fn encode_seat(obs: &mut Vec<f32>, seat: &SeatView) {
+    obs.push(1.0);
+    obs.push(seat.hand_size as f32);
+    obs.push(seat.red_threes.len() as f32);
+    obs.push(seat.score as f32 / 1000.0);
+    for rank in MELD_RANKS {
+        let meld = seat.melds.iter().find(|m| m.rank == rank);
+        let wilds = meld.map_or(0, |m| m.cards.iter().filter(|c| c.is_wild()).count());
+        let naturals = meld.map_or(0, |m| m.cards.len()) - wilds;
+        obs.push(naturals as f32);
+        obs.push(wilds as f32);
+    }
+    obs.extend(kind_counts(seat.known_cards.iter()));
+}
+
+fn kind_counts<'a>(cards: impl Iterator<Item = &'a PlayCard>) -> [f32; KINDS] {
+    let mut counts = [0.0; KINDS];
+    cards.for_each(|c| counts[kind(c)] += 1.0);
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Draws if possible, otherwise discards the highest card
+    fn draw_or_discard(env: &CanastaEnv) -> usize {
+        let mask = env.action_mask();
+        if mask[0] { 0 } else { mask.iter().rposition(|&legal| legal).unwrap() }
+    }
+
+    #[test]
+    fn opponents_are_checked() {
+        assert_eq!(CanastaEnv::new(vec![]).err(), Some(EnvError::InvalidOpponents(0)));
+        assert_eq!(CanastaEnv::new(vec![AgentSpec::Random; 4]).err(), Some(EnvError::InvalidOpponents(4)));
+        for opponents in 1..=3 {
+            let mut env = CanastaEnv::new(vec![AgentSpec::Random; opponents]).unwrap();
+            assert_eq!(env.reset(1).unwrap().len(), OBSERVATION_SIZE);
+            assert_eq!(env.game().unwrap().num_players() as usize, opponents + 1);
+        }
+    }
+
+    #[test]
+    fn mask_agrees_with_legal_actions() {
+        for seed in 0..5 {
+            let mut env = CanastaEnv::new(vec![AgentSpec::Random, AgentSpec::Random]).unwrap().seat(1);
+            env.reset(seed).unwrap();
+            loop {
+                let game = env.game().unwrap();
+                let hand = game.get_hand(1).unwrap();
+                let legal = game.legal_actions();
+                let mask = env.action_mask();
+                for (index, _) in mask.iter().enumerate().filter(|(_, &m)| m) {
+                    let action = env.action(index).unwrap();
+                    assert!(legal.contains(action), "{action:?} is not legal");
+                    assert_eq!(action_index(action, hand), Some(index));
+                }
+                for action in &legal {
+                    if let Some(index) = action_index(action, hand) { assert!(mask[index], "{action:?} is not masked") }
+                }
+                if env.step(draw_or_discard(&env)).unwrap().done { break }
+            }
+            assert_eq!(env.step(0), Err(EnvError::GameOver));
+        }
+    }
+
+    #[test]
+    fn observations_have_fixed_size() {
+        for players in PLAYERS {
+            let mut game = CanastaGame::builder().players(players).canastas(1).hand().seed(2).build().unwrap();
+            for _ in 0..20 {
+                for player in 0..players {
+                    assert_eq!(encode_view(&game.player_view(player).unwrap()).len(), OBSERVATION_SIZE);
+                }
+                if game.get_phase() == TurnPhase::GameOver { break }
+                let action = game.legal_actions()[0].clone();
+                game.apply(game.get_current_player(), action).unwrap();
+            }
+        }
+        let env = CanastaEnv::new(vec![AgentSpec::Random]).unwrap();
+        assert_eq!(env.observation(), vec![0.0; OBSERVATION_SIZE]);
+    }
+
+    #[test]
+    fn rewards_follow_shaping() {
+        let shaping = RewardShaping { win: 0.0, score_difference: 0.0, meld_points: 0.0, canasta: 0.0, pile_card: 1.0 };
+        let mut taken = false;
+        for seed in 0..20 {
+            let mut env = CanastaEnv::new(vec![AgentSpec::Random]).unwrap().reward(shaping.clone());
+            env.reset(seed).unwrap();
+            loop {
+                let mask = env.action_mask();
+                let take = (TAKE_OFFSET..MELD_OFFSET).find(|i| mask[*i]);
+                let pile = env.game().unwrap().player_view(0).unwrap().table.discard_size;
+                let step = env.step(take.unwrap_or_else(|| draw_or_discard(&env))).unwrap();
+                // only taking the pile is rewarded
+                assert_eq!(step.reward, if take.is_some() { pile as f64 } else { 0.0 });
+                taken |= take.is_some();
+                if step.done { break }
+            }
+        }
+        assert!(taken);
+
+        let shaping = RewardShaping { win: 1.0, score_difference: 0.0, ..shaping };
+        let mut env = CanastaEnv::new(vec![AgentSpec::Random]).unwrap().reward(RewardShaping { pile_card: 0.0, ..shaping });
+        env.reset(4).unwrap();
+        let last = loop {
+            let step = env.step(draw_or_discard(&env)).unwrap();
+            if step.done { break step }
+            assert_eq!(step.reward, 0.0);
+        };
+        let scores = env.game().unwrap().scores();
+        let expected = match scores[0].cmp(&scores[1]) {
+            Ordering::Greater => 1.0,
+            Ordering::Less => -1.0,
+            Ordering::Equal => 0.0,
+        };
+        assert_eq!(last.reward, expected);
+    }
+
+    #[test]
+    fn step_needs_reset() {
+        let mut env = CanastaEnv::new(vec![AgentSpec::Random]).unwrap();
+        assert_eq!(env.step(0), Err(EnvError::NotStarted));
+        assert_eq!(env.action_mask(), vec![false; ACTION_COUNT]);
+        env.reset(1).unwrap();
+        assert_eq!(env.step(ACTION_COUNT), Err(EnvError::IllegalAction(ACTION_COUNT)));
+    }
+}
@@ -0,0 +1,9 @@
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AgentError {
+    #[error("Unknown agent {0}, expected random, heuristic or ismcts")]
+    UnknownAgent(String),
+    #[error("Invalid setting {0} for agent")]
+    InvalidSetting(String),
+}
@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DeterminizeError {
+    #[error("Card {0} is in more than one place")]
+    DuplicateCard(u8),
+    #[error("Player {player} has {known} known cards but only {hand_size} cards in hand")]
+    TooManyKnownCards { player: u8, known: usize, hand_size: usize },
+    #[error("Viewers hand has {found} cards but the table shows {expected}")]
+    HandSizeMismatch { expected: usize, found: usize },
+    #[error("{unseen} cards are unseen but {expected} are needed for hands, the stock and the discard pile")]
+    CardCountMismatch { expected: usize, unseen: usize },
+    #[error("{red_threes} red threes are unseen but the stock and discard pile only hide {stock} cards")]
+    RedThreesOutsideStock { red_threes: usize, stock: usize },
+}
@@ -0,0 +1,17 @@
+use thiserror::Error;
+
+use super::player_action_error::PlayerActionError;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum EnvError {
+    #[error("Canasta is played by 2 to 4 players, {0} opponents cannot be seated")]
+    InvalidOpponents(usize),
+    #[error("Environment must be reset before taking a step")]
+    NotStarted,
+    #[error("Game is over, reset to start a new game")]
+    GameOver,
+    #[error("Action {0} is not legal")]
+    IllegalAction(usize),
+    #[error("Opponent chose an illegal action: {0}")]
+    OpponentFailed(PlayerActionError),
+}
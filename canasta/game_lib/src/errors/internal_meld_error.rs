@@ -1,6 +1,7 @@
-
 use thiserror::Error;
 
+use crate::card::Rank;
+
 #[derive(Debug, Error)]
 pub(crate) enum InternalMeldError {
     #[error("Card ID is not in hand")]
@@ -9,4 +10,15 @@ pub(crate) enum InternalMeldError {
     InvalidCardToMeld(u8),
     #[error("Selected card is not of the selected rank")]
     IncorrectRank(u8),
+    #[error("Cannot meld wild cards as their own rank")]
+    WildRank,
+    #[error("Meld of {} has too few cards", .0.name())]
+    TooFewCards(Rank),
+    #[error("Meld of {} needs at least two natural cards", .0.name())]
+    NotEnoughNaturals(Rank),
+    #[error("Meld of {} has too many wild cards", .0.name())]
+    TooManyWilds(Rank),
+    #[error("No cards are staged to meld")]
+    NothingStaged,
 }
+
@@ -1,4 +1,12 @@
-
 pub mod player_action_error;
 pub mod game_error;
+pub mod replay_error;
+pub mod determinize_error;
+pub mod agent_error;
+pub mod simulation_error;
+pub mod tournament_error;
+pub mod env_error;
+pub mod notation_error;
+pub mod record_error;
+pub mod store_error;
 pub(crate) mod internal_meld_error;
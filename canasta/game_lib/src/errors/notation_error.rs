@@ -0,0 +1,13 @@
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum NotationError {
+    #[error("{0} is not a rank")]
+    InvalidRank(String),
+    #[error("{0} is not a suit")]
+    InvalidSuit(String),
+    #[error("{0} is not a card")]
+    InvalidCard(String),
+    #[error("No {0} in hand")]
+    NotInHand(String),
+}
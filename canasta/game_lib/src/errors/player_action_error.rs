@@ -1,7 +1,10 @@
-
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+use crate::card::Rank;
+
+use super::internal_meld_error::InternalMeldError;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
 pub enum PlayerActionError {
     #[error("Is currently player {0}s turn")]
     NotPlayerTurn(u8),
@@ -11,4 +14,53 @@ pub enum PlayerActionError {
     GameOver,
     #[error("Given card is not a valid ID")]
     InvalidCard,
+    #[error("Card {0} cannot be melded")]
+    CannotMeldCard(u8),
+    #[error("Card {0} is not of the meld rank")]
+    IncorrectRank(u8),
+    #[error("Cannot meld wild cards as their own rank")]
+    WildRank,
+    #[error("Meld of {} has too few cards", .0.name())]
+    TooFewCards(Rank),
+    #[error("Meld of {} needs at least two natural cards", .0.name())]
+    NotEnoughNaturals(Rank),
+    #[error("Meld of {} has too many wild cards", .0.name())]
+    TooManyWilds(Rank),
+    #[error("No cards are staged to meld")]
+    NothingStaged,
+    #[error("Staged cards must be committed or cleared before discarding")]
+    CardsStaged,
+    #[error("Initial meld needs {required} points but only has {value}")]
+    InitialMeldTooLow { required: i32, value: i32 },
+    #[error("Black threes can only be melded when going out")]
+    BlackThreesNotGoingOut,
+    #[error("Not enough canastas to go out")]
+    CannotGoOut,
+    #[error("The discard pile is empty")]
+    PileEmpty,
+    #[error("The top of the discard pile cannot be taken")]
+    PileTopBlocked,
+    #[error("The discard pile is frozen and needs two natural cards to take")]
+    PileFrozen,
+    #[error("Undo is not enabled for this game")]
+    UndoDisabled,
+    #[error("There is no action to undo")]
+    NothingToUndo,
+    #[error("There is no action to redo")]
+    NothingToRedo,
+}
+
+impl From<InternalMeldError> for PlayerActionError {
+    fn from(value: InternalMeldError) -> Self {
+        match value {
+            InternalMeldError::InvalidCardId(_) => PlayerActionError::InvalidCard,
+            InternalMeldError::InvalidCardToMeld(id) => PlayerActionError::CannotMeldCard(id),
+            InternalMeldError::IncorrectRank(id) => PlayerActionError::IncorrectRank(id),
+            InternalMeldError::WildRank => PlayerActionError::WildRank,
+            InternalMeldError::TooFewCards(rank) => PlayerActionError::TooFewCards(rank),
+            InternalMeldError::NotEnoughNaturals(rank) => PlayerActionError::NotEnoughNaturals(rank),
+            InternalMeldError::TooManyWilds(rank) => PlayerActionError::TooManyWilds(rank),
+            InternalMeldError::NothingStaged => PlayerActionError::NothingStaged,
+        }
+    }
 }
@@ -0,0 +1,19 @@
+use thiserror::Error;
+
+use super::{player_action_error::PlayerActionError, notation_error::NotationError};
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum RecordError {
+    #[error("Line {line}: {message}")]
+    Syntax { line: usize, message: String },
+    #[error("Missing {0} tag")]
+    MissingTag(String),
+    #[error("Line {line}: invalid value for {tag} tag")]
+    InvalidTag { line: usize, tag: String },
+    #[error("Line {line}: {source}")]
+    Notation { line: usize, source: NotationError },
+    #[error("Line {line}: illegal move {text}: {source}")]
+    IllegalMove { line: usize, text: String, source: PlayerActionError },
+    #[error("Result tag {0} does not match the final scores")]
+    ResultMismatch(String),
+}
@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+use super::player_action_error::PlayerActionError;
+
+#[derive(Error, Debug)]
+pub enum ReplayError {
+    #[error("Game builder is missing settings")]
+    IncompleteBuilder,
+    #[error("Step {0} is past the end of the log")]
+    StepOutOfRange(usize),
+    #[error("Action {step} could not be applied: {source}")]
+    IllegalAction { step: usize, source: PlayerActionError },
+    #[error("Action {0} had a different outcome than was logged")]
+    OutcomeMismatch(usize),
+}
@@ -0,0 +1,11 @@
+use thiserror::Error;
+
+use super::player_action_error::PlayerActionError;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SimulationError {
+    #[error("Games need between 2 and 4 agents, {0} were given")]
+    InvalidPlayers(usize),
+    #[error("Game with seed {seed} failed: {source}")]
+    GameFailed { seed: u64, source: PlayerActionError },
+}
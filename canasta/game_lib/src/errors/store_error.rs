@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+use super::player_action_error::PlayerActionError;
+
+#[derive(Error, Debug)]
+pub enum StoreError {
+    #[error("Could not access the store: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("No game {0} is stored")]
+    NotFound(u32),
+    #[error("Stored game {id} is corrupt on line {line}: {message}")]
+    Corrupt { id: u32, line: usize, message: String },
+    #[error("Stored game {id} has an illegal action on line {line}: {source}")]
+    IllegalAction { id: u32, line: usize, source: PlayerActionError },
+}
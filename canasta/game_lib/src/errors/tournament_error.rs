@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+use super::simulation_error::SimulationError;
+
+#[derive(Error, Debug)]
+pub enum TournamentError {
+    #[error("A tournament needs at least 2 agents")]
+    TooFewAgents,
+    #[error(transparent)]
+    Simulation(#[from] SimulationError),
+    #[error("Could not access ratings file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid rating on line {0} of ratings file")]
+    InvalidRatings(usize),
+}
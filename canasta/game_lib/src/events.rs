@@ -0,0 +1,137 @@
+use std::sync::mpsc::{Receiver, Sender, channel};
+
+use crate::card::{PlayCard, Rank};
+
+/// Who is watching a game, which decides what hidden information they can see
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Viewer {
+    /// A seated player, who can see their own cards
+    Player(u8),
+    /// Someone not playing, who only sees public information
+    Spectator,
+}
+
+/// Something that happened in a game
+///
+/// Events are sent to subscribers after each action, see `CanastaGame::subscribe`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameEvent {
+    /// A player drew a card from the deck, the card is only given to the player that drew it
+    CardDrawn { player: u8, card: Option<PlayCard> },
+    /// A player laid a red three
+    RedThreeLaid { player: u8, card: PlayCard },
+    /// A player took the discard pile, lists every card in the pile
+    PileTaken { player: u8, cards: Vec<PlayCard> },
+    /// A player added cards to their meld of a rank
+    MeldCommitted { player: u8, rank: Rank, cards: Vec<PlayCard> },
+    /// A players meld of a rank became a canasta
+    CanastaCompleted { player: u8, rank: Rank, natural: bool },
+    /// A player discarded a card
+    CardDiscarded { player: u8, card: PlayCard },
+    /// The discard pile became frozen
+    PileFrozen,
+    /// A players time ran out, the actions that follow are played for them
+    TurnTimedOut { player: u8 },
+    /// A player finished their turn and it is now the next players turn
+    TurnEnded { player: u8, next: u8 },
+    /// A hand was scored, `scores` are the totals after the hand
+    HandEnded { hand: u32, went_out: Option<u8>, hand_scores: Vec<i32>, scores: Vec<i32> },
+    /// A player undid their last action
+    ActionUndone { player: u8 },
+    /// A player redid an undone action
+    ActionRedone { player: u8 },
+}
+
+impl GameEvent {
+    /// Returns the event with anything the viewer is not allowed to know removed
+    pub fn redact_for(&self, viewer: Viewer) -> GameEvent {
+        match self {
+            GameEvent::CardDrawn { player, card: Some(_) } if viewer != Viewer::Player(*player) => {
+                GameEvent::CardDrawn { player: *player, card: None }
+            }
+            event => event.clone(),
+        }
+    }
+}
+
+/// Sends events from a game to its subscribers
+#[derive(Default)]
+pub(crate) struct EventBus {
+    subscribers: Vec<(Viewer, Sender<GameEvent>)>,
+}
+
+impl EventBus {
+    pub(crate) fn new() -> Self {
+        Self { subscribers: vec![] }
+    }
+
+    pub(crate) fn subscribe(&mut self, viewer: Viewer) -> Receiver<GameEvent> {
+        let (sender, receiver) = channel();
+        self.subscribers.push((viewer, sender));
+        receiver
+    }
+
+    /// Sends the event to every subscriber, redacted for what they can see
+    ///
+    /// Subscribers that have dropped their receiver are removed
+    pub(crate) fn emit(&mut self, event: GameEvent) {
+        self.subscribers.retain(|(viewer, sender)| {
+            sender.send(event.redact_for(*viewer)).is_ok()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{game::CanastaGame, action_log::Action};
+
+    use super::*;
+
+    #[test]
+    fn drawn_card_is_only_shown_to_the_drawer() {
+        let card: PlayCard = "KH".parse().unwrap();
+        let drawn = GameEvent::CardDrawn { player: 1, card: Some(card) };
+        let hidden = GameEvent::CardDrawn { player: 1, card: None };
+        assert_eq!(drawn.redact_for(Viewer::Player(1)), drawn);
+        assert_eq!(drawn.redact_for(Viewer::Player(0)), hidden);
+        assert_eq!(drawn.redact_for(Viewer::Spectator), hidden);
+        // public events are never changed
+        let laid = GameEvent::RedThreeLaid { player: 1, card: "3D".parse().unwrap() };
+        assert_eq!(laid.redact_for(Viewer::Spectator), laid);
+    }
+
+    #[test]
+    fn subscribers_see_their_own_draws() {
+        let mut game = CanastaGame::builder().players(2).canastas(1).hand().seed(3).build().unwrap();
+        let player = game.get_current_player();
+        let other = 1 - player;
+        let mine = game.subscribe(Viewer::Player(player));
+        let theirs = game.subscribe(Viewer::Player(other));
+        let watching = game.subscribe(Viewer::Spectator);
+        game.apply(player, Action::Draw).unwrap();
+        let drawn = |events: &Receiver<GameEvent>| events.try_iter().find_map(|e| match e {
+            GameEvent::CardDrawn { player: p, card } if p == player => Some(card),
+            _ => None,
+        });
+        assert!(drawn(&mine).unwrap().is_some_and(|c| game.get_hand(player).unwrap().contains(&c)));
+        assert_eq!(drawn(&theirs), Some(None));
+        assert_eq!(drawn(&watching), Some(None));
+    }
+
+    #[test]
+    fn dropped_subscribers_are_pruned() {
+        let mut bus = EventBus::new();
+        let kept = bus.subscribe(Viewer::Spectator);
+        drop(bus.subscribe(Viewer::Player(0)));
+        assert_eq!(bus.subscribers.len(), 2);
+        let event = GameEvent::ActionUndone { player: 0 };
+        bus.emit(event.clone());
+        assert_eq!(bus.subscribers.len(), 1);
+        assert_eq!(kept.try_recv(), Ok(event));
+        drop(kept);
+        bus.emit(GameEvent::ActionRedone { player: 0 });
+        assert!(bus.subscribers.is_empty());
+    }
+}
@@ -1,13 +1,25 @@
-use crate::{player::Player, card_collections::{deck::Deck, discard::Discard}, game_builder::GameBuilder, card::PlayCard, errors::{player_action_error::PlayerActionError, game_error::GameError}};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
 
-/// Reflects the current phase of the game 
-/// # Phases 
+use crate::{player::{Player, CommitSummary}, card_collections::{deck::Deck, discard::Discard}, game_builder::GameBuilder, card::{PlayCard, Rank}, errors::{player_action_error::PlayerActionError, game_error::GameError}, action_log::{ActionLog, Action, ActionOutcome}, history::{History, Snapshot}, events::{EventBus, GameEvent, Viewer}, view::{PlayerView, SpectatorView, TableView, SeatView, MeldView}, agents::{Agent, FallbackAgent}};
+use std::sync::mpsc::Receiver;
+
+/// Score a player needs to reach to win a full game
+pub const WINNING_SCORE: i32 = 5000;
+
+/// Number of players a game can be played by
+pub const PLAYERS: std::ops::RangeInclusive<u8> = 2..=4;
+
+/// Reflects the current phase of the game
+/// # Phases
 /// - `Draw` - The current player either needs to draw a card or take the pack
 /// - `Meld` - The current player can meld cards and discard
 /// - `TurnOver` - The turn is over, switch to next player
-/// - `GameOver` - The game has ended 
+/// - `GameOver` - The game has ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TurnPhase {
-    Draw, 
+    Draw,
     Meld,
     TurnOver,
     GameOver,
@@ -18,88 +30,423 @@ pub struct CanastaGame {
     players: Vec<Player>,
     deck: Deck,
     discard: Discard,
-    full_game: bool,    
-    canastas_go_out: u8,    
+    full_game: bool,
+    canastas_go_out: u8,
     current_player: u8,
     turn_phase: TurnPhase,
+    seed: u64,
+    rng: ChaCha8Rng,
+    hand_number: u32,
+    log: ActionLog,
+    history: History,
+    events: EventBus,
+}
+
+/// Cloning a game copies its full state and log, but not its event subscribers
+impl Clone for CanastaGame {
+    fn clone(&self) -> Self {
+        Self {
+            game_id: self.game_id,
+            players: self.players.clone(),
+            deck: self.deck.clone(),
+            discard: self.discard.clone(),
+            full_game: self.full_game,
+            canastas_go_out: self.canastas_go_out,
+            current_player: self.current_player,
+            turn_phase: self.turn_phase,
+            seed: self.seed,
+            rng: self.rng.clone(),
+            hand_number: self.hand_number,
+            log: self.log.clone(),
+            history: self.history.clone(),
+            events: EventBus::new(),
+        }
+    }
+}
+
+/// Points the first meld of a hand must be worth for a player with the given score
+pub fn initial_meld_requirement(score: i32) -> i32 {
+    if score < 0 { 15 }
+    else if score < 1500 { 50 }
+    else if score < 3000 { 90 }
+    else { 120 }
 }
 
 impl CanastaGame {
-    pub(crate) fn new(players: u8, canstas: u8, full_game: bool) -> Self {
-        let mut game = Self { 
-            game_id: 0, 
+    pub(crate) fn new(players: u8, canstas: u8, full_game: bool, seed: u64, allow_undo: bool) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut game = Self {
+            game_id: 0,
             players: vec![],
-            deck: Deck::new(), 
-            discard: Discard::new(), 
+            deck: Deck::new(&mut rng),
+            discard: Discard::new(),
             full_game,
             canastas_go_out: canstas,
-            current_player: players-1,
-            turn_phase: TurnPhase::TurnOver,
+            current_player: 0,
+            turn_phase: TurnPhase::Draw,
+            seed,
+            rng,
+            hand_number: 0,
+            log: ActionLog::new(),
+            history: History::new(allow_undo),
+            events: EventBus::new(),
         };
 
         for i in 0..players {
             game.players.push(Player::new(i))
         }
 
-        let deal = 
-            if players == 2 { 15 } 
+        game.deal_hand();
+        game
+    }
+
+    /// Creates a single hand game part way through from its parts
+    ///
+    /// The game has no log and no history, it ends when the hand does.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn from_parts(players: Vec<Player>, deck: Deck, discard: Discard, canastas_go_out: u8, current_player: u8, turn_phase: TurnPhase, hand_number: u32, seed: u64) -> Self {
+        Self {
+            game_id: 0,
+            players,
+            deck,
+            discard,
+            full_game: false,
+            canastas_go_out,
+            current_player,
+            turn_phase,
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            hand_number,
+            log: ActionLog::new(),
+            history: History::new(false),
+            events: EventBus::new(),
+        }
+    }
+
+    /// Shuffles a new deck and deals a hand to every player
+    fn deal_hand(&mut self) {
+        self.deck = Deck::new(&mut self.rng);
+        self.discard = Discard::new();
+        let players = self.players.len() as u8;
+
+        let deal =
+            if players == 2 { 15 }
             else if players == 3 { 13 }
             else { 11 };
 
-        if deal * players >= game.deck.remaining() as u8 { todo!("Too many players") }
-
+        for player in &mut self.players {
+            player.reset_hand();
+        }
         for _ in 0..deal {
-            for player in &mut game.players {
-                player.add_hand(game.deck.draw().unwrap());
+            for player in &mut self.players {
+                player.add_hand(self.deck.draw().unwrap());
+            }
+        }
+
+        // red threes are laid straight away and replaced
+        for player in &mut self.players {
+            loop {
+                let laid = player.meld_red_threes();
+                if laid.is_empty() { break }
+                for _ in laid {
+                    player.add_hand(self.deck.draw().unwrap());
+                }
             }
         }
 
         let mut valid_turn = false;
 
         while !valid_turn {
-            let card = game.deck.draw().unwrap();
+            let card = self.deck.draw().unwrap();
             if !(card.is_wild() || card.is_red_three() || card.is_black_three()) {
                 valid_turn = true;
             }
-            game.discard.throw(card);
+            self.discard.throw(card);
         }
-        game
+
+        self.current_player = (self.hand_number % players as u32) as u8;
+        self.turn_phase = TurnPhase::Draw;
+    }
+
+    /// Scores the hand that was just played and either deals the next hand or ends the game
+    ///
+    /// `went_out` is the player that went out, if any.
+    pub(crate) fn end_hand(&mut self, went_out: Option<u8>) {
+        let mut hand_scores = vec![];
+        for player in &mut self.players {
+            let score = player.hand_score(went_out == Some(player.id()));
+            player.add_score(score);
+            hand_scores.push(score);
+        }
+        self.events.emit(GameEvent::HandEnded { hand: self.hand_number, went_out, hand_scores, scores: self.scores() });
+        self.hand_number += 1;
+        if !self.full_game || self.players.iter().any(|p| p.score() >= WINNING_SCORE) {
+            self.turn_phase = TurnPhase::GameOver;
+        } else {
+            self.deal_hand();
+            if self.discard.is_frozen() { self.events.emit(GameEvent::PileFrozen) }
+        }
+    }
+
+    /// Subscribe to the events of the game
+    /// # Overview
+    /// Every event after subscribing is sent to the returned receiver, with any
+    /// information the viewer should not see removed. For example only the player
+    /// that drew a card is told which card it was.
+    ///
+    /// Dropping the receiver unsubscribes.
+    /// # Example
+    /// ```
+    /// use game_lib::{game::CanastaGame, events::{GameEvent, Viewer}};
+    /// let mut game = CanastaGame::quick_hand();
+    /// let player = game.get_current_player();
+    /// let own = game.subscribe(Viewer::Player(player));
+    /// let spectator = game.subscribe(Viewer::Spectator);
+    /// game.draw(player).unwrap();
+    /// let drawn = |event: &GameEvent| matches!(event, GameEvent::CardDrawn { .. });
+    /// // the player sees their card while the spectator does not
+    /// let event = own.try_iter().find(drawn).unwrap();
+    /// assert!(matches!(event, GameEvent::CardDrawn { card: Some(_), .. }));
+    /// let event = spectator.try_iter().find(drawn).unwrap();
+    /// assert!(matches!(event, GameEvent::CardDrawn { card: None, .. }));
+    /// ```
+    pub fn subscribe(&mut self, viewer: Viewer) -> Receiver<GameEvent> {
+        self.events.subscribe(viewer)
     }
 
-    pub(crate) fn end_game(&mut self) {
-        todo!()
+    /// Sends events for staged melds that were just committed
+    fn emit_commit(&mut self, player: u8, staged: Vec<(Rank, Vec<PlayCard>)>, canastas: &[Rank]) {
+        for (rank, cards) in staged {
+            self.events.emit(GameEvent::MeldCommitted { player, rank, cards });
+        }
+        for rank in canastas {
+            let natural = self.players[player as usize].get_meld(rank).is_some_and(|m| m.is_natural());
+            self.events.emit(GameEvent::CanastaCompleted { player, rank: *rank, natural });
+        }
+    }
+
+    /// Sends an event for each of the players red threes with the given IDs
+    fn emit_red_threes(&mut self, player: u8, ids: &[u8]) {
+        let cards: Vec<PlayCard> = self.players[player as usize].red_threes().iter()
+            .filter(|c| ids.contains(&c.id()))
+            .cloned()
+            .collect();
+        for card in cards {
+            self.events.emit(GameEvent::RedThreeLaid { player, card });
+        }
     }
 
     pub fn builder() -> GameBuilder {
         GameBuilder::new()
     }
-    
+
     pub fn quick_hand() -> Self {
-        CanastaGame::new(2, 1, false)
+        CanastaGame::new(2, 1, false, rand::random(), false)
+    }
+
+    /// ID of the game, given by the `GameRegistry` holding it or 0 if it has none
+    pub fn game_id(&self) -> u32 {
+        self.game_id
+    }
+
+    pub(crate) fn set_game_id(&mut self, id: u32) {
+        self.game_id = id;
+    }
+
+    /// The seed used to shuffle every deck in the game
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn num_players(&self) -> u8 {
+        self.players.len() as u8
+    }
+
+    /// Number of canastas a player needs before they can go out
+    pub fn canastas_to_go_out(&self) -> u8 {
+        self.canastas_go_out
+    }
+
+    /// If the game is played over multiple hands until a player reaches `WINNING_SCORE`
+    pub fn is_full_game(&self) -> bool {
+        self.full_game
+    }
+
+    pub fn get_phase(&self) -> TurnPhase {
+        self.turn_phase
+    }
+
+    /// Number of hands that have been completed
+    pub fn hand_number(&self) -> u32 {
+        self.hand_number
+    }
+
+    /// Total score of every player, indexed by player number
+    pub fn scores(&self) -> Vec<i32> {
+        self.players.iter().map(|p| p.score()).collect()
+    }
+
+    /// Every action applied to the game so far
+    pub fn log(&self) -> &ActionLog {
+        &self.log
+    }
+
+    pub(crate) fn log_mut(&mut self) -> &mut ActionLog {
+        &mut self.log
+    }
+
+    /// If players are allowed to undo and redo their actions
+    pub fn undo_enabled(&self) -> bool {
+        self.history.is_enabled()
+    }
+
+    /// Saves the state an undoable action can change
+    fn snapshot(&self, player: u8) -> Snapshot {
+        Snapshot {
+            player,
+            players: self.players.clone(),
+            discard: self.discard.clone(),
+            current_player: self.current_player,
+            turn_phase: self.turn_phase,
+        }
     }
 
-    /// Returns which players turn it currently is 
-    /// # Overview 
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.players = snapshot.players;
+        self.discard = snapshot.discard;
+        self.current_player = snapshot.current_player;
+        self.turn_phase = snapshot.turn_phase;
+    }
+
+    /// Saves the state before an action if it could be undone
+    fn undo_point(&self, player: u8) -> Option<Snapshot> {
+        self.history.is_enabled().then(|| self.snapshot(player))
+    }
+
+    /// Logs an applied action and updates the undo history
+    ///
+    /// `before` is the state before the action, or `None` if it cannot be undone.
+    fn record(&mut self, player: u8, action: Action, outcome: ActionOutcome, before: Option<Snapshot>) {
+        self.log.push(player, action, outcome);
+        self.history.record(before);
+    }
+
+    /// Returns which players turn it currently is
+    /// # Overview
     /// Will return the player number for the curret player.
     ///
-    /// This will start at player 0 and will change evert time a player 
+    /// This will start at player 0 and will change evert time a player
     /// discards.
     ///
     /// Once the final player discards this will return to player 0.
     ///
     /// This is the number that should be entered into any player actions.
-    /// # Returns 
-    /// - `u8` - The player number for whos turn it is 
+    /// # Returns
+    /// - `u8` - The player number for whos turn it is
     pub fn get_current_player(&self) -> u8 {
         self.current_player
     }
 
+    /// Checks it is the players turn and the game is in the given phase
+    fn check_turn(&self, player: u8, phase: TurnPhase) -> Result<(), PlayerActionError> {
+        if self.turn_phase == TurnPhase::GameOver { return Err(PlayerActionError::GameOver) }
+        if player != self.current_player { return Err(PlayerActionError::NotPlayerTurn(self.current_player)) }
+        if self.turn_phase != phase { return Err(PlayerActionError::IncorrectTurnPhase) }
+        Ok(())
+    }
+
+    /// Checks it is the players turn and they are able to stage melds
+    fn check_staging(&self, player: u8) -> Result<(), PlayerActionError> {
+        if self.turn_phase == TurnPhase::GameOver { return Err(PlayerActionError::GameOver) }
+        if player != self.current_player { return Err(PlayerActionError::NotPlayerTurn(self.current_player)) }
+        match self.turn_phase {
+            TurnPhase::Draw | TurnPhase::Meld => Ok(()),
+            TurnPhase::TurnOver | TurnPhase::GameOver => Err(PlayerActionError::IncorrectTurnPhase),
+        }
+    }
+
+    /// Checks the rules that apply to any cards being melded
+    ///
+    /// `cards_left` is the number of cards the player will hold after melding.
+    fn check_meld_rules(&self, player: &Player, summary: &CommitSummary, cards_left: usize) -> Result<(), PlayerActionError> {
+        if !player.has_melded() {
+            let required = initial_meld_requirement(player.score());
+            if summary.value < required {
+                return Err(PlayerActionError::InitialMeldTooLow { required, value: summary.value })
+            }
+        }
+        let can_go_out = summary.canastas >= self.canastas_go_out as usize;
+        if summary.black_threes && !(can_go_out && cards_left <= 1) { return Err(PlayerActionError::BlackThreesNotGoingOut) }
+        // a player needs a card left to discard unless they are able to go out
+        if cards_left < 2 && !can_go_out { return Err(PlayerActionError::CannotGoOut) }
+        Ok(())
+    }
+
+    /// Applies an action for a player
+    /// # Overview
+    /// Calls the action method on the game matching the given `Action`.
+    ///
+    /// This gives a single entry point for driving a game from recorded or
+    /// generated actions.
+    /// # Returns
+    /// - `Ok(ActionOutcome)` - The action was applied, the outcome is the same as
+    ///   the one recorded in the log.
+    /// - `Err(PlayerActionError)` - The action was not valid, the game is unchanged.
+    pub fn apply(&mut self, player: u8, action: Action) -> Result<ActionOutcome, PlayerActionError> {
+        match action {
+            Action::Draw => { self.draw(player)?; }
+            Action::TakeDiscard(cards) => self.take_discard(player, cards)?,
+            Action::Meld { cards, rank } => self.meld(player, cards, rank)?,
+            Action::Unmeld(cards) => self.unmeld(player, cards)?,
+            Action::ClearMeld => self.clear_meld(player)?,
+            Action::CommitMeld => self.commit_meld(player)?,
+            Action::Discard(card_id) => { self.discard(player, card_id)?; }
+            Action::Undo => self.undo(player)?,
+            Action::Redo => self.redo(player)?,
+        }
+        Ok(self.log.last().expect("Applied action is logged").outcome.clone())
+    }
+
+    /// Check if an action would succeed without applying it
+    /// # Overview
+    /// Runs every check the action would make when applied but does not change the game.
+    /// This can be used to show a player whether an action, such as committing their staged
+    /// melds, is allowed and the reason if it is not.
+    /// # Returns
+    /// - `Ok(())` - Applying the action would succeed
+    /// - `Err(PlayerActionError)` - The reason the action would fail, such as
+    ///   `IncorrectTurnPhase`, `InitialMeldTooLow`, `TooManyWilds` or `PileFrozen`
+    /// # Example
+    /// ```
+    /// use game_lib::{game::CanastaGame, action_log::Action, errors::player_action_error::PlayerActionError};
+    /// let mut game = CanastaGame::quick_hand();
+    /// let player = game.get_current_player();
+    /// assert_eq!(game.validate(player, &Action::CommitMeld), Err(PlayerActionError::IncorrectTurnPhase));
+    /// assert!(game.validate(player, &Action::Draw).is_ok());
+    /// // nothing was changed by validating
+    /// assert!(game.draw(player).is_ok());
+    /// assert_eq!(game.validate(player, &Action::CommitMeld), Err(PlayerActionError::NothingStaged));
+    /// ```
+    pub fn validate(&self, player: u8, action: &Action) -> Result<(), PlayerActionError> {
+        match action {
+            Action::Draw => self.check_draw(player),
+            Action::TakeDiscard(cards) => self.check_take_discard(player, cards),
+            Action::Meld { cards, rank } => self.check_meld(player, cards, *rank),
+            Action::Unmeld(cards) => self.check_unmeld(player, cards),
+            Action::ClearMeld => self.check_clear_meld(player),
+            Action::CommitMeld => self.check_commit_meld(player).map(|_| ()),
+            Action::Discard(card_id) => self.check_discard(player, *card_id),
+            Action::Undo => self.check_undo(player),
+            Action::Redo => self.check_redo(player),
+        }
+    }
+
     /// Attempt to draw a card for a player
-    /// # Overview 
+    /// # Overview
     /// For an entered player number attempt to draw a card.
     ///
     /// If it is the entered players turn this will draw a card from the deck
-    /// and place it into their hand. 
+    /// and place it into their hand.
     ///
     /// This will transition the phase of their turn into the `Meld` phase
     ///
@@ -107,73 +454,86 @@ impl CanastaGame {
     /// pile and a new card will be drawn.
     ///
     /// If a draw is successful a reference to the newly drawn card will be returned.
-    /// # Parameters 
-    /// - `player` - the player number for the player drawing. 
-    /// # Returns 
-    /// - `Ok(&PlayCard)` - A successful draw has occured. 
-    /// - `Err(PlayerActionError::NotPlayerTurn(u8)` - Was not the entered players turn, 
-    /// error contains current player number as a u8.
+    /// If the deck runs out while replacing red threes no card is drawn and the hand ends.
+    /// # Parameters
+    /// - `player` - the player number for the player drawing.
+    /// # Returns
+    /// - `Ok(Some(&PlayCard))` - A successful draw has occured.
+    /// - `Ok(None)` - The deck ran out, ending the hand.
+    /// - `Err(PlayerActionError::NotPlayerTurn(u8)` - Was not the entered players turn,
+    ///   error contains current player number as a u8.
     /// - `Err(PlayerActionError::IncorrectTurnPhase)` - Is the entered players turn but
-    /// they have already drawn.
+    ///   they have already drawn.
     /// # Example
     /// ```
+    /// use game_lib::game::CanastaGame;
     /// // Create the game
-    /// let game = CanastaGame::quick_hand();
-    /// // Find which players turn it is 
+    /// let mut game = CanastaGame::quick_hand();
+    /// // Find which players turn it is
     /// let player = game.get_current_player();
     /// // Draw a card will work
     /// assert!(game.draw(player).is_ok());
     /// // Drawing again will fail as the player has already drawn
     /// assert!(game.draw(player).is_err());
     /// ```
-    pub fn draw(&mut self, player: u8) -> Result<&PlayCard, PlayerActionError> {
-        // Check that current game state is valid for request 
-        if player != self.current_player { return Err(PlayerActionError::NotPlayerTurn(self.current_player)) }
-        match self.turn_phase {
-            TurnPhase::Draw => {}
-            TurnPhase::Meld | TurnPhase::TurnOver => return Err(PlayerActionError::IncorrectTurnPhase),
-            TurnPhase::GameOver => return Err(PlayerActionError::GameOver)
-        }
+    pub fn draw(&mut self, player: u8) -> Result<Option<&PlayCard>, PlayerActionError> {
+        // Check that current game state is valid for request
+        self.check_draw(player)?;
         // get a card off the deck
-        let card: PlayCard = loop {
-            let card = match self.deck.draw() {
-                Some(card) => card,
-                None => {
-                    self.end_game();
-                    return Err(PlayerActionError::GameOver)
-                }
-            };
+        let mut red_threes = vec![];
+        let card = loop {
+            let Some(card) = self.deck.draw() else { break None };
             // if the card is a red three then add to player and redraw
             if !card.is_red_three() {
-                break card
+                break Some(card)
             } else {
                 let player = &mut self.players[player as usize];
                 player.add_hand(card);
-                player.meld_red_threes();
+                red_threes.extend(player.meld_red_threes());
             }
         };
-        self.turn_phase = TurnPhase::Meld;
-        let player = &mut self.players[player as usize];
-        Ok(player.add_hand(card))
+        self.emit_red_threes(player, &red_threes);
+        self.events.emit(GameEvent::CardDrawn { player, card: card.clone() });
+        self.record(player, Action::Draw, ActionOutcome::Drew { card: card.as_ref().map(|c| c.id()), red_threes }, None);
+        match card {
+            Some(card) => {
+                self.turn_phase = TurnPhase::Meld;
+                let player = &mut self.players[player as usize];
+                Ok(Some(player.add_hand(card)))
+            }
+            None => {
+                self.end_hand(None);
+                Ok(None)
+            }
+        }
     }
 
-    /// Returns a reference to a players hand 
-    /// # Overview 
-    /// For the given player a reference will be returned to a slice of thier hand 
+    /// Checks a player could draw
+    pub(crate) fn check_draw(&self, player: u8) -> Result<(), PlayerActionError> {
+        self.check_turn(player, TurnPhase::Draw)
+    }
+
+    /// Returns a reference to a players hand
+    /// # Overview
+    /// For the given player a reference will be returned to a slice of thier hand
     ///
-    /// If the player given is out of range an error will be returned 
-    /// # Returns 
+    /// If the player given is out of range an error will be returned
+    ///
+    /// This gives the hand of any player, to show a player only what they are allowed
+    /// to see use `player_view` instead.
+    /// # Returns
     /// - `Ok(&[PlayCard]) - Player is valid and a reference to their hand is given
     /// - `Err(GameError::InvalidPlayer)` - The player given was not a valid player number,
-    /// likely out of bounds 
-    /// # Examples 
-    /// ```rust 
-    /// // create quick game with 2 players 
+    ///   likely out of bounds
+    /// # Examples
+    /// ```rust
+    /// use game_lib::game::CanastaGame;
+    /// // create quick game with 2 players
     /// let game = CanastaGame::quick_hand();
-    /// // check player hands 
+    /// // check player hands
     /// // 0 and 1 are valid as it's a 2 player hand
     /// assert!(game.get_hand(1).is_ok());
-    /// // there are 2 players so 4 is invalid 
+    /// // there are 2 players so 4 is invalid
     /// assert!(game.get_hand(4).is_err());
     /// ```
     pub fn get_hand(&self, player: u8) -> Result<&[PlayCard], GameError> {
@@ -183,37 +543,582 @@ impl CanastaGame {
         }
     }
 
+    pub(crate) fn player(&self, player: u8) -> &Player {
+        &self.players[player as usize]
+    }
+
+    /// What a players hand would score if it ended now without anyone going out
+    pub(crate) fn hand_value(&self, player: u8) -> i32 {
+        self.players[player as usize].hand_score(false)
+    }
+
+    pub(crate) fn discard_top(&self) -> Option<&PlayCard> {
+        self.discard.top()
+    }
+
+    /// Public information about the table
+    fn table_view(&self) -> TableView {
+        let seats = self.players.iter().map(|p| SeatView {
+            player: p.id(),
+            hand_size: p.get_hand().len() + p.staged().map(|(_, cards)| cards.len()).sum::<usize>(),
+            melds: p.melds().map(|m| MeldView {
+                rank: *m.rank(),
+                cards: m.iter().cloned().collect(),
+                natural: m.is_natural(),
+                canasta: m.is_canasta(),
+            }).collect(),
+            red_threes: p.red_threes().to_vec(),
+            known_cards: p.known_cards().cloned().collect(),
+            score: p.score(),
+        }).collect();
+        TableView {
+            seats,
+            discard_top: self.discard.top().cloned(),
+            discard_size: self.discard.len(),
+            pile_frozen: self.discard.is_frozen(),
+            stock_count: self.deck.remaining(),
+            current_player: self.current_player,
+            phase: self.turn_phase,
+            hand_number: self.hand_number,
+            canastas_to_go_out: self.canastas_go_out,
+        }
+    }
+
+    /// Returns what a player is allowed to know about the game
+    /// # Overview
+    /// Unlike `get_hand`, which gives any hand, the view only contains the players own
+    /// hand and staged melds along with the public state of the table. Opponents are
+    /// only shown by how many cards they hold.
+    ///
+    /// Staged cards are counted as part of a players hand size as they have not been
+    /// shown to the other players.
+    /// # Returns
+    /// - `Ok(PlayerView)` - The view for the player
+    /// - `Err(GameError::InvalidPlayer)` - The player number is out of range
+    /// # Example
+    /// ```
+    /// use game_lib::game::CanastaGame;
+    /// let game = CanastaGame::quick_hand();
+    /// let view = game.player_view(0).unwrap();
+    /// assert_eq!(view.hand.len(), 15);
+    /// assert_eq!(view.opponents().next().unwrap().hand_size, 15);
+    /// assert!(game.player_view(2).is_err());
+    /// ```
+    pub fn player_view(&self, player: u8) -> Result<PlayerView, GameError> {
+        let player_ref = self.players.get(player as usize).ok_or(GameError::InvalidPlayer)?;
+        Ok(PlayerView {
+            player,
+            hand: player_ref.get_hand().to_vec(),
+            staged: player_ref.staged().map(|(rank, cards)| (rank, cards.to_vec())).collect(),
+            table: self.table_view(),
+        })
+    }
+
+    /// Returns what someone watching the game is allowed to know
+    pub fn spectator_view(&self) -> SpectatorView {
+        SpectatorView { table: self.table_view() }
+    }
+
+    /// Returns the red threes a player has laid this hand
+    pub fn get_red_threes(&self, player: u8) -> Result<&[PlayCard], GameError> {
+        match self.players.get(player as usize) {
+            Some(player) => Ok(player.red_threes()),
+            None => Err(GameError::InvalidPlayer),
+        }
+    }
+
+    /// Returns the cards a player has staged into a meld of the given rank
+    pub fn get_staged(&self, player: u8, rank: Rank) -> Result<&[PlayCard], GameError> {
+        match self.players.get(player as usize) {
+            Some(player) => Ok(player.view_temp_meld(rank)),
+            None => Err(GameError::InvalidPlayer),
+        }
+    }
+
     /// Discard a card of a given ID for a player
-    /// # Overview 
+    /// # Overview
     /// For the given player number discard the card with the given id in that players hand.
     ///
-    /// If the game state is invalid this will error. This can happen for events such as it not 
-    /// being the entered players turn, the player still having to draw or the given card not 
-    /// being a card ID in the players hand. 
-    pub fn discard(&mut self, player: u8, card_id: u8) -> Result<&PlayCard, PlayerActionError> {
-        // Check that current game state is valid for request 
-        if player != self.current_player { return Err(PlayerActionError::NotPlayerTurn(self.current_player)) }
-        match self.turn_phase {
-            TurnPhase::Meld => {}
-            TurnPhase::Draw | TurnPhase::TurnOver => return Err(PlayerActionError::IncorrectTurnPhase),
-            TurnPhase::GameOver => return Err(PlayerActionError::GameOver)
-        };
-        let player = &mut self.players[player as usize];
-        let card = match player.discard(card_id) {
-            Some(card) => card,
-            None => return Err(PlayerActionError::InvalidCard)
-        };
-        Ok(self.discard.throw(card))
+    /// If the game state is invalid this will error. This can happen for events such as it not
+    /// being the entered players turn, the player still having to draw or the given card not
+    /// being a card ID in the players hand.
+    ///
+    /// Discarding ends the turn. If it was the players last card they go out and the hand
+    /// ends, which is only allowed once they have enough canastas. The hand also ends if
+    /// the deck has run out. Staged cards must be committed or cleared first.
+    /// # Returns
+    /// - `Ok(PlayCard)` - The card that was discarded
+    /// - `Err(PlayerActionError::CardsStaged)` - The player still has cards staged
+    /// - `Err(PlayerActionError::CannotGoOut)` - This was the players last card and they
+    ///   do not have enough canastas to go out
+    pub fn discard(&mut self, player: u8, card_id: u8) -> Result<PlayCard, PlayerActionError> {
+        // Check that current game state is valid for request
+        self.check_discard(player, card_id)?;
+        let before = self.undo_point(player);
+        let player_ref = &mut self.players[player as usize];
+        let card = player_ref.throw(card_id).unwrap();
+        let went_out = player_ref.get_hand().is_empty();
+        let was_frozen = self.discard.is_frozen();
+        self.discard.throw(card.clone());
+        self.events.emit(GameEvent::CardDiscarded { player, card: card.clone() });
+        if !was_frozen && self.discard.is_frozen() { self.events.emit(GameEvent::PileFrozen) }
+        // ending the hand deals new cards so cannot be undone
+        let before = if went_out || self.deck.remaining() == 0 { None } else { before };
+        self.record(player, Action::Discard(card_id), ActionOutcome::Discarded { card: card_id }, before);
+        if went_out {
+            self.end_hand(Some(player));
+        } else if self.deck.remaining() == 0 {
+            self.end_hand(None);
+        } else {
+            self.current_player = (self.current_player + 1) % self.num_players();
+            self.turn_phase = TurnPhase::Draw;
+            self.events.emit(GameEvent::TurnEnded { player, next: self.current_player });
+        }
+        Ok(card)
+    }
+
+    /// Checks a player could discard the card
+    pub(crate) fn check_discard(&self, player: u8, card_id: u8) -> Result<(), PlayerActionError> {
+        self.check_turn(player, TurnPhase::Meld)?;
+        let player_ref = &self.players[player as usize];
+        let hand = player_ref.get_hand();
+        if !hand.iter().any(|c| c.id() == card_id) { return Err(PlayerActionError::InvalidCard) }
+        if player_ref.has_staged() { return Err(PlayerActionError::CardsStaged) }
+        if hand.len() == 1 && player_ref.canastas() < self.canastas_go_out as usize {
+            return Err(PlayerActionError::CannotGoOut)
+        }
+        Ok(())
+    }
+
+    /// Stage cards from a players hand into a meld
+    /// # Overview
+    /// Moves the cards with the given IDs out of the players hand into a staged meld of
+    /// the given rank. Staged melds are only checked and added to the table by `commit_meld`,
+    /// or when taking the discard pile.
+    ///
+    /// Every card must be in the players hand and either be of the given rank or wild.
+    /// If any card is invalid no cards are moved.
+    /// # Returns
+    /// - `Ok(())` - The cards were staged
+    /// - `Err(PlayerActionError::InvalidCard)` - A card was not in the players hand
+    /// - `Err(PlayerActionError::IncorrectRank(u8))` - A card was not of the given rank
+    pub fn meld(&mut self, player: u8, cards: Vec<u8>, rank: Rank) -> Result<(), PlayerActionError> {
+        self.check_meld(player, &cards, rank)?;
+        let before = self.undo_point(player);
+        self.players[player as usize].meld(cards.clone(), rank)?;
+        self.record(player, Action::Meld { cards, rank }, ActionOutcome::Melded, before);
+        Ok(())
+    }
+
+    /// Checks a player could stage the cards into a meld of the rank
+    pub(crate) fn check_meld(&self, player: u8, cards: &[u8], rank: Rank) -> Result<(), PlayerActionError> {
+        self.check_staging(player)?;
+        Ok(self.players[player as usize].check_meld(cards, rank)?)
+    }
+
+    /// Checks a player could return the cards from staging
+    pub(crate) fn check_unmeld(&self, player: u8, cards: &[u8]) -> Result<(), PlayerActionError> {
+        self.check_staging(player)?;
+        if !self.players[player as usize].missing_from_temp(cards).is_empty() { return Err(PlayerActionError::InvalidCard) }
+        Ok(())
+    }
+
+    /// Return staged cards to a players hand
+    /// # Overview
+    /// If any of the IDs are not currently staged nothing is returned and an error is given
+    pub fn unmeld(&mut self, player: u8, cards: Vec<u8>) -> Result<(), PlayerActionError> {
+        self.check_unmeld(player, &cards)?;
+        let before = self.undo_point(player);
+        self.players[player as usize].remove_from_temp(cards.clone())
+            .map_err(|_| PlayerActionError::InvalidCard)?;
+        self.record(player, Action::Unmeld(cards), ActionOutcome::Unmelded, before);
+        Ok(())
+    }
+
+    /// Checks a player could clear their staged melds
+    pub(crate) fn check_clear_meld(&self, player: u8) -> Result<(), PlayerActionError> {
+        self.check_staging(player)
+    }
+
+    /// Return every staged card to a players hand
+    pub fn clear_meld(&mut self, player: u8) -> Result<(), PlayerActionError> {
+        self.check_clear_meld(player)?;
+        let before = self.undo_point(player);
+        self.players[player as usize].clear_temp_meld();
+        self.record(player, Action::ClearMeld, ActionOutcome::ClearedMeld, before);
+        Ok(())
+    }
+
+    /// Commit a players staged melds to the table
+    /// # Overview
+    /// Every staged meld is checked together with any existing meld of the same rank.
+    /// A meld needs at least two natural cards and more naturals than wilds, with no more
+    /// than three wilds.
+    ///
+    /// The first meld of a hand must be worth at least `initial_meld_requirement` points.
+    /// Black threes can only be melded when going out.
+    ///
+    /// If this melds every card in the players hand they go out and the hand ends.
+    /// # Returns
+    /// - `Ok(())` - The melds were committed
+    /// - `Err(PlayerActionError)` - A rule was broken, the staged melds are left in place
+    pub fn commit_meld(&mut self, player: u8) -> Result<(), PlayerActionError> {
+        let summary = self.check_commit_meld(player)?;
+        let before = if summary.cards_left == 0 { None } else { self.undo_point(player) };
+        let staged = self.players[player as usize].staged().map(|(rank, cards)| (rank, cards.to_vec())).collect();
+        let canastas = self.players[player as usize].commit_meld();
+        self.emit_commit(player, staged, &canastas);
+        self.record(player, Action::CommitMeld, ActionOutcome::Committed { canastas }, before);
+        if summary.cards_left == 0 {
+            self.end_hand(Some(player));
+        }
+        Ok(())
+    }
+
+    /// Checks a player could commit their staged melds
+    pub(crate) fn check_commit_meld(&self, player: u8) -> Result<CommitSummary, PlayerActionError> {
+        self.check_turn(player, TurnPhase::Meld)?;
+        let player_ref = &self.players[player as usize];
+        let summary = player_ref.check_commit()?;
+        self.check_meld_rules(player_ref, &summary, summary.cards_left)?;
+        Ok(summary)
+    }
+
+    /// Checks a player could take the discard pile using the given cards from their hand
+    pub(crate) fn check_take_discard(&self, player: u8, cards: &[u8]) -> Result<(), PlayerActionError> {
+        self.check_turn(player, TurnPhase::Draw)?;
+        let top = self.discard.top().ok_or(PlayerActionError::PileEmpty)?;
+        if top.is_wild() || top.is_black_three() || top.is_red_three() { return Err(PlayerActionError::PileTopBlocked) }
+        let rank = *top.rank();
+        let player_ref = &self.players[player as usize];
+        player_ref.check_meld(cards, rank)?;
+        let mut trial = player_ref.clone();
+        trial.meld(cards.to_vec(), rank)?;
+        let naturals = player_ref.get_hand().iter()
+            .filter(|c| cards.contains(&c.id()) && !c.is_wild())
+            .count();
+        let frozen = self.discard.is_frozen() || !player_ref.has_melded();
+        if frozen && naturals < 2 { return Err(PlayerActionError::PileFrozen) }
+        trial.stage_card(top.clone(), rank);
+        let summary = trial.check_commit()?;
+        // red threes in the pile are laid rather than added to the hand
+        let red_threes = self.discard.iter().filter(|c| c.is_red_three()).count();
+        let cards_left = summary.cards_left + self.discard.len() - 1 - red_threes;
+        self.check_meld_rules(player_ref, &summary, cards_left)
+    }
+
+    /// Take the discard pile for a player
+    /// # Overview
+    /// Instead of drawing a player may take the whole discard pile. The top card must be
+    /// melded straight away, either with the given cards from the players hand or by adding
+    /// it to an existing meld of the same rank. Any other staged melds are committed at the
+    /// same time and count towards the initial meld.
+    ///
+    /// The pile cannot be taken when a wild card or black three is on top. While the pile is
+    /// frozen, or the player has not yet melded this hand, the top card must be matched with
+    /// two natural cards from the hand.
+    ///
+    /// The rest of the pile is added to the players hand and the turn moves to the `Meld` phase.
+    /// # Parameters
+    /// - `player` - the player number for the player taking the pile
+    /// - `cards` - IDs of cards in hand to meld with the top card
+    /// # Returns
+    /// - `Ok(())` - The pile was taken
+    /// - `Err(PlayerActionError::PileFrozen)` - Two natural cards are needed to take the pile
+    /// - `Err(PlayerActionError::PileTopBlocked)` - The top card cannot be taken
+    pub fn take_discard(&mut self, player: u8, cards: Vec<u8>) -> Result<(), PlayerActionError> {
+        self.check_take_discard(player, &cards)?;
+        let mut pile = self.discard.take();
+        let taken = pile.iter().map(|c| c.id()).collect();
+        self.events.emit(GameEvent::PileTaken { player, cards: pile.clone() });
+        let top = pile.pop().unwrap();
+        let rank = *top.rank();
+        let player_ref = &mut self.players[player as usize];
+        player_ref.meld(cards.clone(), rank)?;
+        player_ref.stage_card(top, rank);
+        let staged = player_ref.staged().map(|(rank, cards)| (rank, cards.to_vec())).collect();
+        let canastas = player_ref.commit_meld();
+        // everyone saw the cards in the pile so they are known to be in the hand
+        player_ref.reveal(pile.iter().map(|c| c.id()));
+        for card in pile {
+            player_ref.add_hand(card);
+        }
+        let red_threes = player_ref.meld_red_threes();
+        let went_out = player_ref.get_hand().is_empty();
+        self.emit_commit(player, staged, &canastas);
+        self.emit_red_threes(player, &red_threes);
+        self.turn_phase = TurnPhase::Meld;
+        self.record(player, Action::TakeDiscard(cards), ActionOutcome::TookDiscard { cards: taken }, None);
+        if went_out {
+            self.end_hand(Some(player));
+        }
+        Ok(())
+    }
+
+    /// Checks a player could undo their last action
+    pub(crate) fn check_undo(&self, player: u8) -> Result<(), PlayerActionError> {
+        if !self.history.is_enabled() { return Err(PlayerActionError::UndoDisabled) }
+        if self.turn_phase == TurnPhase::GameOver { return Err(PlayerActionError::GameOver) }
+        if self.history.undo_player() != Some(player) { return Err(PlayerActionError::NothingToUndo) }
+        Ok(())
+    }
+
+    /// Checks a player could redo an undone action
+    pub(crate) fn check_redo(&self, player: u8) -> Result<(), PlayerActionError> {
+        if !self.history.is_enabled() { return Err(PlayerActionError::UndoDisabled) }
+        if self.turn_phase == TurnPhase::GameOver { return Err(PlayerActionError::GameOver) }
+        if self.history.redo_player() != Some(player) { return Err(PlayerActionError::NothingToRedo) }
+        Ok(())
+    }
+
+    /// Undo the last action of a player
+    /// # Overview
+    /// Only actions that reveal no hidden information can be undone. These are staging,
+    /// committing melds and discarding, up until the next player acts. Drawing from the deck
+    /// or taking the discard pile clears the undo history.
+    ///
+    /// Undo must be enabled when building the game with `GameBuilder::undo`.
+    /// # Returns
+    /// - `Ok(())` - The last action of the player was undone
+    /// - `Err(PlayerActionError::UndoDisabled)` - The game does not allow undo
+    /// - `Err(PlayerActionError::NothingToUndo)` - The player has no action that can be undone
+    /// # Example
+    /// ```
+    /// use game_lib::game::CanastaGame;
+    /// let mut game = CanastaGame::builder().players(2).canastas(1).hand().undo().build().unwrap();
+    /// let player = game.get_current_player();
+    /// game.draw(player).unwrap();
+    /// let card = game.get_hand(player).unwrap()[0].id();
+    /// game.discard(player, card).unwrap();
+    /// // the discard can be taken back until the next player acts
+    /// game.undo(player).unwrap();
+    /// assert_eq!(game.get_current_player(), player);
+    /// assert_eq!(game.get_hand(player).unwrap().len(), 16);
+    /// // and done again
+    /// game.redo(player).unwrap();
+    /// assert_eq!(game.get_hand(player).unwrap().len(), 15);
+    /// // once the next player draws the discard is final
+    /// game.draw(game.get_current_player()).unwrap();
+    /// assert!(game.undo(player).is_err());
+    /// ```
+    pub fn undo(&mut self, player: u8) -> Result<(), PlayerActionError> {
+        self.check_undo(player)?;
+        let current = self.snapshot(player);
+        let previous = self.history.undo(current).unwrap();
+        self.restore(previous);
+        self.log.push(player, Action::Undo, ActionOutcome::Undone);
+        self.events.emit(GameEvent::ActionUndone { player });
+        Ok(())
     }
 
-    pub fn meld(&mut self) {
-        todo!()
+    /// Redo the last action a player undid
+    /// # Overview
+    /// Any action applied after an undo clears what can be redone.
+    /// # Returns
+    /// - `Ok(())` - The action was redone
+    /// - `Err(PlayerActionError::UndoDisabled)` - The game does not allow undo
+    /// - `Err(PlayerActionError::NothingToRedo)` - The player has no undone action
+    pub fn redo(&mut self, player: u8) -> Result<(), PlayerActionError> {
+        self.check_redo(player)?;
+        let current = self.snapshot(player);
+        let next = self.history.redo(current).unwrap();
+        self.restore(next);
+        self.log.push(player, Action::Redo, ActionOutcome::Redone);
+        self.events.emit(GameEvent::ActionRedone { player });
+        Ok(())
     }
 
-    pub fn take_discard(&mut self) {
-        todo!()
+    /// Plays the rest of a players turn for them when their time runs out
+    /// # Overview
+    /// `GameEvent::TurnTimedOut` is sent, then any staged cards are returned to the hand,
+    /// a card is drawn if the player has not yet drawn, and the safest card is discarded,
+    /// see `FallbackAgent`. Each action is logged as timed out.
+    /// # Returns
+    /// - `Ok(Vec<ActionOutcome>)` - The outcome of each action played, in order
+    /// - `Err(PlayerActionError)` - It is not the players turn or the game is over
+    /// # Example
+    /// ```
+    /// use game_lib::{game::CanastaGame, action_log::Action};
+    /// let mut game = CanastaGame::builder().players(2).canastas(1).hand().seed(3).build().unwrap();
+    /// let player = game.get_current_player();
+    /// let outcomes = game.time_out(player).unwrap();
+    /// assert_eq!(outcomes.len(), 2);
+    /// assert_ne!(game.get_current_player(), player);
+    /// let last = game.log().last().unwrap();
+    /// assert!(matches!(last.action, Action::Discard(_)) && last.timed_out);
+    /// ```
+    pub fn time_out(&mut self, player: u8) -> Result<Vec<ActionOutcome>, PlayerActionError> {
+        if self.turn_phase == TurnPhase::GameOver { return Err(PlayerActionError::GameOver) }
+        if player != self.current_player { return Err(PlayerActionError::NotPlayerTurn(self.current_player)) }
+        self.events.emit(GameEvent::TurnTimedOut { player });
+        let hand = self.hand_number;
+        let mut agent = FallbackAgent;
+        let mut outcomes = vec![];
+        // the turn is over once play passes on or the hand ends
+        while self.current_player == player && self.hand_number == hand && self.turn_phase != TurnPhase::GameOver {
+            let legal = self.legal_actions();
+            if legal.is_empty() { break }
+            let view = self.player_view(player).expect("Current player is valid");
+            outcomes.push(self.apply(player, agent.choose(&view, &legal))?);
+            self.log.mark_timed_out();
+        }
+        Ok(outcomes)
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A hand that needs no canastas to go out, with the current player holding a meld
+    /// they could stage and one other card, after drawing
+    fn one_card_after_meld() -> (CanastaGame, Vec<u8>, Rank, u8) {
+        for seed in 0.. {
+            let mut game = CanastaGame::builder().players(2).canastas(0).hand().seed(seed).build().unwrap();
+            let player = game.get_current_player();
+            game.draw(player).unwrap();
+            let Some((cards, rank)) = game.legal_actions().into_iter().find_map(|a| match a {
+                Action::Meld { cards, rank } => Some((cards, rank)),
+                _ => None,
+            }) else { continue };
+            let hand: Vec<u8> = game.get_hand(player).unwrap().iter().map(|c| c.id()).collect();
+            let mut others = hand.into_iter().filter(|id| !cards.contains(id));
+            let last = others.next().unwrap();
+            for id in others { game.players[player as usize].throw(id); }
+            return (game, cards, rank, last)
+        }
+        unreachable!()
+    }
+
+    /// A game with undo where the player after the first holds a meld they could
+    /// stage before drawing, with the first player's turn played
+    fn next_player_can_stage() -> (CanastaGame, u8, Vec<u8>, Rank) {
+        for seed in 0.. {
+            let mut game = CanastaGame::builder().players(2).canastas(1).hand().undo().seed(seed).build().unwrap();
+            let first = game.get_current_player();
+            game.draw(first).unwrap();
+            let card = game.get_hand(first).unwrap()[0].id();
+            game.discard(first, card).unwrap();
+            let Some((cards, rank)) = game.legal_actions().into_iter().find_map(|a| match a {
+                Action::Meld { cards, rank } => Some((cards, rank)),
+                _ => None,
+            }) else { continue };
+            return (game, first, cards, rank)
+        }
+        unreachable!()
+    }
+
+    #[test]
+    fn undo_stops_at_the_turn() {
+        let (mut game, first, cards, rank) = next_player_can_stage();
+        let next = game.get_current_player();
+        game.meld(next, cards, rank).unwrap();
+        assert_eq!(game.undo(first), Err(PlayerActionError::NothingToUndo));
+        game.undo(next).unwrap();
+        // the discard stays final after the next player takes back their staging
+        assert_eq!(game.undo(first), Err(PlayerActionError::NothingToUndo));
+        assert_eq!(game.get_current_player(), next);
+        game.redo(next).unwrap();
+        assert!(!game.get_staged(next, rank).unwrap().is_empty());
+    }
+
+    #[test]
+    fn undo_within_a_turn() {
+        let mut game = CanastaGame::builder().players(2).canastas(1).hand().undo().seed(2).build().unwrap();
+        let player = game.get_current_player();
+        assert_eq!(game.undo(player), Err(PlayerActionError::NothingToUndo));
+        game.draw(player).unwrap();
+        // drawing reveals a card so cannot be undone
+        assert_eq!(game.undo(player), Err(PlayerActionError::NothingToUndo));
+        let hand = game.get_hand(player).unwrap().to_vec();
+        game.discard(player, hand[0].id()).unwrap();
+        game.undo(player).unwrap();
+        assert_eq!(game.get_hand(player).unwrap(), hand);
+        assert_eq!(game.get_phase(), TurnPhase::Meld);
+        game.redo(player).unwrap();
+        assert_eq!(game.redo(player), Err(PlayerActionError::NothingToRedo));
+    }
+
+    #[test]
+    fn undo_disabled() {
+        let mut game = CanastaGame::builder().players(2).canastas(1).hand().seed(2).build().unwrap();
+        let player = game.get_current_player();
+        game.draw(player).unwrap();
+        let card = game.get_hand(player).unwrap()[0].id();
+        game.discard(player, card).unwrap();
+        assert_eq!(game.undo(player), Err(PlayerActionError::UndoDisabled));
+    }
+
+    #[test]
+    fn cannot_discard_while_staged() {
+        let (mut game, cards, rank, last) = one_card_after_meld();
+        let player = game.get_current_player();
+        game.meld(player, cards, rank).unwrap();
+        assert_eq!(game.get_hand(player).unwrap().len(), 1);
+        assert_eq!(game.discard(player, last), Err(PlayerActionError::CardsStaged));
+        assert!(!game.legal_actions().iter().any(|a| matches!(a, Action::Discard(_))));
+        assert_eq!(game.get_phase(), TurnPhase::Meld);
+
+        // with the staged cards back in hand the discard is an ordinary one
+        game.clear_meld(player).unwrap();
+        game.discard(player, last).unwrap();
+        assert_eq!(game.get_phase(), TurnPhase::Draw);
+        assert_ne!(game.get_current_player(), player);
+    }
+
+    #[test]
+    fn deal_accounts_for_every_card() {
+        for (players, deal) in [(2, 15), (3, 13), (4, 11)] {
+            for seed in 0..10 {
+                let game = CanastaGame::builder().players(players).canastas(1).hand().seed(seed).build().unwrap();
+                let table = game.spectator_view().table;
+                let mut cards = table.stock_count + table.discard_size;
+                for seat in &table.seats {
+                    assert_eq!(seat.hand_size, deal);
+                    // red threes are laid and replaced straight away
+                    assert!(!game.player(seat.player).get_hand().iter().any(|c| c.is_red_three()));
+                    cards += seat.hand_size + seat.red_threes.len();
+                }
+                assert_eq!(cards, 108);
+            }
+        }
+    }
+
+    #[test]
+    fn turn_order_is_enforced() {
+        let mut game = CanastaGame::builder().players(3).canastas(1).hand().seed(4).build().unwrap();
+        let player = game.get_current_player();
+        let other = (player + 1) % 3;
+        assert_eq!(game.draw(other).err(), Some(PlayerActionError::NotPlayerTurn(player)));
+        let card = game.get_hand(player).unwrap()[0].id();
+        assert_eq!(game.discard(player, card).err(), Some(PlayerActionError::IncorrectTurnPhase));
+        game.draw(player).unwrap();
+        assert_eq!(game.draw(player).err(), Some(PlayerActionError::IncorrectTurnPhase));
+        assert_eq!(game.discard(player, 255).err(), Some(PlayerActionError::InvalidCard));
+        game.discard(player, card).unwrap();
+        assert_eq!(game.get_current_player(), other);
+    }
 
+    /// A game after the current player draws, left holding a single card
+    fn last_card(canastas: u8) -> (CanastaGame, u8) {
+        let mut game = CanastaGame::builder().players(2).canastas(canastas).hand().seed(6).build().unwrap();
+        let player = game.get_current_player();
+        game.draw(player).unwrap();
+        let hand: Vec<u8> = game.get_hand(player).unwrap().iter().map(|c| c.id()).collect();
+        for id in &hand[1..] { game.players[player as usize].throw(*id); }
+        (game, hand[0])
+    }
 
+    #[test]
+    fn going_out_needs_canastas() {
+        let (mut game, card) = last_card(1);
+        let player = game.get_current_player();
+        assert_eq!(game.discard(player, card), Err(PlayerActionError::CannotGoOut));
+        assert_eq!(game.get_hand(player).unwrap().len(), 1);
+
+        let (mut game, card) = last_card(0);
+        game.discard(player, card).unwrap();
+        assert!(game.get_hand(player).unwrap().is_empty());
+        assert_eq!(game.get_phase(), TurnPhase::GameOver);
+    }
+}
@@ -1,10 +1,13 @@
-use crate::game::CanastaGame;
+use crate::game::{CanastaGame, PLAYERS};
 
 
+#[derive(Clone, Debug, Default)]
 pub struct GameBuilder {
     num_players: Option<u8>,
     num_canastas: Option<u8>,
     full_game: Option<bool>,
+    seed: Option<u64>,
+    undo: bool,
 }
 
 impl GameBuilder {
@@ -13,6 +16,8 @@ impl GameBuilder {
             num_players: None,
             num_canastas: None,
             full_game: None,
+            seed: None,
+            undo: false,
         }
     }
     
@@ -36,11 +41,59 @@ impl GameBuilder {
         self
     }
 
+    /// Seed used to shuffle the decks, the same seed and actions always give the same game
+    ///
+    /// If no seed is given a random one is used
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Allow players to undo actions that reveal no hidden information
+    ///
+    /// Intended for casual and teaching games, see `CanastaGame::undo`
+    pub fn undo(mut self) -> Self {
+        self.undo = true;
+        self
+    }
+
+    /// Deals the game
+    /// # Returns
+    /// - `Some(CanastaGame)` - The game, ready for the first player to draw
+    /// - `None` - The players, canastas or kind of game were not given, or the number of
+    ///   players is outside `PLAYERS`
     pub fn build(&mut self) -> Option<CanastaGame> {
-        let players = self.num_players?;
+        let players = self.num_players.filter(|p| PLAYERS.contains(p))?;
         let canastas = self.num_canastas?;
         let full_game = self.full_game?;
+        let seed = self.seed.unwrap_or_else(rand::random);
+
+        Some(CanastaGame::new(players, canastas, full_game, seed, self.undo))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(players: u8) -> Option<CanastaGame> {
+        CanastaGame::builder().players(players).canastas(1).hand().seed(1).build()
+    }
+
+    #[test]
+    fn player_count_is_checked() {
+        for players in [0, 1, 5, 128, 255] {
+            assert!(build(players).is_none(), "{players} players");
+        }
+        for players in PLAYERS {
+            assert_eq!(build(players).unwrap().num_players(), players);
+        }
+    }
 
-        Some(CanastaGame::new(players, canastas, full_game))
+    #[test]
+    fn missing_settings() {
+        assert!(CanastaGame::builder().players(2).canastas(1).build().is_none());
+        assert!(CanastaGame::builder().players(2).hand().build().is_none());
+        assert!(CanastaGame::builder().canastas(1).hand().build().is_none());
     }
 }
@@ -0,0 +1,316 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    game::{CanastaGame, TurnPhase, initial_meld_requirement},
+    action_log::Action,
+    agents::{Agent, HeuristicAgent},
+    card::{PlayCard, Rank},
+    card_collections::meld::CANASTA_SIZE,
+    view::PlayerView,
+    errors::game_error::GameError,
+};
+
+/// A suggested action with the reasons for it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hint {
+    pub action: Action,
+    /// Plain text explanation of why the action is suggested
+    pub explanation: String,
+    /// Other legal actions ranked from best to worst by the evaluator, with their scores
+    pub alternatives: Vec<(Action, f64)>,
+}
+
+/// Suggests an action for a player from what they can see
+/// # Overview
+/// The legal actions are found from a determinization of the view, which has the same
+/// legal actions as the real game as they only depend on the players own cards and the
+/// table. The action is chosen by a `HeuristicAgent` and explained in plain text.
+/// # Returns
+/// - `Some(Hint)` - It is the players turn and the game is not over
+/// - `None` - There is nothing for the player to do
+/// # Example
+/// ```
+/// use game_lib::{game::CanastaGame, hint::suggest};
+/// let game = CanastaGame::builder().players(2).canastas(1).hand().seed(4).build().unwrap();
+/// let player = game.get_current_player();
+/// let hint = suggest(&game.player_view(player).unwrap()).unwrap();
+/// assert!(game.legal_actions().contains(&hint.action));
+/// println!("{}", hint.explanation);
+/// ```
+pub fn suggest(view: &PlayerView) -> Option<Hint> {
+    if !view.is_turn() || view.table.phase == TurnPhase::GameOver { return None }
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    let game = CanastaGame::determinize(view, &mut rng).ok()?;
+    explain(view, &game.legal_actions())
+}
+
+impl CanastaGame {
+    /// Suggests an action for the player with an explanation, see `hint::suggest`
+    /// # Returns
+    /// - `Ok(Some(Hint))` - The suggested action
+    /// - `Ok(None)` - It is not the players turn or the game is over
+    /// - `Err(GameError::InvalidPlayer)` - The player does not exist
+    pub fn hint(&self, player: u8) -> Result<Option<Hint>, GameError> {
+        let view = self.player_view(player)?;
+        if !view.is_turn() || self.get_phase() == TurnPhase::GameOver { return Ok(None) }
+        Ok(explain(&view, &self.legal_actions()))
+    }
+}
+
+fn explain(view: &PlayerView, legal: &[Action]) -> Option<Hint> {
+    if legal.is_empty() { return None }
+    let mut agent = HeuristicAgent::default();
+    let action = agent.choose(view, legal);
+    let mut alternatives: Vec<(Action, f64)> = legal.iter()
+        .filter(|a| **a != action)
+        .map(|a| (a.clone(), agent.score(view, a)))
+        .collect();
+    alternatives.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let explanation = match &action {
+        Action::Draw => explain_draw(view, legal),
+        Action::TakeDiscard(cards) => explain_take(view, cards),
+        Action::Meld { cards, rank } => explain_meld(view, cards, *rank),
+        Action::CommitMeld => explain_commit(view),
+        Action::ClearMeld => "Your staged cards cannot be committed as they are, return them to your hand.".to_string(),
+        Action::Discard(card) => explain_discard(view, *card),
+        Action::Unmeld(_) | Action::Undo | Action::Redo => String::new(),
+    };
+    Some(Hint { action, explanation, alternatives })
+}
+
+fn explain_draw(view: &PlayerView, legal: &[Action]) -> String {
+    let table = &view.table;
+    let Some(top) = &table.discard_top else {
+        return "Draw from the stock, the discard pile is empty.".to_string()
+    };
+    if !legal.iter().any(|a| matches!(a, Action::TakeDiscard(_))) {
+        if top.is_wild() || top.is_black_three() {
+            return format!("Draw from the stock, the {} on top blocks the discard pile.", card_name(top))
+        }
+        if table.pile_frozen || view.seat().melds.is_empty() {
+            return format!(
+                "Draw from the stock, the pile is frozen so you need two natural {} to take it.",
+                plural(*top.rank()),
+            )
+        }
+        return format!("Draw from the stock, you cannot use the {} on top of the pile.", card_name(top))
+    }
+    format!(
+        "Draw from the stock, taking the pile of {} cards would cost you too many wild cards.",
+        table.discard_size,
+    )
+}
+
+fn explain_take(view: &PlayerView, cards: &[u8]) -> String {
+    let table = &view.table;
+    let Some(top) = &table.discard_top else { return String::new() };
+    let rank = *top.rank();
+    let existing = view.seat().melds.iter().find(|m| m.rank == rank).map_or(0, |m| m.cards.len());
+    let used = hand_cards(view, cards);
+    let wilds = used.iter().filter(|c| c.is_wild()).count();
+    let meld_size = existing + used.len() + 1;
+    let mut text = format!("Taking the pile gives you {} cards", table.discard_size);
+    if meld_size >= CANASTA_SIZE && existing < CANASTA_SIZE {
+        text += &format!(" and completes a canasta of {}", plural(rank));
+    } else if CANASTA_SIZE - meld_size.min(CANASTA_SIZE) <= 2 {
+        text += &format!(" and a near canasta of {} with {} cards", plural(rank), meld_size);
+    } else {
+        text += &format!(" and a meld of {} {}", meld_size, plural(rank));
+    }
+    if wilds > 0 {
+        text += &format!(". It uses {} wild card{}", wilds, if wilds == 1 { "" } else { "s" });
+    }
+    text + "."
+}
+
+fn explain_meld(view: &PlayerView, cards: &[u8], rank: Rank) -> String {
+    let used = hand_cards(view, cards);
+    let existing = view.seat().melds.iter().find(|m| m.rank == rank).map_or(0, |m| m.cards.len());
+    let staged = view.staged.iter().find(|(r, _)| *r == rank).map_or(0, |(_, c)| c.len());
+    let size = existing + staged + used.len();
+    let mut text = if existing > 0 {
+        format!("Add {} to your meld of {}", count_cards(&used, rank), plural(rank))
+    } else if staged > 0 {
+        format!("Add {} to your staged {}", count_cards(&used, rank), plural(rank))
+    } else {
+        format!("Stage a meld of {}", count_cards(&used, rank))
+    };
+    if size >= CANASTA_SIZE && existing + staged < CANASTA_SIZE {
+        text += ", completing a canasta";
+    } else if size < CANASTA_SIZE {
+        text += &format!(", {} card{} from a canasta", CANASTA_SIZE - size, if CANASTA_SIZE - size == 1 { "" } else { "s" });
+    }
+    if view.seat().melds.is_empty() {
+        let required = initial_meld_requirement(view.seat().score);
+        let points: i32 = view.staged.iter().flat_map(|(_, c)| c).chain(used.iter().copied())
+            .map(|c| c.value() as i32)
+            .sum();
+        text += &if points >= required {
+            format!(". Your staged cards are worth {points} points, enough for the {required} needed for your first meld")
+        } else {
+            format!(". Your staged cards are worth {points} of the {required} points needed for your first meld")
+        };
+    }
+    text + "."
+}
+
+fn explain_commit(view: &PlayerView) -> String {
+    let cards: Vec<&PlayCard> = view.staged.iter().flat_map(|(_, c)| c).collect();
+    let points: i32 = cards.iter().map(|c| c.value() as i32).sum();
+    let ranks: Vec<String> = view.staged.iter().map(|(rank, _)| plural(*rank)).collect();
+    format!("Commit your melds of {} to the table, worth {} points.", join(&ranks), points)
+}
+
+fn explain_discard(view: &PlayerView, card_id: u8) -> String {
+    let Some(card) = view.hand.iter().find(|c| c.id() == card_id) else { return String::new() };
+    let name = card_name(card);
+    if card.is_wild() {
+        return format!(
+            "Discard the {name} to freeze the pile of {} cards so your opponents need a natural pair to take it.",
+            view.table.discard_size + 1,
+        )
+    }
+    if card.is_black_three() {
+        return format!("Discard the {name} to stop the next player taking the pile.")
+    }
+    let rank = *card.rank();
+    let mut reasons = vec![];
+    if !view.opponents().any(|o| o.melds.iter().any(|m| m.rank == rank)) {
+        reasons.push(format!("no opponent has melded {}", plural(rank)));
+    }
+    if !view.opponents().any(|o| o.known_cards.iter().any(|c| *c.rank() == rank)) {
+        reasons.push(format!("no opponent is known to hold {}", plural(rank)));
+    }
+    let pairs = view.hand.iter().filter(|c| c.id() != card_id && *c.rank() == rank).count();
+    if pairs == 0 {
+        reasons.push(format!("it is your only {}", rank.name()));
+    }
+    if card.value() <= 5 {
+        reasons.push("it is worth few points".to_string());
+    }
+    if reasons.is_empty() {
+        return format!("Discard the {name}, it is the card you can best spare.")
+    }
+    format!("Discard the {name}, {}.", join(&reasons))
+}
+
+fn hand_cards<'a>(view: &'a PlayerView, ids: &[u8]) -> Vec<&'a PlayCard> {
+    view.hand.iter().filter(|c| ids.contains(&c.id())).collect()
+}
+
+fn card_name(card: &PlayCard) -> String {
+    match card.rank() {
+        Rank::Joker => "Joker".to_string(),
+        rank => format!("{} of {}", rank.name(), card.suit().name()),
+    }
+}
+
+/// Describes a set of cards for a meld, such as `3 Kings and a Joker`
+fn count_cards(cards: &[&PlayCard], rank: Rank) -> String {
+    let naturals = cards.iter().filter(|c| *c.rank() == rank).count();
+    let mut parts = vec![];
+    let wilds = [Rank::Two, Rank::Joker].map(|w| (w, cards.iter().filter(|c| *c.rank() == w).count()));
+    for (kind, count) in [(rank, naturals)].into_iter().chain(wilds) {
+        match count {
+            0 => {}
+            1 => parts.push(format!("{} {}", if matches!(kind, Rank::Ace | Rank::Eight) { "an" } else { "a" }, kind.name())),
+            _ => parts.push(format!("{} {}", count, plural(kind))),
+        }
+    }
+    join(&parts)
+}
+
+fn plural(rank: Rank) -> String {
+    match rank {
+        Rank::Six => "Sixes".to_string(),
+        rank => format!("{}s", rank.name()),
+    }
+}
+
+/// Joins items as `a`, `a and b` or `a, b and c`
+fn join(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [one] => one.clone(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agents::RandomAgent;
+
+    use super::*;
+
+    /// Calls `check` with the view of the current player at every point of a few random games
+    fn each_turn(mut check: impl FnMut(&CanastaGame, &PlayerView, &[Action])) {
+        for seed in 0..4 {
+            let mut game = CanastaGame::builder().players(2 + (seed % 2) as u8).canastas(1).hand().seed(seed).build().unwrap();
+            let mut agent = RandomAgent::new(seed);
+            while game.get_phase() != TurnPhase::GameOver {
+                let player = game.get_current_player();
+                let view = game.player_view(player).unwrap();
+                let legal = game.legal_actions();
+                check(&game, &view, &legal);
+                game.apply(player, agent.choose(&view, &legal)).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn suggestions_are_legal() {
+        each_turn(|game, view, legal| {
+            let hint = suggest(view).unwrap();
+            assert!(legal.contains(&hint.action), "{:?} is not legal", hint.action);
+            assert!(hint.alternatives.iter().all(|(a, _)| legal.contains(a) && *a != hint.action));
+            assert_eq!(game.hint(view.player).unwrap().unwrap().action, hint.action);
+            let waiting = (view.player + 1) % game.num_players();
+            assert_eq!(suggest(&game.player_view(waiting).unwrap()), None);
+        });
+    }
+
+    #[test]
+    fn explanations_name_the_cards() {
+        each_turn(|_, view, legal| {
+            for action in legal {
+                match action {
+                    Action::Discard(id) => {
+                        let card = view.hand.iter().find(|c| c.id() == *id).unwrap();
+                        assert!(explain_discard(view, *id).contains(&card_name(card)));
+                    }
+                    Action::Meld { cards, rank } => {
+                        let text = explain_meld(view, cards, *rank);
+                        assert!(text.contains(rank.name()), "{text}");
+                        for card in hand_cards(view, cards).iter().filter(|c| c.is_wild()) {
+                            assert!(text.contains(card.rank().name()), "{text}");
+                        }
+                    }
+                    Action::TakeDiscard(cards) => {
+                        let text = explain_take(view, cards);
+                        let top = view.table.discard_top.as_ref().unwrap();
+                        assert!(text.contains(top.rank().name()), "{text}");
+                        assert!(text.contains(&format!("{} cards", view.table.discard_size)), "{text}");
+                    }
+                    Action::CommitMeld => {
+                        let text = explain_commit(view);
+                        assert!(view.staged.iter().all(|(rank, _)| text.contains(rank.name())), "{text}");
+                    }
+                    _ => {}
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn cards_are_counted_in_words() {
+        let cards: Vec<PlayCard> = ["8H", "8S", "8C", "2D", "JK"].iter().map(|c| c.parse().unwrap()).collect();
+        let refs: Vec<&PlayCard> = cards.iter().collect();
+        assert_eq!(count_cards(&refs, Rank::Eight), "3 Eights, a Two and a Joker");
+        assert_eq!(count_cards(&refs[..1], Rank::Eight), "an Eight");
+        assert_eq!(plural(Rank::Six), "Sixes");
+        assert_eq!(card_name(&cards[0]), "Eight of Hearts");
+        assert_eq!(card_name(&cards[4]), "Joker");
+        assert_eq!(join(&["a".to_string(), "b".to_string(), "c".to_string()]), "a, b and c");
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{player::Player, card_collections::discard::Discard, game::TurnPhase};
+
+/// The parts of a game an undoable action can change
+///
+/// Undoable actions never touch the deck so it does not need to be saved.
+#[derive(Clone)]
+pub(crate) struct Snapshot {
+    /// Player that made the action
+    pub(crate) player: u8,
+    pub(crate) players: Vec<Player>,
+    pub(crate) discard: Discard,
+    pub(crate) current_player: u8,
+    pub(crate) turn_phase: TurnPhase,
+}
+
+/// Undo and redo stacks for a game
+///
+/// Only actions that reveal no hidden information are kept. Any other action,
+/// such as drawing from the deck, clears both stacks. Only the actions of the player
+/// who acted last are kept, once another player acts every earlier action is final.
+#[derive(Clone, Default)]
+pub(crate) struct History {
+    enabled: bool,
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+impl History {
+    pub(crate) fn new(enabled: bool) -> Self {
+        Self { enabled, undo: vec![], redo: vec![] }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Records that an action was applied
+    ///
+    /// `before` is the state before the action if it can be undone, or `None`
+    /// if it cannot, which clears the history. Either way redo is no longer possible.
+    pub(crate) fn record(&mut self, before: Option<Snapshot>) {
+        self.redo.clear();
+        match before {
+            Some(snapshot) if self.enabled => {
+                if self.undo_player().is_some_and(|p| p != snapshot.player) { self.undo.clear() }
+                self.undo.push(snapshot)
+            }
+            _ => self.undo.clear(),
+        }
+    }
+
+    /// Player that made the most recent undoable action
+    pub(crate) fn undo_player(&self) -> Option<u8> {
+        self.undo.last().map(|s| s.player)
+    }
+
+    /// Player that made the most recent undone action
+    pub(crate) fn redo_player(&self) -> Option<u8> {
+        self.redo.last().map(|s| s.player)
+    }
+
+    /// Swaps the current state for the one before the last action
+    pub(crate) fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    /// Swaps the current state for the one before the last undo
+    pub(crate) fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        Some(next)
+    }
+}
@@ -0,0 +1,182 @@
+use crate::{game::{CanastaGame, TurnPhase}, action_log::Action, card::{PlayCard, Rank}, card_collections::meld::MAX_WILDS};
+
+/// Every rank a meld can be made of
+pub(crate) const MELD_RANKS: [Rank; 12] = [
+    Rank::Ace, Rank::Three, Rank::Four, Rank::Five, Rank::Six, Rank::Seven,
+    Rank::Eight, Rank::Nine, Rank::Ten, Rank::Jack, Rank::Queen, Rank::King,
+];
+
+/// Every distinct set of cards from a hand that could go into a meld of a rank
+///
+/// Cards of the same rank are interchangeable so only the first cards of each kind are
+/// used. Sets are made of some number of naturals, twos and jokers with no more wilds
+/// than a meld allows. The empty set is included.
+pub(crate) fn card_sets(hand: &[PlayCard], rank: Rank) -> Vec<Vec<u8>> {
+    let naturals: Vec<u8> = hand.iter().filter(|c| *c.rank() == rank && !c.is_red_three()).map(|c| c.id()).collect();
+    let (twos, jokers): (Vec<u8>, Vec<u8>) = if rank == Rank::Three { (vec![], vec![]) } else {
+        (
+            hand.iter().filter(|c| *c.rank() == Rank::Two).map(|c| c.id()).collect(),
+            hand.iter().filter(|c| *c.rank() == Rank::Joker).map(|c| c.id()).collect(),
+        )
+    };
+    let mut sets = vec![];
+    for n in 0..=naturals.len() {
+        for t in 0..=twos.len().min(MAX_WILDS) {
+            for j in 0..=jokers.len().min(MAX_WILDS - t) {
+                let mut set = naturals[..n].to_vec();
+                set.extend_from_slice(&twos[..t]);
+                set.extend_from_slice(&jokers[..j]);
+                sets.push(set);
+            }
+        }
+    }
+    sets
+}
+
+impl CanastaGame {
+    /// Lists every legal action for the current player
+    /// # Overview
+    /// Each action is checked with `validate`, the same validation used when it is
+    /// applied, so every action returned will succeed.
+    ///
+    /// - `Draw` phase - drawing, and taking the discard pile with each set of hand
+    ///   cards that would allow it
+    /// - `Meld` phase - committing the staged melds if they are valid, and discarding
+    ///   each card in hand once nothing is staged
+    ///
+    /// In both phases every set of cards that could be staged to give a valid new meld, or
+    /// be laid off on an existing meld, is included along with clearing any staged cards.
+    ///
+    /// Sets are made from the first cards of each kind in hand, as identical cards are
+    /// interchangeable. Undo and redo are not included as they do not progress the game.
+    /// # Example
+    /// ```
+    /// use game_lib::{game::CanastaGame, action_log::Action};
+    /// let game = CanastaGame::quick_hand();
+    /// let actions = game.legal_actions();
+    /// assert!(actions.contains(&Action::Draw));
+    /// assert!(!actions.iter().any(|a| matches!(a, Action::Discard(_))));
+    /// ```
+    pub fn legal_actions(&self) -> Vec<Action> {
+        let player = self.get_current_player();
+        let mut actions = vec![];
+        let hand = match self.get_phase() {
+            TurnPhase::Draw | TurnPhase::Meld => self.player(player).get_hand(),
+            TurnPhase::TurnOver | TurnPhase::GameOver => return actions,
+        };
+        let mut candidates = vec![];
+        if self.get_phase() == TurnPhase::Draw {
+            candidates.push(Action::Draw);
+            if let Some(top) = self.discard_top() {
+                candidates.extend(card_sets(hand, *top.rank()).into_iter().map(Action::TakeDiscard));
+            }
+        } else {
+            candidates.push(Action::CommitMeld);
+            candidates.extend(hand.iter().map(|c| Action::Discard(c.id())));
+        }
+        for rank in MELD_RANKS {
+            for cards in card_sets(hand, rank) {
+                if cards.is_empty() { continue }
+                if self.player(player).check_stage_valid(rank, &cards).is_err() { continue }
+                candidates.push(Action::Meld { cards, rank });
+            }
+        }
+        if self.player(player).has_staged() { candidates.push(Action::ClearMeld) }
+        actions.extend(candidates.into_iter().filter(|a| self.validate(player, a).is_ok()));
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agents::{Agent, RandomAgent};
+
+    use super::*;
+
+    /// What the games played by `every_action_applies` went through
+    #[derive(Default)]
+    struct Seen {
+        takes: usize,
+        frozen_takes: usize,
+        initial_melds: usize,
+    }
+
+    #[test]
+    fn every_action_applies() {
+        let mut seen = Seen::default();
+        for seed in 0..8 {
+            let players = 2 + (seed % 3) as u8;
+            let mut game = CanastaGame::builder().players(players).canastas(1).hand().seed(seed).build().unwrap();
+            let mut agent = RandomAgent::new(seed);
+            while game.get_phase() != TurnPhase::GameOver {
+                let player = game.get_current_player();
+                let legal = game.legal_actions();
+                assert!(!legal.is_empty());
+                let melded = game.player(player).has_melded();
+                let pile_frozen = game.player_view(player).unwrap().table.pile_frozen;
+                // the pile is also frozen for a player who has not melded
+                let frozen = pile_frozen || !melded;
+                for action in &legal {
+                    let mut trial = game.clone();
+                    trial.apply(player, action.clone()).unwrap_or_else(|e| panic!("{action:?} failed: {e}"));
+                    match action {
+                        Action::TakeDiscard(cards) => {
+                            seen.takes += 1;
+                            if pile_frozen { seen.frozen_takes += 1 }
+                            if frozen {
+                                let top = *game.discard_top().unwrap().rank();
+                                let naturals = game.player(player).get_hand().iter()
+                                    .filter(|c| cards.contains(&c.id()) && *c.rank() == top)
+                                    .count();
+                                assert!(naturals >= 2, "frozen pile taken with {naturals} naturals");
+                            }
+                        }
+                        Action::CommitMeld if !melded => {
+                            seen.initial_melds += 1;
+                            assert!(trial.player(player).has_melded());
+                        }
+                        _ => {}
+                    }
+                }
+                let view = game.player_view(player).unwrap();
+                game.apply(player, agent.choose(&view, &legal)).unwrap();
+            }
+        }
+        assert!(seen.takes > 0 && seen.frozen_takes > 0 && seen.initial_melds > 0);
+    }
+
+    #[test]
+    fn actions_follow_the_phase() {
+        let mut game = CanastaGame::builder().players(2).canastas(1).hand().seed(3).build().unwrap();
+        let player = game.get_current_player();
+        let draw = game.legal_actions();
+        assert!(draw.contains(&Action::Draw));
+        assert!(!draw.iter().any(|a| matches!(a, Action::Discard(_) | Action::CommitMeld | Action::ClearMeld)));
+
+        game.apply(player, Action::Draw).unwrap();
+        let meld = game.legal_actions();
+        assert!(!meld.iter().any(|a| matches!(a, Action::Draw | Action::TakeDiscard(_))));
+        let hand = game.get_hand(player).unwrap();
+        assert_eq!(meld.iter().filter(|a| matches!(a, Action::Discard(_))).count(), hand.len());
+        // staging leaves only meld actions, clearing and committing
+        if let Some(stage) = meld.iter().find(|a| matches!(a, Action::Meld { .. })) {
+            game.apply(player, stage.clone()).unwrap();
+            let staged = game.legal_actions();
+            assert!(staged.contains(&Action::ClearMeld));
+            assert!(!staged.iter().any(|a| matches!(a, Action::Discard(_))));
+        }
+    }
+
+    #[test]
+    fn card_sets_limit_wilds() {
+        let hand: Vec<PlayCard> = ["KH", "KS", "2C", "2D", "2H", "2S", "JK", "3H"].iter().map(|c| c.parse().unwrap()).collect();
+        let sets = card_sets(&hand, Rank::King);
+        assert!(sets.contains(&vec![]));
+        for set in &sets {
+            let wilds = hand.iter().filter(|c| set.contains(&c.id()) && c.is_wild()).count();
+            assert!(wilds <= MAX_WILDS);
+        }
+        // threes never take wild cards, and red threes are never melded
+        assert_eq!(card_sets(&hand, Rank::Three), vec![vec![]]);
+    }
+}
@@ -2,5 +2,21 @@ pub mod game;
 pub mod game_builder;
 pub mod card;
 pub mod errors;
+pub mod action_log;
+pub mod replay;
+pub mod events;
+pub mod view;
+pub mod agents;
+pub mod simulation;
+pub mod tournament;
+pub mod env;
+pub mod hint;
+pub mod record;
+pub mod clock;
+pub mod registry;
+pub mod store;
 pub(crate) mod card_collections;
 pub(crate) mod player;
+pub(crate) mod history;
+pub(crate) mod legal_moves;
+pub(crate) mod determinize;
@@ -1,138 +1,327 @@
-use std::collections::BTreeMap;
-use crate::{card::{PlayCard, Suit, Rank}, card_collections::meld::Meld, errors::internal_meld_error::InternalMeldError};
+use crate::{card::{PlayCard, Rank}, card_collections::meld::{Meld, check_composition, CANASTA_SIZE}, errors::internal_meld_error::InternalMeldError};
 
+#[derive(Clone)]
 pub(crate) struct Player {
     id: u8,
     hand: Vec<PlayCard>,
     melds: [Option<Meld>; 13],
     temp_melds: [Vec<PlayCard>; 13],
     red_threes: Vec<PlayCard>,
+    score: i32,
+    /// IDs of cards every player has seen go into the hand
+    known: Vec<u8>,
+}
+
+/// Result of checking the staged melds of a player can be committed
+pub(crate) struct CommitSummary {
+    /// Point value of the staged cards
+    pub(crate) value: i32,
+    /// Number of canastas the player would have after committing
+    pub(crate) canastas: usize,
+    /// Number of cards that would be left in hand
+    pub(crate) cards_left: usize,
+    /// If a meld of black threes is staged
+    pub(crate) black_threes: bool,
+}
+
+/// Index into the meld arrays for a rank, wild ranks cannot be melded
+pub(crate) fn meld_index(rank: &Rank) -> Option<usize> {
+    match rank {
+        Rank::Two | Rank::Joker => None,
+        rank => Some(Into::<usize>::into(rank) - 1),
+    }
 }
 
 impl Player {
     pub(crate) fn new(id: u8) -> Self {
-        Self { 
+        Self {
             id,
             hand: vec![],
             melds: Default::default(),
             temp_melds: Default::default(),
             red_threes: vec![],
+            score: 0,
+            known: vec![],
         }
     }
 
+    /// Creates a player part way through a hand
+    pub(crate) fn from_parts(id: u8, hand: Vec<PlayCard>, melds: Vec<Meld>, red_threes: Vec<PlayCard>, score: i32, known: Vec<u8>) -> Self {
+        let mut player = Self::new(id);
+        player.hand = hand;
+        for meld in melds {
+            let index = meld_index(meld.rank()).expect("Meld rank must be meldable");
+            player.melds[index] = Some(meld);
+        }
+        player.red_threes = red_threes;
+        player.score = score;
+        player.known = known;
+        player
+    }
+
+    pub(crate) fn id(&self) -> u8 {
+        self.id
+    }
+
+    /// Clears all cards from the player ready for a new hand
+    pub(crate) fn reset_hand(&mut self) {
+        self.hand.clear();
+        self.melds = Default::default();
+        self.temp_melds = Default::default();
+        self.red_threes.clear();
+        self.known.clear();
+    }
+
     pub(crate) fn add_hand(&mut self, card: PlayCard) -> &PlayCard {
         self.hand.push(card);
         self.hand.last().unwrap()
     }
-    
-    /// Melds red threes in a players hand 
-    /// Returns the number of cards melded 
-    pub(crate) fn meld_red_threes(&mut self) -> u8 {
-        let mut counter = 0;
+
+    /// Melds red threes in a players hand
+    /// Returns the IDs of the cards melded
+    pub(crate) fn meld_red_threes(&mut self) -> Vec<u8> {
+        let mut melded = vec![];
         for i in (0..self.hand.len()).rev() {
-            if self.hand[i].is_red_three() { 
+            if self.hand[i].is_red_three() {
+                melded.push(self.hand[i].id());
                 self.red_threes.push(self.hand.remove(i))
             }
         }
-        counter 
+        melded
     }
 
     pub(crate) fn get_hand(&self) -> &[PlayCard] {
         &self.hand
     }
 
+    /// Marks cards as known to every player, such as cards picked up from the discard pile
+    pub(crate) fn reveal(&mut self, ids: impl IntoIterator<Item = u8>) {
+        self.known.extend(ids);
+    }
+
+    /// Cards in the hand, including staged cards, that every player knows are there
+    ///
+    /// Cards stop being known once they leave the hand by being melded or discarded.
+    pub(crate) fn known_cards(&self) -> impl Iterator<Item = &PlayCard> {
+        self.hand.iter()
+            .chain(self.temp_melds.iter().flatten())
+            .filter(|c| self.known.contains(&c.id()))
+    }
+
+    pub(crate) fn red_threes(&self) -> &[PlayCard] {
+        &self.red_threes
+    }
+
+    pub(crate) fn score(&self) -> i32 {
+        self.score
+    }
+
+    pub(crate) fn add_score(&mut self, points: i32) {
+        self.score += points;
+    }
+
+    /// Iterates the committed melds of the player
+    pub(crate) fn melds(&self) -> impl Iterator<Item = &Meld> {
+        self.melds.iter().flatten()
+    }
+
+    /// Returns the committed meld of a rank if there is one
+    pub(crate) fn get_meld(&self, rank: &Rank) -> Option<&Meld> {
+        meld_index(rank).and_then(|i| self.melds[i].as_ref())
+    }
+
+    /// If the player has made their initial meld this hand
+    pub(crate) fn has_melded(&self) -> bool {
+        self.melds().next().is_some()
+    }
+
+    pub(crate) fn canastas(&self) -> usize {
+        self.melds().filter(|m| m.is_canasta()).count()
+    }
+
     /// Throw the card of some ID
     ///
-    /// If the card is found it is removed from the hand and returned 
+    /// If the card is found it is removed from the hand and returned
     ///
-    /// If it is not then `None` will be returned 
+    /// If it is not then `None` will be returned
     pub(crate) fn discard(&mut self, card_id: u8) -> Option<PlayCard> {
-        for i in 0..self.hand.len() {
-            if self.hand[i].id() == card_id {
-                return Some(self.hand.remove(i))
-            }
-        }
-        None
+        let index = self.hand.iter().position(|c| c.id() == card_id)?;
+        Some(self.hand.remove(index))
+    }
+
+    /// Throws a card from the hand so that it is no longer known to be held
+    pub(crate) fn throw(&mut self, card_id: u8) -> Option<PlayCard> {
+        let card = self.discard(card_id)?;
+        self.known.retain(|id| *id != card_id);
+        Some(card)
     }
 
-    /// attempts to meld a list of card IDs 
+    /// attempts to meld a list of card IDs
     ///
-    /// If the card cannot be melded or the given ID is not in the players hand 
-    /// then the whole operation will fail and an error will be returned 
+    /// If the card cannot be melded or the given ID is not in the players hand
+    /// then the whole operation will fail and an error will be returned
     pub(crate) fn meld(&mut self, cards: Vec<u8>, rank: Rank) -> Result<(), InternalMeldError> {
-        let mut to_meld: Vec<PlayCard> = Vec::new();
-        for to_meld_id in &cards {
-            let hand_index = match self.hand.iter().position(|c| c.id() == *to_meld_id) {
-                Some(i) => i,
-                None => {
-                    self.hand.extend(to_meld);
-                    return Err(InternalMeldError::InvalidCardId(*to_meld_id))
-                }
-            };
-            let card = &self.hand[hand_index];
-            if card.is_wild() { to_meld.push(self.hand.remove(hand_index)); }
-            else if card.is_red_three() { 
-                self.hand.extend(to_meld.drain(..));
-                return Err(InternalMeldError::InvalidCardToMeld(*to_meld_id))
-            }
-            else if *card.rank() != rank {
-                self.hand.extend(to_meld);
-                return Err(InternalMeldError::IncorrectRank(*to_meld_id))
-            }
-            else { to_meld.push(self.hand.remove(hand_index)); }
+        self.check_meld(&cards, rank)?;
+        let index = meld_index(&rank).unwrap();
+        for to_meld_id in cards {
+            let card = self.discard(to_meld_id).unwrap();
+            self.temp_melds[index].push(card);
         }
-        self.temp_melds[Into::<usize>::into(&rank)].extend(to_meld);
         Ok(())
     }
 
-    /// View a temp meld for a given rank 
-    pub(crate) fn view_temp_meld(&self, rank: Rank) -> &[PlayCard] {
-        &self.temp_melds[Into::<usize>::into(&rank)]
+    /// Checks that a list of card IDs could be staged into a meld of a rank
+    pub(crate) fn check_meld(&self, cards: &[u8], rank: Rank) -> Result<(), InternalMeldError> {
+        meld_index(&rank).ok_or(InternalMeldError::WildRank)?;
+        for (i, to_meld_id) in cards.iter().enumerate() {
+            if cards[..i].contains(to_meld_id) { return Err(InternalMeldError::InvalidCardId(*to_meld_id)) }
+            let card = self.hand.iter()
+                .find(|c| c.id() == *to_meld_id)
+                .ok_or(InternalMeldError::InvalidCardId(*to_meld_id))?;
+            if card.is_red_three() { return Err(InternalMeldError::InvalidCardToMeld(*to_meld_id)) }
+            if !card.is_wild() && *card.rank() != rank { return Err(InternalMeldError::IncorrectRank(*to_meld_id)) }
+        }
+        Ok(())
     }
 
-    /// View a list of all temp melds 
-    ///
-    /// The index of the meld aligns with the cards rank
-    pub(crate) fn view_all_temp(&self) -> [&[PlayCard]; 13] {
-        let mut slices: [&[PlayCard]; 13] = Default::default();
-        for (i, meld) in self.temp_melds.iter().enumerate() {
-            slices[i] = meld;
+    /// Stages a card that is not from the players hand into the meld of a rank
+    pub(crate) fn stage_card(&mut self, card: PlayCard, rank: Rank) {
+        let index = meld_index(&rank).expect("Staged card must be meldable");
+        self.temp_melds[index].push(card);
+    }
+
+    /// View a temp meld for a given rank
+    pub(crate) fn view_temp_meld(&self, rank: Rank) -> &[PlayCard] {
+        match meld_index(&rank) {
+            Some(index) => &self.temp_melds[index],
+            None => &[],
         }
-        slices
     }
 
-    /// Remove cards of some id from the temp list 
+    /// Iterates the ranks and cards of every non empty staged meld
+    pub(crate) fn staged(&self) -> impl Iterator<Item = (Rank, &[PlayCard])> {
+        self.temp_melds.iter()
+            .enumerate()
+            .filter(|(_, meld)| !meld.is_empty())
+            .map(|(index, meld)| ((index as u8 + 1).into(), meld.as_slice()))
+    }
+
+    /// If any cards are currently staged
+    pub(crate) fn has_staged(&self) -> bool {
+        self.temp_melds.iter().any(|m| !m.is_empty())
+    }
+
+    /// Remove cards of some id from the temp list
     ///
-    /// If any fail it will return an error containing the failed IDs
+    /// If any are not staged nothing is removed and an error containing the missing IDs is returned
     pub(crate) fn remove_from_temp(&mut self, cards: Vec<u8>) -> Result<(), Vec<u8>> {
-        let failed_remove: Vec<u8> = cards.iter().filter_map(|&id| {
-            self.temp_melds.iter_mut()
-                .find_map(|meld| meld.iter().position(|c| c.id() == id).map(|pos| meld.remove(pos)))
-                .map_or(Some(id), |card| {
-                    self.hand.push(card);
-                    None
-                })
-        }).collect();
-        failed_remove.is_empty().then(|| ()).ok_or(failed_remove)
-    }
-
-    pub(crate) fn clear_temp_meld(&mut self) { 
+        let missing = self.missing_from_temp(&cards);
+        if !missing.is_empty() { return Err(missing) }
+        for id in cards {
+            let card = self.temp_melds.iter_mut()
+                .find_map(|meld| meld.iter().position(|c| c.id() == id).map(|pos| meld.remove(pos)));
+            if let Some(card) = card { self.hand.push(card) }
+        }
+        Ok(())
+    }
+
+    /// Returns the IDs in the list that are not staged
+    pub(crate) fn missing_from_temp(&self, cards: &[u8]) -> Vec<u8> {
+        cards.iter()
+            .filter(|&&id| !self.temp_melds.iter().flatten().any(|c| c.id() == id))
+            .copied()
+            .collect()
+    }
+
+    pub(crate) fn clear_temp_meld(&mut self) {
         self.temp_melds.iter_mut().for_each(|meld| {
-            self.hand.extend(meld.drain(..))
+            self.hand.append(meld)
         })
     }
 
-    pub(crate) fn commit_meld(&mut self) -> Result<(), ()> {
-        // check all melds are valid 
-        //
-        // Check wild numbers are not violated 
-        //
-        // Check there are a valid number of cards in melds 
-        //
-        // Update natural/canasta flags 
-        
-        Ok(())
+    /// Checks that staging the cards into the meld of a rank would give a valid meld
+    ///
+    /// Any existing meld and cards already staged for the rank are included
+    pub(crate) fn check_stage_valid(&self, rank: Rank, cards: &[u8]) -> Result<(), InternalMeldError> {
+        let index = meld_index(&rank).ok_or(InternalMeldError::WildRank)?;
+        let combined: Vec<PlayCard> = self.temp_melds[index].iter()
+            .chain(self.hand.iter().filter(|c| cards.contains(&c.id())))
+            .cloned()
+            .collect();
+        match &self.melds[index] {
+            Some(meld) => meld.can_add(&combined),
+            None => {
+                let wilds = combined.iter().filter(|c| c.is_wild()).count();
+                check_composition(&rank, combined.len() - wilds, wilds)
+            }
+        }
+    }
+
+    /// Checks that every staged meld is valid when combined with any existing meld
+    /// of the same rank
+    pub(crate) fn check_commit(&self) -> Result<CommitSummary, InternalMeldError> {
+        if !self.has_staged() { return Err(InternalMeldError::NothingStaged) }
+        let mut summary = CommitSummary {
+            value: 0,
+            canastas: self.canastas(),
+            cards_left: self.hand.len(),
+            black_threes: false,
+        };
+        for (index, staged) in self.temp_melds.iter().enumerate() {
+            if staged.is_empty() { continue }
+            let rank: Rank = (index as u8 + 1).into();
+            let existing = self.melds[index].as_ref();
+            match existing {
+                Some(meld) => meld.can_add(staged)?,
+                None => {
+                    let wilds = staged.iter().filter(|c| c.is_wild()).count();
+                    check_composition(&rank, staged.len() - wilds, wilds)?;
+                }
+            }
+            let was_canasta = existing.is_some_and(|m| m.is_canasta());
+            let new_len = existing.map_or(0, |m| m.len()) + staged.len();
+            if !was_canasta && new_len >= CANASTA_SIZE { summary.canastas += 1 }
+            if rank == Rank::Three { summary.black_threes = true }
+            summary.value += staged.iter().map(|c| c.value() as i32).sum::<i32>();
+        }
+        Ok(summary)
     }
-}
 
+    /// Moves all staged cards into the players melds
+    ///
+    /// Returns the ranks of melds that became canastas.
+    /// This does not validate the melds, see `check_commit`
+    pub(crate) fn commit_meld(&mut self) -> Vec<Rank> {
+        let mut completed = vec![];
+        for (index, staged) in self.temp_melds.iter_mut().enumerate() {
+            if staged.is_empty() { continue }
+            self.known.retain(|id| !staged.iter().any(|c| c.id() == *id));
+            let rank: Rank = (index as u8 + 1).into();
+            let meld = self.melds[index].get_or_insert_with(|| Meld::new(rank));
+            let was_canasta = meld.is_canasta();
+            staged.drain(..).for_each(|card| meld.push(card));
+            if !was_canasta && meld.is_canasta() { completed.push(rank) }
+        }
+        completed
+    }
 
+    /// Score for the hand just played
+    ///
+    /// Melded cards and canasta bonuses are added, cards left in hand are subtracted.
+    /// Red threes are worth 100 each or 800 for all four, but count against a player
+    /// that has not melded.
+    pub(crate) fn hand_score(&self, went_out: bool) -> i32 {
+        let melds: i32 = self.melds().map(|m| m.card_value() + m.bonus()).sum();
+        let hand: i32 = self.hand.iter()
+            .chain(self.temp_melds.iter().flatten())
+            .map(|c| c.value() as i32)
+            .sum();
+        let red_threes = match self.red_threes.len() {
+            4 => 800,
+            n => n as i32 * 100,
+        };
+        let red_threes = if self.has_melded() { red_threes } else { -red_threes };
+        let going_out = if went_out { 100 } else { 0 };
+        melds - hand + red_threes + going_out
+    }
+}
@@ -0,0 +1,427 @@
+use std::{fmt, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::{
+    game::{CanastaGame, TurnPhase},
+    action_log::{Action, LogEntry},
+    card::{PlayCard, Rank, notation::resolve_all},
+    errors::record_error::RecordError,
+};
+
+/// Written record of a game, in a format similar to chess PGN
+/// # Overview
+/// A record starts with header tags, one per line, describing the game:
+/// - `Players`, `Canastas`, `Game` (`Full` or `Hand`), `Seed` and `Undo` (`Yes` or
+///   `No`) are the rules needed to replay the game, every one but `Undo` must be given
+/// - `Date` and `Result`, the final scores or `*` if the game is unfinished
+/// - Any other tags, such as `Event` or player names
+///
+/// After a blank line the move text lists each turn on its own line, giving the turn
+/// number, the player and their actions separated by `;`. Cards are written in card
+/// notation, drawn cards are not written as they are decided by the seed.
+/// ```text
+/// [Players "2"]
+/// [Canastas "1"]
+/// [Game "Hand"]
+/// [Seed "42"]
+/// [Undo "No"]
+/// [Date "2024.01.31"]
+/// [Result "*"]
+///
+/// 1. P1 draw; meld K KH KS JK; commit; discard 7C
+/// 2. P0 take 7H 7D; discard 4S
+/// ```
+/// Text between `{` and `}` on a line is a comment and is ignored. Actions played for a
+/// player whose time ran out follow a `{timeout}` comment, and are logged as timed out
+/// when the record is read.
+///
+/// There are two decks so most cards have a twin with the same notation. A card read
+/// back is the first matching card the player holds, which may be the twin of the card
+/// played. The replayed game is the same but for which twin is where, use a
+/// `GameStore` where the exact cards matter.
+/// # Example
+/// ```
+/// use game_lib::{game::CanastaGame, record::GameRecord, agents::{Agent, Driver, RandomAgent}};
+/// let mut game = CanastaGame::builder().players(2).canastas(1).hand().seed(9).build().unwrap();
+/// let seats: Vec<Option<Box<dyn Agent>>> = vec![Some(Box::new(RandomAgent::new(1))), Some(Box::new(RandomAgent::new(2)))];
+/// Driver::new(seats).run(&mut game).unwrap();
+///
+/// let text = GameRecord::from_game(&game).tag("Event", "Example").to_string();
+/// let (record, replayed) = GameRecord::parse(&text).unwrap();
+/// assert_eq!(record.get("Event"), Some("Example"));
+/// assert_eq!(replayed.scores(), game.scores());
+/// ```
+/// Comment before the actions played for a player out of time
+const TIMEOUT: &str = "{timeout}";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameRecord {
+    tags: Vec<(String, String)>,
+    /// Each turn, the player and the text of their actions
+    turns: Vec<(u8, Vec<String>)>,
+}
+
+impl GameRecord {
+    /// Record the rules, result and every action of a game, dated today
+    pub fn from_game(game: &CanastaGame) -> Self {
+        let result = if game.get_phase() == TurnPhase::GameOver {
+            game.scores().iter().map(|s| s.to_string()).collect::<Vec<_>>().join(" ")
+        } else {
+            "*".to_string()
+        };
+        let tags = [
+            ("Players", game.num_players().to_string()),
+            ("Canastas", game.canastas_to_go_out().to_string()),
+            ("Game", if game.is_full_game() { "Full" } else { "Hand" }.to_string()),
+            ("Seed", game.seed().to_string()),
+            ("Undo", if game.undo_enabled() { "Yes" } else { "No" }.to_string()),
+            ("Date", today()),
+            ("Result", result),
+        ];
+        Self {
+            tags: tags.into_iter().map(|(k, v)| (k.to_string(), v)).collect(),
+            turns: turns(game.log().entries()),
+        }
+    }
+
+    /// Set a tag, replacing any existing value
+    pub fn tag(mut self, name: &str, value: &str) -> Self {
+        match self.tags.iter_mut().find(|(k, _)| k == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+        self
+    }
+
+    /// Value of a tag
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    /// Read a record and replay its moves
+    /// # Returns
+    /// - `Ok((GameRecord, CanastaGame))` - The record and the game after every move
+    /// - `Err(RecordError)` - The record is malformed or has an illegal move, with the
+    ///   line it is on
+    pub fn parse(text: &str) -> Result<(GameRecord, CanastaGame), RecordError> {
+        let mut tags = vec![];
+        let mut lines = text.lines().enumerate().map(|(i, l)| (i + 1, l));
+        let mut first_move = None;
+        for (line, raw) in lines.by_ref() {
+            let content = strip_comments(raw, false);
+            let content = content.trim();
+            if content.is_empty() { continue }
+            if !content.starts_with('[') { first_move = Some((line, raw)); break }
+            tags.push((line, parse_tag(line, content)?));
+        }
+        let find = |name: &str| tags.iter().find(|(_, (k, _))| k == name).map(|(line, (_, v))| (*line, v.as_str()));
+        let require = |name: &str| find(name).ok_or_else(|| RecordError::MissingTag(name.to_string()));
+        let invalid = |line: usize, tag: &str| RecordError::InvalidTag { line, tag: tag.to_string() };
+
+        let (players_line, players) = require("Players")?;
+        let players: u8 = players.parse().map_err(|_| invalid(players_line, "Players"))?;
+        let (line, canastas) = require("Canastas")?;
+        let canastas: u8 = canastas.parse().map_err(|_| invalid(line, "Canastas"))?;
+        let (line, seed) = require("Seed")?;
+        let seed: u64 = seed.parse().map_err(|_| invalid(line, "Seed"))?;
+        let builder = CanastaGame::builder().players(players).canastas(canastas).seed(seed);
+        let builder = match require("Game")? {
+            (_, "Full") => builder.full_game(),
+            (_, "Hand") => builder.hand(),
+            (line, _) => return Err(invalid(line, "Game")),
+        };
+        let mut builder = match find("Undo") {
+            Some((_, "Yes")) => builder.undo(),
+            Some((_, "No")) | None => builder,
+            Some((line, _)) => return Err(invalid(line, "Undo")),
+        };
+        let mut game = builder.build().ok_or_else(|| invalid(players_line, "Players"))?;
+
+        let mut turns = vec![];
+        for (line, raw) in first_move.into_iter().chain(lines) {
+            let content = strip_comments(raw, true);
+            let content = content.trim();
+            if content.is_empty() { continue }
+            let (player, actions) = parse_turn(line, content)?;
+            // every action after a timeout comment was played for the player
+            let mut timed_out = false;
+            for text in &actions {
+                timed_out |= text.contains(TIMEOUT);
+                let played = text.replace(TIMEOUT, " ").split_whitespace().collect::<Vec<_>>().join(" ");
+                let action = parse_action(&game, player, line, &played)?;
+                game.apply(player, action).map_err(|source| RecordError::IllegalMove { line, text: played, source })?;
+                if timed_out { game.log_mut().mark_timed_out() }
+            }
+            turns.push((player, actions));
+        }
+
+        if let Some((_, result)) = find("Result") {
+            let scores = game.scores().iter().map(|s| s.to_string()).collect::<Vec<_>>().join(" ");
+            let finished = game.get_phase() == TurnPhase::GameOver;
+            if result != "*" && (!finished || result != scores) {
+                return Err(RecordError::ResultMismatch(result.to_string()))
+            }
+        }
+        let tags = tags.into_iter().map(|(_, tag)| tag).collect();
+        Ok((GameRecord { tags, turns }, game))
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in &self.tags {
+            writeln!(f, "[{} \"{}\"]", name, value.replace('\\', "\\\\").replace('"', "\\\""))?;
+        }
+        writeln!(f)?;
+        for (number, (player, actions)) in self.turns.iter().enumerate() {
+            writeln!(f, "{}. P{} {}", number + 1, player, actions.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+impl CanastaGame {
+    /// Writes a record of the game, see `GameRecord`
+    pub fn to_record(&self) -> GameRecord {
+        GameRecord::from_game(self)
+    }
+}
+
+/// Groups logged actions into turns written in card notation
+fn turns(entries: &[LogEntry]) -> Vec<(u8, Vec<String>)> {
+    let mut turns: Vec<(u8, Vec<String>)> = vec![];
+    let mut turn_over = true;
+    for entry in entries {
+        if turn_over || turns.last().is_some_and(|(p, _)| *p != entry.player) {
+            turns.push((entry.player, vec![]));
+        }
+        turn_over = matches!(entry.action, Action::Discard(_));
+        let actions = &mut turns.last_mut().expect("A turn was started").1;
+        let timed_out = entry.timed_out && !actions.iter().any(|a| a.contains(TIMEOUT));
+        let text = write_action(&entry.action);
+        // the actions played for a player out of time follow a comment
+        actions.push(if timed_out { format!("{TIMEOUT} {text}") } else { text });
+    }
+    turns
+}
+
+fn write_action(action: &Action) -> String {
+    let cards = |ids: &[u8]| ids.iter().map(|id| card(*id).to_string()).collect::<Vec<_>>();
+    let with_cards = |name: &str, ids: &[u8]| [name.to_string()].into_iter().chain(cards(ids)).collect::<Vec<_>>().join(" ");
+    match action {
+        Action::Draw => "draw".to_string(),
+        Action::TakeDiscard(ids) => with_cards("take", ids),
+        Action::Meld { cards, rank } => with_cards(&format!("meld {rank}"), cards),
+        Action::Unmeld(ids) => with_cards("unmeld", ids),
+        Action::ClearMeld => "clear".to_string(),
+        Action::CommitMeld => "commit".to_string(),
+        Action::Discard(id) => format!("discard {}", card(*id)),
+        Action::Undo => "undo".to_string(),
+        Action::Redo => "redo".to_string(),
+    }
+}
+
+/// The card with an ID, IDs are the position of the card in a full deck
+fn card(id: u8) -> PlayCard {
+    PlayCard::from_id(id).expect("Card IDs are in the deck")
+}
+
+/// Removes the comments from a line, keeping `{timeout}` comments if asked to
+fn strip_comments(line: &str, keep_timeouts: bool) -> String {
+    let mut out = String::new();
+    let mut comment = String::new();
+    let mut depth = 0;
+    for c in line.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth > 0 => depth -= 1,
+            c if depth == 0 => out.push(c),
+            _ => {}
+        }
+        if depth > 0 || c == '}' { comment.push(c) }
+        if depth == 0 && !comment.is_empty() {
+            if keep_timeouts && comment == TIMEOUT { out += &format!(" {TIMEOUT} ") }
+            comment.clear();
+        }
+    }
+    out
+}
+
+fn parse_tag(line: usize, content: &str) -> Result<(String, String), RecordError> {
+    let syntax = |message: &str| RecordError::Syntax { line, message: message.to_string() };
+    let inner = content.strip_prefix('[').and_then(|c| c.strip_suffix(']')).ok_or_else(|| syntax("Tags must be written [Name \"Value\"]"))?;
+    let (name, value) = inner.split_once(' ').ok_or_else(|| syntax("Tags must be written [Name \"Value\"]"))?;
+    let value = value.trim().strip_prefix('"').and_then(|v| v.strip_suffix('"')).ok_or_else(|| syntax("Tag values must be quoted"))?;
+    Ok((name.to_string(), value.replace("\\\"", "\"").replace("\\\\", "\\")))
+}
+
+/// Reads a line of move text such as `3. P1 draw; discard 7C`
+fn parse_turn(line: usize, content: &str) -> Result<(u8, Vec<String>), RecordError> {
+    let syntax = |message: &str| RecordError::Syntax { line, message: message.to_string() };
+    let mut words = content.splitn(3, char::is_whitespace);
+    let number = words.next().unwrap_or_default();
+    if number.strip_suffix('.').and_then(|n| n.parse::<usize>().ok()).is_none() {
+        return Err(syntax("Turns must start with their number, e.g. `1.`"))
+    }
+    let player = words.next()
+        .and_then(|p| p.strip_prefix('P'))
+        .and_then(|p| p.parse().ok())
+        .ok_or_else(|| syntax("Turn numbers must be followed by the player, e.g. `P0`"))?;
+    let actions = words.next().unwrap_or_default()
+        .split(';')
+        .map(|a| a.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|a| !a.is_empty())
+        .collect();
+    Ok((player, actions))
+}
+
+/// Reads an action, finding the cards it names in the players hand or staged melds
+fn parse_action(game: &CanastaGame, player: u8, line: usize, text: &str) -> Result<Action, RecordError> {
+    let syntax = |message: String| RecordError::Syntax { line, message };
+    let notation = |source| RecordError::Notation { line, source };
+    let hand = game.get_hand(player).map_err(|_| syntax(format!("There is no player {player}")))?;
+    let (verb, args) = text.split_once(' ').unwrap_or((text, ""));
+    let action = match verb {
+        "draw" => Action::Draw,
+        "take" => Action::TakeDiscard(resolve_all(hand, [args]).map_err(notation)?),
+        "meld" => {
+            let (rank, cards) = args.split_once(' ').unwrap_or((args, ""));
+            let rank: Rank = rank.parse().map_err(notation)?;
+            Action::Meld { cards: resolve_all(hand, [cards]).map_err(notation)?, rank }
+        }
+        "unmeld" => {
+            let view = game.player_view(player).map_err(|_| syntax(format!("There is no player {player}")))?;
+            let staged: Vec<PlayCard> = view.staged.into_iter().flat_map(|(_, cards)| cards).collect();
+            Action::Unmeld(resolve_all(&staged, [args]).map_err(notation)?)
+        }
+        "clear" => Action::ClearMeld,
+        "commit" => Action::CommitMeld,
+        "discard" => Action::Discard(resolve_all(hand, [args]).map_err(notation).and_then(|ids| match ids[..] {
+            [id] => Ok(id),
+            _ => Err(syntax("Discard takes one card".to_string())),
+        })?),
+        "undo" => Action::Undo,
+        "redo" => Action::Redo,
+        other => return Err(syntax(format!("Unknown action `{other}`"))),
+    };
+    Ok(action)
+}
+
+/// Todays date as `YYYY.MM.DD`
+fn today() -> String {
+    let days = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() / 86_400) as i64;
+    // convert days since 1970-01-01 to a civil date, shifting the year to start in March
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{year:04}.{month:02}.{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agents::{Agent, Driver, RandomAgent};
+
+    use super::*;
+
+    const HEADER: &str = "[Players \"2\"]\n[Canastas \"1\"]\n[Game \"Hand\"]\n[Seed \"42\"]\n";
+
+    fn played(seed: u64) -> CanastaGame {
+        let mut game = CanastaGame::builder().players(2).canastas(1).hand().seed(seed).build().unwrap();
+        let seats: Vec<Option<Box<dyn Agent>>> = vec![Some(Box::new(RandomAgent::new(seed))), Some(Box::new(RandomAgent::new(seed + 1)))];
+        Driver::new(seats).run(&mut game).unwrap();
+        game
+    }
+
+    fn error(text: &str) -> RecordError {
+        GameRecord::parse(text).err().expect("Record should not parse")
+    }
+
+    #[test]
+    fn game_tag_is_required() {
+        let text = HEADER.replace("[Game \"Hand\"]\n", "");
+        assert_eq!(error(&text), RecordError::MissingTag("Game".to_string()));
+        let text = HEADER.replace("Hand", "Short");
+        assert_eq!(error(&text), RecordError::InvalidTag { line: 3, tag: "Game".to_string() });
+        let (_, game) = GameRecord::parse(HEADER).unwrap();
+        assert!(!game.is_full_game());
+    }
+
+    #[test]
+    fn replay_matches_the_game() {
+        for seed in 0..5 {
+            let game = played(seed);
+            let (record, replayed) = GameRecord::parse(&game.to_record().to_string()).unwrap();
+            assert_eq!(record, game.to_record().tag("Date", record.get("Date").unwrap()));
+            assert_eq!(replayed.scores(), game.scores());
+            assert_eq!(replayed.log().len(), game.log().len());
+            for player in 0..2 {
+                let faces = |g: &CanastaGame| g.get_hand(player).unwrap().iter().map(|c| c.to_string()).collect::<Vec<_>>();
+                assert_eq!(faces(&replayed), faces(&game));
+            }
+        }
+    }
+
+    #[test]
+    fn result_must_match() {
+        let game = played(3);
+        let text = game.to_record().tag("Result", "1 2").to_string();
+        assert_eq!(error(&text), RecordError::ResultMismatch("1 2".to_string()));
+    }
+
+    #[test]
+    fn timed_out_actions_are_marked() {
+        let mut game = CanastaGame::builder().players(2).canastas(1).hand().seed(3).build().unwrap();
+        let player = game.get_current_player();
+        game.time_out(player).unwrap();
+        let text = game.to_record().to_string();
+        assert!(text.contains(&format!("1. P{player} {{timeout}} draw; discard")));
+        let (_, replayed) = GameRecord::parse(&text).unwrap();
+        assert_eq!(replayed.get_current_player(), game.get_current_player());
+        assert!(replayed.log().entries().iter().all(|e| e.timed_out));
+    }
+
+    #[test]
+    fn timeouts_survive_a_round_trip() {
+        let mut game = CanastaGame::builder().players(2).canastas(1).hand().seed(5).build().unwrap();
+        let mut agent = RandomAgent::new(5);
+        // every third turn times out, one of them after the player has drawn
+        for turn in 0..12 {
+            if game.get_phase() == TurnPhase::GameOver { break }
+            let player = game.get_current_player();
+            if turn % 3 == 0 {
+                if turn % 2 == 0 { game.apply(player, Action::Draw).unwrap(); }
+                game.time_out(player).unwrap();
+                continue
+            }
+            while game.get_current_player() == player && game.get_phase() != TurnPhase::GameOver {
+                let action = agent.choose(&game.player_view(player).unwrap(), &game.legal_actions());
+                game.apply(player, action).unwrap();
+            }
+        }
+        let text = game.to_record().to_string();
+        assert!(text.contains("draw; {timeout} discard"));
+        let (record, replayed) = GameRecord::parse(&text).unwrap();
+        let flags = |g: &CanastaGame| g.log().entries().iter().map(|e| e.timed_out).collect::<Vec<_>>();
+        assert_eq!(flags(&replayed), flags(&game));
+        assert_eq!(record.to_string(), text);
+        assert_eq!(replayed.to_record().to_string(), text);
+    }
+
+    #[test]
+    fn other_comments_are_ignored() {
+        let game = played(1);
+        let text = game.to_record().to_string().replacen("1. P", "{opening {nested}} 1. P", 1).replacen("; ", "; {timed} ", 1);
+        let (_, replayed) = GameRecord::parse(&text).unwrap();
+        assert_eq!(replayed.scores(), game.scores());
+        assert!(replayed.log().entries().iter().all(|e| !e.timed_out));
+    }
+}
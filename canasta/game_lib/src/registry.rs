@@ -0,0 +1,544 @@
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard, atomic::{AtomicU32, Ordering}},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    game::{CanastaGame, TurnPhase},
+    game_builder::GameBuilder,
+    store::{GameStore, FileStore},
+    errors::store_error::StoreError,
+};
+
+/// A game held by a `GameRegistry`, which can be shared between threads
+///
+/// Cloning a handle gives another handle to the same game. Every lock of the game
+/// counts as activity, see `GameRegistry::expire`.
+#[derive(Clone)]
+pub struct GameHandle {
+    id: u32,
+    inner: Arc<Inner>,
+    store: Option<Arc<dyn GameStore>>,
+}
+
+struct Inner {
+    game: Mutex<CanastaGame>,
+    last_used: Mutex<Instant>,
+    /// Number of log entries in the store, `None` if the game has not been saved
+    stored: Mutex<Option<usize>>,
+}
+
+/// A game in a registry as listed by `GameRegistry::list`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameSummary {
+    pub id: u32,
+    pub players: u8,
+    pub phase: TurnPhase,
+    /// Number of hands that have been completed
+    pub hand_number: u32,
+    pub scores: Vec<i32>,
+    /// Time since the game was last locked
+    pub idle: Duration,
+}
+
+impl GameHandle {
+    fn new(id: u32, game: CanastaGame, store: Option<Arc<dyn GameStore>>, stored: Option<usize>) -> Self {
+        let inner = Inner { game: Mutex::new(game), last_used: Mutex::new(Instant::now()), stored: Mutex::new(stored) };
+        Self { id, inner: Arc::new(inner), store }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Locks the game for use, blocking until no other thread holds it
+    pub fn lock(&self) -> MutexGuard<'_, CanastaGame> {
+        *relock(&self.inner.last_used) = Instant::now();
+        relock(&self.inner.game)
+    }
+
+    /// Time since the game was last locked
+    pub fn idle(&self) -> Duration {
+        relock(&self.inner.last_used).elapsed()
+    }
+
+    /// Deletes the game from the store, if it was saved
+    fn unstore(&self) -> Result<(), StoreError> {
+        let Some(store) = &self.store else { return Ok(()) };
+        let mut stored = relock(&self.inner.stored);
+        if stored.is_none() { return Ok(()) }
+        store.delete(self.id)?;
+        *stored = None;
+        Ok(())
+    }
+
+    /// Writes the game to the store of the registry, does nothing without a store
+    /// # Overview
+    /// The first call saves the whole game, later calls append the actions logged since
+    /// the last call. Call it after changing the game so the store keeps up.
+    /// # Returns
+    /// - `Ok(())` - The store holds every action of the game
+    /// - `Err(StoreError)` - The store could not be written, a later call tries again
+    pub fn persist(&self) -> Result<(), StoreError> {
+        let Some(store) = &self.store else { return Ok(()) };
+        let game = relock(&self.inner.game);
+        let mut stored = relock(&self.inner.stored);
+        let entries = game.log().entries();
+        match *stored {
+            None => {
+                store.save(&game)?;
+                *stored = Some(entries.len());
+            }
+            Some(done) => {
+                for (index, entry) in entries.iter().enumerate().skip(done) {
+                    store.append(self.id, index + 1, entry)?;
+                    *stored = Some(index + 1);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Locks a mutex, carrying on if another thread panicked while holding it
+///
+/// A panic while one game is in use should not take down every other game
+fn relock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Holds many games at once, giving each a unique ID
+/// # Overview
+/// Games are added with `register` or `create`, which give the game the next free ID,
+/// see `CanastaGame::game_id`. Each game is behind its own lock so games can be played
+/// on different threads at the same time.
+///
+/// Games nobody has used for a while can be removed with `expire`. Every game can be
+/// written to a directory with `snapshot`, one file per game, and read back with
+/// `restore`.
+///
+/// A registry given a `GameStore` keeps its games there as they are played, see
+/// `GameHandle::persist`. After a crash `load` restores every game in progress.
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use game_lib::{game::CanastaGame, registry::GameRegistry};
+/// let registry = GameRegistry::new();
+/// let first = registry.create(CanastaGame::builder().players(2).canastas(1).hand()).unwrap();
+/// let second = registry.create(CanastaGame::builder().players(3).canastas(1).hand()).unwrap();
+/// assert_ne!(first.id(), second.id());
+/// assert_eq!(first.lock().game_id(), first.id());
+///
+/// let found = registry.get(second.id()).unwrap();
+/// assert_eq!(found.lock().num_players(), 3);
+/// assert_eq!(registry.list().len(), 2);
+///
+/// // games unused for longer than the limit are removed
+/// std::thread::sleep(Duration::from_millis(20));
+/// assert_eq!(first.lock().num_players(), 2);
+/// assert_eq!(registry.expire(Duration::from_millis(10)).unwrap(), vec![second.id()]);
+/// assert_eq!(registry.ids(), vec![first.id()]);
+/// ```
+pub struct GameRegistry {
+    games: RwLock<BTreeMap<u32, GameHandle>>,
+    next_id: AtomicU32,
+    store: Option<Arc<dyn GameStore>>,
+}
+
+impl Default for GameRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameRegistry {
+    pub fn new() -> Self {
+        Self { games: RwLock::new(BTreeMap::new()), next_id: AtomicU32::new(1), store: None }
+    }
+
+    /// Keep games in the store as they are played
+    ///
+    /// Give the registry its store before adding games, games already added are not
+    /// kept in it.
+    pub fn store(mut self, store: Arc<dyn GameStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Restores every game in progress from a store, keeping their IDs
+    /// # Overview
+    /// Finished games are left in the store. The registry keeps using the store for the
+    /// restored games and any new ones, which are given IDs after every stored game.
+    /// # Returns
+    /// - `Ok(GameRegistry)` - Every game in progress in the store
+    /// - `Err(StoreError)` - The store could not be read or holds a corrupt game
+    /// # Example
+    /// ```
+    /// use std::sync::Arc;
+    /// use game_lib::{game::CanastaGame, registry::GameRegistry, store::{GameStore, MemoryStore}};
+    /// let store = Arc::new(MemoryStore::new());
+    /// let registry = GameRegistry::new().store(store.clone());
+    /// let handle = registry.create(CanastaGame::builder().players(2).canastas(1).hand().seed(3)).unwrap();
+    /// let player = handle.lock().get_current_player();
+    /// handle.persist().unwrap();
+    /// handle.lock().draw(player).unwrap();
+    /// handle.persist().unwrap();
+    ///
+    /// // the server crashes, its games are restored from the store
+    /// let restored = GameRegistry::load(store).unwrap();
+    /// let game = restored.get(handle.id()).unwrap();
+    /// assert_eq!(game.lock().get_hand(player).unwrap(), handle.lock().get_hand(player).unwrap());
+    /// assert_eq!(game.lock().log().len(), 1);
+    /// ```
+    pub fn load(store: Arc<dyn GameStore>) -> Result<Self, StoreError> {
+        let registry = Self::new().store(store.clone());
+        for id in store.list()? {
+            let game = store.load(id)?;
+            // finished games keep their ID so it is not given to a new game
+            registry.next_id.fetch_max(id + 1, Ordering::SeqCst);
+            if game.get_phase() == TurnPhase::GameOver { continue }
+            let stored = game.log().len();
+            registry.insert(id, game, Some(stored));
+        }
+        Ok(registry)
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, BTreeMap<u32, GameHandle>> {
+        self.games.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, BTreeMap<u32, GameHandle>> {
+        self.games.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Adds a game, giving it the next ID
+    pub fn register(&self, mut game: CanastaGame) -> GameHandle {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        game.set_game_id(id);
+        let handle = GameHandle::new(id, game, self.store.clone(), None);
+        self.write().insert(id, handle.clone());
+        handle
+    }
+
+    /// Builds a game and adds it, see `register`
+    /// # Returns
+    /// `None` if the builder is missing a setting
+    pub fn create(&self, mut builder: GameBuilder) -> Option<GameHandle> {
+        Some(self.register(builder.build()?))
+    }
+
+    /// The game with the ID, or `None` if there is no such game
+    pub fn get(&self, id: u32) -> Option<GameHandle> {
+        self.read().get(&id).cloned()
+    }
+
+    /// Removes a game and deletes it from the store, handles to it can still be used
+    /// # Returns
+    /// - `Ok(Option<GameHandle>)` - The game, or `None` if there is no game with the ID
+    /// - `Err(StoreError)` - The game was removed but could not be deleted from the store
+    pub fn remove(&self, id: u32) -> Result<Option<GameHandle>, StoreError> {
+        let Some(handle) = self.write().remove(&id) else { return Ok(None) };
+        handle.unstore()?;
+        Ok(Some(handle))
+    }
+
+    /// IDs of every game, in order
+    pub fn ids(&self) -> Vec<u32> {
+        self.read().keys().copied().collect()
+    }
+
+    /// Summary of every game, in order of ID
+    ///
+    /// Each game is locked in turn, so this waits on games in use
+    pub fn list(&self) -> Vec<GameSummary> {
+        self.read().values()
+            .map(|handle| {
+                let idle = handle.idle();
+                let game = relock(&handle.inner.game);
+                GameSummary {
+                    id: handle.id,
+                    players: game.num_players(),
+                    phase: game.get_phase(),
+                    hand_number: game.hand_number(),
+                    scores: game.scores(),
+                    idle,
+                }
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Removes every game that has not been locked for longer than `idle`, deleting them
+    /// from the store
+    /// # Returns
+    /// - `Ok(Vec<u32>)` - The IDs of the games removed
+    /// - `Err(StoreError)` - A game could not be deleted from the store, every expired
+    ///   game is still removed
+    pub fn expire(&self, idle: Duration) -> Result<Vec<u32>, StoreError> {
+        let removed: Vec<GameHandle> = {
+            let mut games = self.write();
+            let expired: Vec<u32> = games.values().filter(|h| h.idle() > idle).map(|h| h.id).collect();
+            expired.iter().filter_map(|id| games.remove(id)).collect()
+        };
+        let mut result = Ok(());
+        for handle in &removed {
+            if let Err(err) = handle.unstore() { result = Err(err) }
+        }
+        result.map(|_| removed.iter().map(|h| h.id).collect())
+    }
+
+    /// Writes every game to the store, see `GameHandle::persist`
+    pub fn persist(&self) -> Result<(), StoreError> {
+        let handles: Vec<GameHandle> = self.read().values().cloned().collect();
+        handles.iter().try_for_each(GameHandle::persist)
+    }
+
+    /// Writes every game to the directory, one file per game named after its ID
+    /// # Overview
+    /// Games are written with a `FileStore`, the directory is created if needed. Games no
+    /// longer in the registry are removed, so the directory always holds the games of the
+    /// last snapshot.
+    /// # Returns
+    /// - `Ok(usize)` - The number of games written
+    /// - `Err(StoreError::Io)` - The directory or a file could not be written
+    /// # Example
+    /// ```
+    /// use game_lib::{game::CanastaGame, registry::GameRegistry};
+    /// let dir = std::env::temp_dir().join(format!("canasta-snapshot-{}", std::process::id()));
+    /// let registry = GameRegistry::new();
+    /// let handle = registry.create(CanastaGame::builder().players(2).canastas(1).hand().seed(3)).unwrap();
+    /// let player = handle.lock().get_current_player();
+    /// handle.lock().draw(player).unwrap();
+    /// assert_eq!(registry.snapshot(&dir).unwrap(), 1);
+    ///
+    /// let restored = GameRegistry::restore(&dir).unwrap();
+    /// let game = restored.get(handle.id()).unwrap();
+    /// assert_eq!(game.lock().get_hand(player).unwrap(), handle.lock().get_hand(player).unwrap());
+    /// // new games are not given the IDs of restored ones
+    /// let new = restored.create(CanastaGame::builder().players(2).canastas(1).hand()).unwrap();
+    /// assert!(new.id() > handle.id());
+    /// std::fs::remove_dir_all(&dir).unwrap();
+    /// ```
+    pub fn snapshot(&self, dir: &Path) -> Result<usize, StoreError> {
+        let store = FileStore::open(dir)?;
+        let handles: Vec<GameHandle> = self.read().values().cloned().collect();
+        for handle in &handles {
+            store.save(&relock(&handle.inner.game))?;
+        }
+        for id in store.list()? {
+            if !handles.iter().any(|h| h.id == id) { store.delete(id)? }
+        }
+        Ok(handles.len())
+    }
+
+    /// Reads the games of a snapshot back into a new registry, keeping their IDs
+    ///
+    /// New games are given IDs after the highest ID read.
+    /// # Returns
+    /// - `Ok(GameRegistry)` - Every game in the snapshot
+    /// - `Err(StoreError)` - A file could not be read or holds a corrupt game
+    pub fn restore(dir: &Path) -> Result<Self, StoreError> {
+        let store = FileStore::open(dir)?;
+        let registry = Self::new();
+        for id in store.list()? {
+            registry.insert(id, store.load(id)?, None);
+        }
+        Ok(registry)
+    }
+
+    /// Adds a game with an ID it already has, keeping later IDs unique
+    fn insert(&self, id: u32, mut game: CanastaGame, stored: Option<usize>) {
+        game.set_game_id(id);
+        let handle = GameHandle::new(id, game, self.store.clone(), stored);
+        self.write().insert(id, handle);
+        self.next_id.fetch_max(id + 1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf, sync::atomic::AtomicUsize, thread};
+
+    use crate::{action_log::LogEntry, agents::{Agent, Driver, RandomAgent}, store::{MemoryStore, file::EXTENSION}};
+
+    use super::*;
+
+    /// Counts what is written to a memory store
+    #[derive(Default)]
+    struct CountingStore {
+        store: MemoryStore,
+        saves: AtomicUsize,
+        appends: AtomicUsize,
+    }
+
+    impl GameStore for CountingStore {
+        fn save(&self, game: &CanastaGame) -> Result<(), StoreError> {
+            self.saves.fetch_add(1, Ordering::SeqCst);
+            self.store.save(game)
+        }
+
+        fn load(&self, id: u32) -> Result<CanastaGame, StoreError> {
+            self.store.load(id)
+        }
+
+        fn list(&self) -> Result<Vec<u32>, StoreError> {
+            self.store.list()
+        }
+
+        fn delete(&self, id: u32) -> Result<(), StoreError> {
+            self.store.delete(id)
+        }
+
+        fn append(&self, id: u32, number: usize, entry: &LogEntry) -> Result<(), StoreError> {
+            self.appends.fetch_add(1, Ordering::SeqCst);
+            self.store.append(id, number, entry)
+        }
+    }
+
+    fn builder(seed: u64) -> GameBuilder {
+        CanastaGame::builder().players(2).canastas(1).hand().seed(seed)
+    }
+
+    /// Plays the current player's turn, drawing then discarding their first card
+    fn play_turn(handle: &GameHandle) {
+        let mut game = handle.lock();
+        let player = game.get_current_player();
+        game.draw(player).unwrap();
+        let card = game.get_hand(player).unwrap()[0].id();
+        game.discard(player, card).unwrap();
+    }
+
+    #[test]
+    fn persist_appends_new_actions() {
+        let store = Arc::new(CountingStore::default());
+        let registry = GameRegistry::new().store(store.clone());
+        let handle = registry.create(builder(1)).unwrap();
+        registry.persist().unwrap();
+        play_turn(&handle);
+        registry.persist().unwrap();
+        registry.persist().unwrap();
+        assert_eq!(store.saves.load(Ordering::SeqCst), 1);
+        assert_eq!(store.appends.load(Ordering::SeqCst), 2);
+        assert_eq!(store.load(handle.id()).unwrap().log().len(), 2);
+    }
+
+    #[test]
+    fn removed_games_leave_the_store() {
+        let store = Arc::new(MemoryStore::new());
+        let registry = GameRegistry::new().store(store.clone());
+        let kept = registry.create(builder(1)).unwrap();
+        let removed = registry.create(builder(2)).unwrap();
+        // a game never persisted has nothing to delete
+        let unsaved = registry.create(builder(3)).unwrap();
+        kept.persist().unwrap();
+        removed.persist().unwrap();
+        assert!(registry.remove(removed.id()).unwrap().is_some());
+        assert!(registry.remove(unsaved.id()).unwrap().is_some());
+        assert!(registry.remove(removed.id()).unwrap().is_none());
+        assert_eq!(store.list().unwrap(), vec![kept.id()]);
+    }
+
+    #[test]
+    fn load_skips_finished_games() {
+        let store = Arc::new(MemoryStore::new());
+        let registry = GameRegistry::new().store(store.clone());
+        let playing = registry.create(builder(1)).unwrap();
+        let finished = registry.create(builder(2)).unwrap();
+        let seats: Vec<Option<Box<dyn Agent>>> = vec![Some(Box::new(RandomAgent::new(1))), Some(Box::new(RandomAgent::new(2)))];
+        Driver::new(seats).run(&mut finished.lock()).unwrap();
+        registry.persist().unwrap();
+
+        let loaded = GameRegistry::load(store).unwrap();
+        assert_eq!(loaded.ids(), vec![playing.id()]);
+        // the finished game keeps its ID
+        assert!(loaded.create(builder(3)).unwrap().id() > finished.id());
+    }
+
+    /// An empty directory for a test to write to
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("canasta-registry-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn ids_are_unique_across_threads() {
+        let registry = Arc::new(GameRegistry::new());
+        let threads: Vec<_> = (0..4).map(|t| {
+            let registry = registry.clone();
+            thread::spawn(move || (0..25).map(|i| registry.create(builder(t * 25 + i)).unwrap()).collect::<Vec<_>>())
+        }).collect();
+        let handles: Vec<GameHandle> = threads.into_iter().flat_map(|t| t.join().unwrap()).collect();
+        let mut ids: Vec<u32> = handles.iter().map(|h| h.id()).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 100);
+        assert_eq!(registry.ids(), ids);
+        for handle in &handles {
+            assert_eq!(handle.lock().game_id(), handle.id());
+        }
+    }
+
+    #[test]
+    fn removed_games_can_still_be_played() {
+        let registry = GameRegistry::new();
+        let handle = registry.create(builder(1)).unwrap();
+        assert!(registry.remove(handle.id()).unwrap().is_some());
+        assert!(registry.remove(handle.id()).unwrap().is_none());
+        assert!(registry.is_empty());
+        let player = handle.lock().get_current_player();
+        handle.lock().draw(player).unwrap();
+        assert_eq!(handle.lock().get_phase(), TurnPhase::Meld);
+    }
+
+    #[test]
+    fn poisoned_game_can_still_be_used() {
+        let registry = GameRegistry::new();
+        let handle = registry.create(builder(1)).unwrap();
+        let other = handle.clone();
+        let result = thread::spawn(move || {
+            let _game = other.lock();
+            panic!("Panic while holding the game");
+        }).join();
+        assert!(result.is_err());
+        assert_eq!(handle.lock().num_players(), 2);
+        assert_eq!(registry.list()[0].id, handle.id());
+    }
+
+    #[test]
+    fn snapshot_drops_removed_games() {
+        let dir = temp_dir("removed");
+        let registry = GameRegistry::new();
+        let kept = registry.create(builder(1)).unwrap();
+        let removed = registry.create(builder(2)).unwrap();
+        assert_eq!(registry.snapshot(&dir).unwrap(), 2);
+        registry.remove(removed.id()).unwrap();
+        assert_eq!(registry.snapshot(&dir).unwrap(), 1);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        assert_eq!(GameRegistry::restore(&dir).unwrap().ids(), vec![kept.id()]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn restore_rejects_corrupt_games() {
+        let dir = temp_dir("bad");
+        fs::create_dir_all(&dir).unwrap();
+        // files that are not named after a game are ignored
+        fs::write(dir.join("notes.txt"), "not a game").unwrap();
+        fs::write(dir.join(format!("first.{EXTENSION}")), "").unwrap();
+        assert!(GameRegistry::restore(&dir).unwrap().is_empty());
+
+        fs::write(dir.join(format!("1.{EXTENSION}")), "not a game").unwrap();
+        assert!(matches!(GameRegistry::restore(&dir), Err(StoreError::Corrupt { id: 1, .. })));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,162 @@
+use crate::{game::CanastaGame, game_builder::GameBuilder, action_log::LogEntry, errors::replay_error::ReplayError};
+
+/// Rebuilds the state of a game from its seed and action log
+/// # Overview
+/// As every deck is shuffled from the game seed, applying the same actions to a new
+/// game with the same settings always gives the same result. The replayer uses this to
+/// reconstruct the game after any number of logged actions.
+///
+/// Each action is checked to give the same outcome as was logged, so a log that does
+/// not belong to the seed is detected.
+/// # Example
+/// ```
+/// use game_lib::{game::CanastaGame, replay::Replayer};
+/// let mut game = CanastaGame::builder().players(2).canastas(1).hand().seed(7).build().unwrap();
+/// let player = game.get_current_player();
+/// game.draw(player).unwrap();
+/// let card = game.get_hand(player).unwrap()[0].id();
+/// game.discard(player, card).unwrap();
+///
+/// let replayer = Replayer::from_game(&game);
+/// assert_eq!(replayer.len(), 2);
+/// // state before anything happened
+/// let start = replayer.state_at(0).unwrap();
+/// assert_eq!(start.get_current_player(), player);
+/// // state after the draw
+/// let drawn = replayer.state_at(1).unwrap();
+/// assert_eq!(drawn.get_hand(player).unwrap().len(), 16);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Replayer {
+    builder: GameBuilder,
+    entries: Vec<LogEntry>,
+}
+
+impl Replayer {
+    /// Create a replayer for the log of a game created by the builder
+    ///
+    /// The builder must have a seed set for the replay to match the original game
+    pub fn new(builder: GameBuilder, entries: Vec<LogEntry>) -> Self {
+        Self { builder, entries }
+    }
+
+    /// Create a replayer for the settings, seed and log of an existing game
+    pub fn from_game(game: &CanastaGame) -> Self {
+        let builder = CanastaGame::builder()
+            .players(game.num_players())
+            .canastas(game.canastas_to_go_out())
+            .seed(game.seed());
+        let builder = if game.is_full_game() { builder.full_game() } else { builder.hand() };
+        let builder = if game.undo_enabled() { builder.undo() } else { builder };
+        Self::new(builder, game.log().entries().to_vec())
+    }
+
+    /// Number of actions in the log
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> &[LogEntry] {
+        &self.entries
+    }
+
+    /// Rebuild the game after the first `step` actions have been applied
+    /// # Returns
+    /// - `Ok(CanastaGame)` - The state of the game after `step` actions
+    /// - `Err(ReplayError::StepOutOfRange)` - There are fewer than `step` actions in the log
+    /// - `Err(ReplayError::IllegalAction)` - A logged action could not be applied
+    /// - `Err(ReplayError::OutcomeMismatch)` - A logged action had a different outcome
+    pub fn state_at(&self, step: usize) -> Result<CanastaGame, ReplayError> {
+        if step > self.entries.len() { return Err(ReplayError::StepOutOfRange(step)) }
+        let mut game = self.builder.clone().build().ok_or(ReplayError::IncompleteBuilder)?;
+        for (i, entry) in self.entries[..step].iter().enumerate() {
+            let outcome = game.apply(entry.player, entry.action.clone())
+                .map_err(|source| ReplayError::IllegalAction { step: i, source })?;
+            if outcome != entry.outcome { return Err(ReplayError::OutcomeMismatch(i)) }
+        }
+        Ok(game)
+    }
+
+    /// Rebuild the game after every logged action
+    pub fn final_state(&self) -> Result<CanastaGame, ReplayError> {
+        self.state_at(self.entries.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{game::TurnPhase, action_log::{Action, ActionOutcome}, agents::{Agent, RandomAgent}};
+
+    use super::*;
+
+    /// A game played to the end by random agents
+    fn played_game(seed: u64) -> CanastaGame {
+        let mut game = CanastaGame::builder().players(3).canastas(1).hand().seed(seed).build().unwrap();
+        let mut agent = RandomAgent::new(seed);
+        while game.get_phase() != TurnPhase::GameOver {
+            let player = game.get_current_player();
+            let view = game.player_view(player).unwrap();
+            let action = agent.choose(&view, &game.legal_actions());
+            game.apply(player, action).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn replays_a_whole_game() {
+        for seed in 0..5 {
+            let game = played_game(seed);
+            let replayer = Replayer::from_game(&game);
+            let replayed = replayer.final_state().unwrap();
+            assert_eq!(replayed.log().entries(), game.log().entries());
+            assert_eq!(replayed.get_phase(), TurnPhase::GameOver);
+            assert_eq!(replayed.scores(), game.scores());
+            for player in 0..game.num_players() {
+                assert_eq!(replayed.get_hand(player).unwrap(), game.get_hand(player).unwrap());
+                assert_eq!(replayed.get_red_threes(player).unwrap(), game.get_red_threes(player).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn every_step_can_be_rebuilt() {
+        let game = played_game(9);
+        let replayer = Replayer::from_game(&game);
+        for step in (0..=replayer.len()).step_by(7) {
+            let state = replayer.state_at(step).unwrap();
+            assert_eq!(state.log().entries(), &game.log().entries()[..step]);
+        }
+        assert!(matches!(replayer.state_at(replayer.len() + 1), Err(ReplayError::StepOutOfRange(_))));
+    }
+
+    #[test]
+    fn changed_outcome_is_rejected() {
+        let game = played_game(3);
+        let mut entries = game.log().entries().to_vec();
+        let step = entries.iter().position(|e| e.action == Action::Draw).unwrap();
+        let ActionOutcome::Drew { card: Some(card), .. } = &mut entries[step].outcome else { panic!("Expected a drawn card") };
+        *card = card.wrapping_add(1);
+        let replayer = Replayer::new(Replayer::from_game(&game).builder, entries);
+        assert!(matches!(replayer.final_state(), Err(ReplayError::OutcomeMismatch(s)) if s == step));
+        // the state before the changed action can still be rebuilt
+        assert!(replayer.state_at(step).is_ok());
+    }
+
+    #[test]
+    fn log_of_another_seed_is_rejected() {
+        let game = played_game(4);
+        let builder = CanastaGame::builder().players(3).canastas(1).hand().seed(5);
+        let replayer = Replayer::new(builder, game.log().entries().to_vec());
+        assert!(matches!(replayer.final_state(), Err(ReplayError::OutcomeMismatch(_) | ReplayError::IllegalAction { .. })));
+    }
+
+    #[test]
+    fn incomplete_builder_is_rejected() {
+        let replayer = Replayer::new(CanastaGame::builder().players(2).seed(1), vec![]);
+        assert!(matches!(replayer.state_at(0), Err(ReplayError::IncompleteBuilder)));
+    }
+}
@@ -0,0 +1,387 @@
+use std::{fmt, sync::{Mutex, atomic::{AtomicUsize, Ordering}}, thread};
+
+use crate::{
+    game::{CanastaGame, PLAYERS},
+    agents::{AgentSpec, Driver},
+    events::{GameEvent, Viewer},
+    errors::simulation_error::SimulationError,
+};
+
+/// z value for a 95% confidence interval
+const Z_95: f64 = 1.96;
+
+/// Plays many seeded games between agents to compare how well they play
+/// # Overview
+/// Game `i` uses the seed `seed + i`. Agents move one seat to the left each game so
+/// every agent plays from every seat equally often. Games are shared between threads
+/// and the results are the same whatever the number of threads.
+///
+/// In duplicate mode each deal is played once for every seat rotation, so every agent
+/// is dealt the same cards and luck of the deal cancels out.
+/// # Example
+/// ```
+/// use game_lib::{simulation::Simulation, agents::AgentSpec};
+/// let report = Simulation::new(vec![AgentSpec::Random, "heuristic".parse().unwrap()])
+///     .games(4)
+///     .seed(10)
+///     .threads(2)
+///     .run()
+///     .unwrap();
+/// assert_eq!(report.games, 4);
+/// assert_eq!(report.agents[0].games, 4);
+/// println!("{report}");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Simulation {
+    agents: Vec<AgentSpec>,
+    games: u32,
+    seed: u64,
+    threads: usize,
+    canastas: u8,
+    full_game: bool,
+    duplicate: bool,
+}
+
+impl Simulation {
+    /// Create a simulation of single hands between the agents, one seat each
+    pub fn new(agents: Vec<AgentSpec>) -> Self {
+        Self {
+            agents,
+            games: 100,
+            seed: 0,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            canastas: 1,
+            full_game: false,
+            duplicate: false,
+        }
+    }
+
+    /// Number of games to play, or deals in duplicate mode
+    pub fn games(mut self, games: u32) -> Self {
+        self.games = games;
+        self
+    }
+
+    /// Seed of the first game
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Number of threads to play games on, defaults to the available parallelism
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Canastas needed to go out
+    pub fn canastas(mut self, canastas: u8) -> Self {
+        self.canastas = canastas;
+        self
+    }
+
+    /// Play full games to 5000 points rather than single hands
+    pub fn full_game(mut self) -> Self {
+        self.full_game = true;
+        self
+    }
+
+    pub fn hand(mut self) -> Self {
+        self.full_game = false;
+        self
+    }
+
+    /// Play every deal once for each seat rotation
+    ///
+    /// A simulation of `n` games between `p` agents plays `n * p` games.
+    pub fn duplicate(mut self) -> Self {
+        self.duplicate = true;
+        self
+    }
+
+    /// Play every game and collect the results
+    /// # Returns
+    /// - `Ok(SimulationReport)` - Results of every game and statistics for each agent
+    /// - `Err(SimulationError::InvalidPlayers)` - There must be 2 to 4 agents
+    /// - `Err(SimulationError::GameFailed)` - An agent chose an illegal action
+    pub fn run(&self) -> Result<SimulationReport, SimulationError> {
+        let players = self.agents.len();
+        if !u8::try_from(players).is_ok_and(|p| PLAYERS.contains(&p)) { return Err(SimulationError::InvalidPlayers(players)) }
+        let games = if self.duplicate { self.games as usize * players } else { self.games as usize };
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(Vec::with_capacity(games));
+        let failure = Mutex::new(None);
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(games.max(1)) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    if index >= games || failure.lock().unwrap().is_some() { break }
+                    match self.play(index as u32) {
+                        Ok(result) => results.lock().unwrap().push(result),
+                        Err(err) => { failure.lock().unwrap().get_or_insert(err); }
+                    }
+                });
+            }
+        });
+        if let Some(err) = failure.into_inner().unwrap() { return Err(err) }
+        let mut results = results.into_inner().unwrap();
+        results.sort_by_key(|r| r.index);
+        Ok(SimulationReport::new(&self.agents, results))
+    }
+
+    /// Play a single game of the simulation
+    fn play(&self, index: u32) -> Result<GameResult, SimulationError> {
+        let players = self.agents.len();
+        let deal = if self.duplicate { index / players as u32 } else { index };
+        let seed = self.seed.wrapping_add(deal as u64);
+        let builder = CanastaGame::builder().players(players as u8).canastas(self.canastas).seed(seed);
+        let mut builder = if self.full_game { builder.full_game() } else { builder.hand() };
+        let mut game = builder.build().expect("All settings are given");
+        let events = game.subscribe(Viewer::Spectator);
+
+        let seats: Vec<usize> = (0..players).map(|seat| (seat + index as usize) % players).collect();
+        let agents = seats.iter().enumerate()
+            .map(|(seat, &agent)| Some(self.agents[agent].build(seed.wrapping_mul(31).wrapping_add(seat as u64))))
+            .collect();
+        Driver::new(agents).run(&mut game).map_err(|source| SimulationError::GameFailed { seed, source })?;
+
+        let mut result = GameResult {
+            index,
+            seed,
+            seats,
+            scores: game.scores(),
+            hands: 0,
+            turns: 0,
+            canastas: vec![0; players],
+        };
+        for event in events.try_iter() {
+            match event {
+                GameEvent::CardDiscarded { .. } => result.turns += 1,
+                GameEvent::HandEnded { .. } => result.hands += 1,
+                GameEvent::CanastaCompleted { player, .. } => result.canastas[player as usize] += 1,
+                _ => {}
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// The result of one simulated game
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameResult {
+    /// Position of the game in the simulation
+    pub index: u32,
+    pub seed: u64,
+    /// Index of the agent in each seat
+    pub seats: Vec<usize>,
+    /// Final score of each seat
+    pub scores: Vec<i32>,
+    pub hands: u32,
+    /// Turns played across every hand
+    pub turns: u32,
+    /// Canastas completed by each seat
+    pub canastas: Vec<u32>,
+}
+
+impl GameResult {
+    /// Seats with the highest score
+    pub fn winners(&self) -> Vec<u8> {
+        let best = self.scores.iter().max().copied().unwrap_or(0);
+        (0..self.scores.len() as u8).filter(|&s| self.scores[s as usize] == best).collect()
+    }
+}
+
+/// How well one agent did across a simulation
+#[derive(Clone, Debug, PartialEq)]
+pub struct AgentStats {
+    pub name: String,
+    pub games: u32,
+    /// Games won, a tie shares the win between the tied agents
+    pub wins: f64,
+    pub win_rate: f64,
+    /// 95% Wilson score interval of the win rate
+    pub win_rate_interval: (f64, f64),
+    pub mean_score: f64,
+    /// 95% confidence interval of the mean score
+    pub score_interval: (f64, f64),
+    pub canastas_per_hand: f64,
+}
+
+/// Results of a simulation
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimulationReport {
+    pub games: u32,
+    pub hands: u32,
+    /// Average number of turns in a hand
+    pub mean_hand_length: f64,
+    /// Statistics for each agent, in the order they were given
+    pub agents: Vec<AgentStats>,
+    pub results: Vec<GameResult>,
+}
+
+impl SimulationReport {
+    /// Collect statistics for each agent from the results of the games
+    pub fn new(agents: &[AgentSpec], results: Vec<GameResult>) -> Self {
+        let hands: u32 = results.iter().map(|r| r.hands).sum();
+        let turns: u32 = results.iter().map(|r| r.turns).sum();
+        let agents = agents.iter().enumerate().map(|(agent, spec)| {
+            let mut wins = 0.0;
+            let mut scores = vec![];
+            let mut canastas = 0;
+            let mut agent_hands = 0;
+            for result in &results {
+                for (seat, _) in result.seats.iter().enumerate().filter(|(_, &a)| a == agent) {
+                    let winners = result.winners();
+                    if winners.contains(&(seat as u8)) { wins += 1.0 / winners.len() as f64 }
+                    scores.push(result.scores[seat] as f64);
+                    canastas += result.canastas[seat];
+                    agent_hands += result.hands;
+                }
+            }
+            let games = scores.len() as u32;
+            let win_rate = ratio(wins, games as f64);
+            let mean_score = ratio(scores.iter().sum(), games as f64);
+            AgentStats {
+                name: spec.to_string(),
+                games,
+                wins,
+                win_rate,
+                win_rate_interval: wilson_interval(win_rate, games),
+                mean_score,
+                score_interval: mean_interval(&scores, mean_score),
+                canastas_per_hand: ratio(canastas as f64, agent_hands as f64),
+            }
+        }).collect();
+        Self {
+            games: results.len() as u32,
+            hands,
+            mean_hand_length: ratio(turns as f64, hands as f64),
+            agents,
+            results,
+        }
+    }
+}
+
+impl fmt::Display for SimulationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} games, {} hands, {:.1} turns per hand", self.games, self.hands, self.mean_hand_length)?;
+        writeln!(f, "{:<16} {:>8} {:>17} {:>10} {:>21} {:>10}", "agent", "win %", "95% interval", "score", "95% interval", "canastas")?;
+        for (i, agent) in self.agents.iter().enumerate() {
+            let (win_low, win_high) = agent.win_rate_interval;
+            let (score_low, score_high) = agent.score_interval;
+            writeln!(
+                f,
+                "{:<16} {:>8.1} {:>8.1}-{:<8.1} {:>10.1} {:>10.1}-{:<10.1} {:>10.2}",
+                format!("{i}: {}", agent.name),
+                agent.win_rate * 100.0,
+                win_low * 100.0,
+                win_high * 100.0,
+                agent.mean_score,
+                score_low,
+                score_high,
+                agent.canastas_per_hand,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn ratio(value: f64, count: f64) -> f64 {
+    if count == 0.0 { 0.0 } else { value / count }
+}
+
+/// 95% Wilson score interval for a proportion
+fn wilson_interval(rate: f64, count: u32) -> (f64, f64) {
+    if count == 0 { return (0.0, 1.0) }
+    let n = count as f64;
+    let z2 = Z_95 * Z_95;
+    let centre = (rate + z2 / (2.0 * n)) / (1.0 + z2 / n);
+    let spread = Z_95 * (rate * (1.0 - rate) / n + z2 / (4.0 * n * n)).sqrt() / (1.0 + z2 / n);
+    ((centre - spread).max(0.0), (centre + spread).min(1.0))
+}
+
+/// 95% normal confidence interval for a mean
+fn mean_interval(values: &[f64], mean: f64) -> (f64, f64) {
+    if values.len() < 2 { return (mean, mean) }
+    let n = values.len() as f64;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    let spread = Z_95 * (variance / n).sqrt();
+    (mean - spread, mean + spread)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(actual: (f64, f64), expected: (f64, f64)) -> bool {
+        (actual.0 - expected.0).abs() < 1e-4 && (actual.1 - expected.1).abs() < 1e-4
+    }
+
+    #[test]
+    fn wilson_interval_matches_known_values() {
+        assert!(close(wilson_interval(0.5, 100), (0.40383, 0.59617)));
+        // never wins still leaves room for a low win rate
+        assert!(close(wilson_interval(0.0, 10), (0.0, 0.27754)));
+        assert!(close(wilson_interval(1.0, 10), (0.72246, 1.0)));
+        assert_eq!(wilson_interval(0.0, 0), (0.0, 1.0));
+    }
+
+    #[test]
+    fn mean_interval_matches_known_values() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert!(close(mean_interval(&values, 3.0), (1.61410, 4.38590)));
+        assert_eq!(mean_interval(&[7.0], 7.0), (7.0, 7.0));
+        assert_eq!(mean_interval(&[], 0.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn ties_share_the_win() {
+        let result = |index, seats: Vec<usize>, scores: Vec<i32>| GameResult {
+            index, seed: 0, seats, scores, hands: 1, turns: 10, canastas: vec![1, 0],
+        };
+        let results = vec![
+            result(0, vec![0, 1], vec![100, 100]),
+            result(1, vec![1, 0], vec![300, 200]),
+        ];
+        let report = SimulationReport::new(&[AgentSpec::Random, AgentSpec::Random], results);
+        assert_eq!(report.agents[0].wins, 0.5);
+        assert_eq!(report.agents[1].wins, 1.5);
+        assert_eq!(report.agents[1].win_rate, 0.75);
+        assert_eq!(report.agents[0].mean_score, 150.0);
+        assert_eq!(report.agents[0].canastas_per_hand, 0.5);
+        assert_eq!(report.mean_hand_length, 10.0);
+    }
+
+    #[test]
+    fn duplicate_plays_every_deal_from_every_seat() {
+        let agents = vec![AgentSpec::Random, "heuristic".parse().unwrap(), AgentSpec::Random];
+        let report = Simulation::new(agents).games(2).seed(5).threads(2).duplicate().run().unwrap();
+        assert_eq!(report.games, 6);
+        for deal in report.results.chunks(3) {
+            assert!(deal.iter().all(|r| r.seed == deal[0].seed));
+            // each agent sits in each seat once for the deal
+            for seat in 0..3 {
+                let mut agents: Vec<usize> = deal.iter().map(|r| r.seats[seat]).collect();
+                agents.sort();
+                assert_eq!(agents, [0, 1, 2]);
+            }
+        }
+        assert_eq!(report.results[0].seed, 5);
+        assert_eq!(report.results[3].seed, 6);
+        assert!(report.agents.iter().all(|a| a.games == 6));
+    }
+
+    #[test]
+    fn results_do_not_depend_on_threads() {
+        let simulation = Simulation::new(vec![AgentSpec::Random, AgentSpec::Random]).games(6).seed(2);
+        let one = simulation.clone().threads(1).run().unwrap();
+        let many = simulation.threads(4).run().unwrap();
+        assert_eq!(one, many);
+    }
+
+    #[test]
+    fn agent_count_is_checked() {
+        assert_eq!(Simulation::new(vec![AgentSpec::Random]).run(), Err(SimulationError::InvalidPlayers(1)));
+        assert_eq!(Simulation::new(vec![AgentSpec::Random; 5]).run(), Err(SimulationError::InvalidPlayers(5)));
+    }
+}
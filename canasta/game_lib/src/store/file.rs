@@ -0,0 +1,185 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, ErrorKind, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{game::CanastaGame, action_log::LogEntry, errors::store_error::StoreError};
+
+use super::{GameStore, write_game, entry_line, read_game};
+
+/// File extension of the games kept by a `FileStore`
+pub const EXTENSION: &str = "canasta";
+
+/// Keeps each game as a file in a directory
+/// # Overview
+/// Files are named after the ID of the game and hold it in the format described by
+/// `GameStore`. Saving writes a temporary file then renames it so a crash never leaves
+/// half a game, appending adds a line to the end of the file. A crash while appending
+/// can at worst lose the action being appended, the part of its line that was written
+/// is cut off by the next append.
+/// # Example
+/// ```
+/// use game_lib::{game::CanastaGame, store::{GameStore, FileStore}};
+/// let dir = std::env::temp_dir().join(format!("canasta-store-{}", std::process::id()));
+/// let store = FileStore::open(&dir).unwrap();
+/// let game = CanastaGame::builder().players(2).canastas(1).hand().seed(3).build().unwrap();
+/// store.save(&game).unwrap();
+/// assert_eq!(store.list().unwrap(), vec![0]);
+/// store.delete(0).unwrap();
+/// assert!(store.load(0).is_err());
+/// std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+impl FileStore {
+    /// Uses the directory for games, creating it if needed
+    pub fn open(dir: &Path) -> Result<Self, StoreError> {
+        fs::create_dir_all(dir)?;
+        Ok(Self { dir: dir.to_path_buf() })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, id: u32) -> PathBuf {
+        self.dir.join(format!("{id}.{EXTENSION}"))
+    }
+}
+
+/// Turns a missing file into `StoreError::NotFound`
+fn not_found(id: u32) -> impl Fn(std::io::Error) -> StoreError {
+    move |err| match err.kind() {
+        ErrorKind::NotFound => StoreError::NotFound(id),
+        _ => StoreError::Io(err),
+    }
+}
+
+/// Cuts off a last line left without a line ending by a crash while appending, so the
+/// next action starts a line of its own
+fn drop_partial_line(file: &mut File) -> io::Result<()> {
+    if file.metadata()?.len() == 0 { return Ok(()) }
+    let mut last = [0];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last)?;
+    if last[0] == b'\n' { return Ok(()) }
+    let mut text = vec![];
+    file.seek(SeekFrom::Start(0))?;
+    file.read_to_end(&mut text)?;
+    let complete = text.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+    file.set_len(complete as u64)
+}
+
+impl GameStore for FileStore {
+    fn save(&self, game: &CanastaGame) -> Result<(), StoreError> {
+        let path = self.path(game.game_id());
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, write_game(game))?;
+        fs::rename(&temp, &path)?;
+        Ok(())
+    }
+
+    fn load(&self, id: u32) -> Result<CanastaGame, StoreError> {
+        let text = fs::read_to_string(self.path(id)).map_err(not_found(id))?;
+        read_game(id, &text)
+    }
+
+    fn list(&self) -> Result<Vec<u32>, StoreError> {
+        let mut ids = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|e| e != EXTENSION) { continue }
+            if let Some(id) = path.file_stem().and_then(|s| s.to_str()).and_then(|s| s.parse().ok()) {
+                ids.push(id);
+            }
+        }
+        ids.sort_unstable();
+        Ok(ids)
+    }
+
+    fn delete(&self, id: u32) -> Result<(), StoreError> {
+        fs::remove_file(self.path(id)).map_err(not_found(id))
+    }
+
+    fn append(&self, id: u32, number: usize, entry: &LogEntry) -> Result<(), StoreError> {
+        let mut file = OpenOptions::new().read(true).append(true).open(self.path(id)).map_err(not_found(id))?;
+        drop_partial_line(&mut file)?;
+        writeln!(file, "{}", entry_line(number, entry))?;
+        file.sync_data()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{game::CanastaGame, registry::GameRegistry};
+
+    use super::*;
+
+    fn temp_store(name: &str) -> FileStore {
+        let dir = std::env::temp_dir().join(format!("canasta-file-store-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        FileStore::open(&dir).unwrap()
+    }
+
+    fn new_game() -> CanastaGame {
+        CanastaGame::builder().players(2).canastas(1).hand().seed(7).build().unwrap()
+    }
+
+    /// Plays the current player's turn, drawing then discarding their first card
+    fn play_turn(game: &mut CanastaGame) {
+        let player = game.get_current_player();
+        game.draw(player).unwrap();
+        let card = game.get_hand(player).unwrap()[0].id();
+        game.discard(player, card).unwrap();
+    }
+
+    #[test]
+    fn appended_actions_load_back() {
+        let store = temp_store("round-trip");
+        let mut game = new_game();
+        store.save(&game).unwrap();
+        for _ in 0..3 { play_turn(&mut game) }
+        for (index, entry) in game.log().entries().iter().enumerate() {
+            store.append(game.game_id(), index + 1, entry).unwrap();
+        }
+        let loaded = store.load(game.game_id()).unwrap();
+        assert_eq!(loaded.log().len(), game.log().len());
+        for player in 0..2 {
+            assert_eq!(loaded.get_hand(player).unwrap(), game.get_hand(player).unwrap());
+        }
+        fs::remove_dir_all(store.dir()).unwrap();
+    }
+
+    #[test]
+    fn append_after_partial_line() {
+        let store = temp_store("partial");
+        let registry = GameRegistry::new().store(Arc::new(store.clone()));
+        let handle = registry.register(new_game());
+        handle.persist().unwrap();
+        play_turn(&mut handle.lock());
+        handle.persist().unwrap();
+        // a crash cut the last append short
+        let path = store.path(handle.id());
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "3 P0 dr").unwrap();
+        drop(file);
+
+        let registry = GameRegistry::load(Arc::new(store.clone())).unwrap();
+        let handle = registry.get(handle.id()).unwrap();
+        assert_eq!(handle.lock().log().len(), 2);
+        play_turn(&mut handle.lock());
+        handle.persist().unwrap();
+
+        let loaded = store.load(handle.id()).unwrap();
+        assert_eq!(loaded.log().len(), 4);
+        assert!(!fs::read_to_string(&path).unwrap().contains("dr\n"));
+        fs::remove_dir_all(store.dir()).unwrap();
+    }
+}
@@ -0,0 +1,87 @@
+use std::{collections::BTreeMap, sync::{Mutex, MutexGuard}};
+
+use crate::{game::CanastaGame, action_log::LogEntry, errors::store_error::StoreError};
+
+use super::{GameStore, write_game, entry_line, read_game};
+
+/// Keeps games in memory, for tests and games that need not survive the process
+///
+/// Games are kept as the same text a `FileStore` writes, so loading them goes through
+/// the same replay.
+/// # Example
+/// ```
+/// use game_lib::{game::CanastaGame, store::{GameStore, MemoryStore}};
+/// let store = MemoryStore::new();
+/// let mut game = CanastaGame::builder().players(2).canastas(1).hand().seed(3).build().unwrap();
+/// store.save(&game).unwrap();
+///
+/// let player = game.get_current_player();
+/// game.draw(player).unwrap();
+/// store.append(game.game_id(), game.log().len(), game.log().last().unwrap()).unwrap();
+///
+/// let loaded = store.load(game.game_id()).unwrap();
+/// assert_eq!(loaded.get_hand(player).unwrap(), game.get_hand(player).unwrap());
+/// assert_eq!(store.list().unwrap(), vec![0]);
+/// ```
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    games: Mutex<BTreeMap<u32, String>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self { games: Mutex::new(BTreeMap::new()) }
+    }
+
+    fn games(&self) -> MutexGuard<'_, BTreeMap<u32, String>> {
+        self.games.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl GameStore for MemoryStore {
+    fn save(&self, game: &CanastaGame) -> Result<(), StoreError> {
+        self.games().insert(game.game_id(), write_game(game));
+        Ok(())
+    }
+
+    fn load(&self, id: u32) -> Result<CanastaGame, StoreError> {
+        let text = self.games().get(&id).cloned().ok_or(StoreError::NotFound(id))?;
+        read_game(id, &text)
+    }
+
+    fn list(&self) -> Result<Vec<u32>, StoreError> {
+        Ok(self.games().keys().copied().collect())
+    }
+
+    fn delete(&self, id: u32) -> Result<(), StoreError> {
+        self.games().remove(&id).map(|_| ()).ok_or(StoreError::NotFound(id))
+    }
+
+    fn append(&self, id: u32, number: usize, entry: &LogEntry) -> Result<(), StoreError> {
+        let mut games = self.games();
+        let text = games.get_mut(&id).ok_or(StoreError::NotFound(id))?;
+        text.push_str(&entry_line(number, entry));
+        text.push('\n');
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_games_are_not_found() {
+        let store = MemoryStore::new();
+        let mut game = CanastaGame::builder().players(2).canastas(1).hand().seed(3).build().unwrap();
+        let player = game.get_current_player();
+        game.draw(player).unwrap();
+        let entry = game.log().last().unwrap();
+        assert!(matches!(store.append(0, 1, entry), Err(StoreError::NotFound(0))));
+        assert!(matches!(store.load(0), Err(StoreError::NotFound(0))));
+        assert!(matches!(store.delete(0), Err(StoreError::NotFound(0))));
+        store.save(&game).unwrap();
+        store.delete(0).unwrap();
+        assert!(store.list().unwrap().is_empty());
+    }
+}
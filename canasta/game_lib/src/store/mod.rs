@@ -0,0 +1,230 @@
+pub mod file;
+pub mod memory;
+
+pub use file::FileStore;
+pub use memory::MemoryStore;
+
+use crate::{game::CanastaGame, action_log::{Action, LogEntry}, card::Rank, errors::store_error::StoreError};
+
+/// Somewhere durable to keep games, so they outlive the process playing them
+/// # Overview
+/// A game is saved whole once, then each action is appended as it is applied, so a
+/// store is never more than one action behind the game. Loading a game replays its
+/// actions from its seed.
+///
+/// Games are kept by `CanastaGame::game_id`, a `GameRegistry` given a store saves its
+/// games to it and can restore every game in progress after a crash, see
+/// `GameRegistry::load`.
+///
+/// Stores are shared between threads, each method takes `&self`.
+/// # Format
+/// Games are kept as text, the rules followed by one logged action per line. Unlike a
+/// `GameRecord` cards are written as their ID, so a loaded game holds exactly the same
+/// cards as the game saved, along with which actions were played on timeout.
+/// ```text
+/// players 2
+/// canastas 1
+/// game hand
+/// seed 42
+/// undo no
+///
+/// 1 P1 draw
+/// 2 P1 meld K 12 64 80
+/// 3 P1 commit
+/// 4 P1 discard 7 timeout
+/// ```
+pub trait GameStore: Send + Sync {
+    /// Saves a whole game, replacing any game stored with its ID
+    fn save(&self, game: &CanastaGame) -> Result<(), StoreError>;
+
+    /// Reads a game back, with its ID set
+    /// # Returns
+    /// - `Ok(CanastaGame)` - The game with every stored action applied
+    /// - `Err(StoreError::NotFound)` - There is no game with the ID
+    /// - `Err(StoreError::Record)` - The stored game could not be replayed
+    fn load(&self, id: u32) -> Result<CanastaGame, StoreError>;
+
+    /// IDs of every stored game, in order
+    fn list(&self) -> Result<Vec<u32>, StoreError>;
+
+    /// Removes a game
+    /// # Returns
+    /// - `Ok(())` - The game was removed
+    /// - `Err(StoreError::NotFound)` - There is no game with the ID
+    fn delete(&self, id: u32) -> Result<(), StoreError>;
+
+    /// Adds an action to the end of a stored game
+    /// # Parameters
+    /// - `number` - Position of the entry in the log of the game, counting from 1.
+    ///   Entries must be appended in order
+    /// # Returns
+    /// - `Ok(())` - The action was stored
+    /// - `Err(StoreError::NotFound)` - There is no game with the ID
+    fn append(&self, id: u32, number: usize, entry: &LogEntry) -> Result<(), StoreError>;
+}
+
+/// Rules a stored game is created with, in the order they are written
+const RULES: [&str; 5] = ["players", "canastas", "game", "seed", "undo"];
+
+/// Writes a game in the store format, see `GameStore`
+pub(crate) fn write_game(game: &CanastaGame) -> String {
+    let mut text = format!(
+        "players {}\ncanastas {}\ngame {}\nseed {}\nundo {}\n\n",
+        game.num_players(),
+        game.canastas_to_go_out(),
+        if game.is_full_game() { "full" } else { "hand" },
+        game.seed(),
+        if game.undo_enabled() { "yes" } else { "no" },
+    );
+    for (index, entry) in game.log().entries().iter().enumerate() {
+        text.push_str(&entry_line(index + 1, entry));
+        text.push('\n');
+    }
+    text
+}
+
+/// A line holding a single logged action, `number` is its position in the log from 1
+pub(crate) fn entry_line(number: usize, entry: &LogEntry) -> String {
+    let ids = |ids: &[u8]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(" ");
+    let action = match &entry.action {
+        Action::Draw => "draw".to_string(),
+        Action::TakeDiscard(cards) => format!("take {}", ids(cards)),
+        Action::Meld { cards, rank } => format!("meld {rank} {}", ids(cards)),
+        Action::Unmeld(cards) => format!("unmeld {}", ids(cards)),
+        Action::ClearMeld => "clear".to_string(),
+        Action::CommitMeld => "commit".to_string(),
+        Action::Discard(card) => format!("discard {card}"),
+        Action::Undo => "undo".to_string(),
+        Action::Redo => "redo".to_string(),
+    };
+    let timeout = if entry.timed_out { " timeout" } else { "" };
+    format!("{number} P{} {action}{timeout}", entry.player)
+}
+
+/// Reads a game in the store format back, giving it its ID
+///
+/// A last line without a line ending was cut short while being appended and is ignored.
+pub(crate) fn read_game(id: u32, text: &str) -> Result<CanastaGame, StoreError> {
+    let complete = if text.ends_with('\n') { text } else { &text[..text.rfind('\n').map_or(0, |i| i + 1)] };
+    let mut lines = complete.lines().enumerate().map(|(i, l)| (i + 1, l.trim()));
+    let corrupt = |line: usize, message: &str| StoreError::Corrupt { id, line, message: message.to_string() };
+
+    let mut rules = vec![];
+    for (line, content) in lines.by_ref() {
+        if content.is_empty() { break }
+        let (name, value) = content.split_once(' ').ok_or_else(|| corrupt(line, "Rules must be written as a name and value"))?;
+        if RULES.get(rules.len()) != Some(&name) { return Err(corrupt(line, &format!("Unexpected rule {name}"))) }
+        rules.push((line, value.trim()));
+    }
+    let [players, canastas, game_kind, seed, undo] = rules[..] else { return Err(corrupt(rules.len() + 1, "Missing rules")) };
+    let number = |(line, value): (usize, &str)| value.parse::<u64>().map_err(|_| corrupt(line, "Expected a number"));
+    let count = |(line, value): (usize, &str)| value.parse::<u8>().map_err(|_| corrupt(line, "Expected a number up to 255"));
+    let builder = CanastaGame::builder()
+        .players(count(players)?)
+        .canastas(count(canastas)?)
+        .seed(number(seed)?);
+    let builder = match game_kind.1 {
+        "full" => builder.full_game(),
+        "hand" => builder.hand(),
+        _ => return Err(corrupt(game_kind.0, "Game must be full or hand")),
+    };
+    let mut builder = match undo.1 {
+        "yes" => builder.undo(),
+        "no" => builder,
+        _ => return Err(corrupt(undo.0, "Undo must be yes or no")),
+    };
+    let mut game = builder.build().ok_or_else(|| corrupt(players.0, "Invalid rules"))?;
+    game.set_game_id(id);
+
+    for (line, content) in lines {
+        if content.is_empty() { continue }
+        let (number, player, action, timed_out) = read_entry(content).ok_or_else(|| corrupt(line, "Invalid action"))?;
+        if number != game.log().len() + 1 {
+            return Err(corrupt(line, &format!("Expected action {}, found {number}", game.log().len() + 1)))
+        }
+        game.apply(player, action).map_err(|source| StoreError::IllegalAction { id, line, source })?;
+        if timed_out { game.log_mut().mark_timed_out() }
+    }
+    Ok(game)
+}
+
+/// Reads a line written by `entry_line`
+fn read_entry(content: &str) -> Option<(usize, u8, Action, bool)> {
+    let mut words = content.split_whitespace();
+    let number = words.next()?.parse().ok()?;
+    let player = words.next()?.strip_prefix('P')?.parse().ok()?;
+    let verb = words.next()?;
+    let rank = if verb == "meld" { Some(words.next()?.parse::<Rank>().ok()?) } else { None };
+    let mut ids = vec![];
+    let mut timed_out = false;
+    for word in words {
+        match word {
+            "timeout" => timed_out = true,
+            id if !timed_out => ids.push(id.parse().ok()?),
+            _ => return None,
+        }
+    }
+    let action = match (verb, ids.as_slice()) {
+        ("draw", []) => Action::Draw,
+        ("take", _) => Action::TakeDiscard(ids),
+        ("meld", _) => Action::Meld { cards: ids, rank: rank? },
+        ("unmeld", _) => Action::Unmeld(ids),
+        ("clear", []) => Action::ClearMeld,
+        ("commit", []) => Action::CommitMeld,
+        ("discard", [id]) => Action::Discard(*id),
+        ("undo", []) => Action::Undo,
+        ("redo", []) => Action::Redo,
+        _ => return None,
+    };
+    Some((number, player, action, timed_out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: &str = "players 2\ncanastas 1\ngame hand\nseed 42\nundo no\n\n";
+
+    fn corrupt_line(text: &str) -> Option<usize> {
+        match read_game(0, text) {
+            Err(StoreError::Corrupt { line, .. }) => Some(line),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn written_game_reads_back() {
+        let mut game = CanastaGame::builder().players(3).canastas(2).full_game().undo().seed(9).build().unwrap();
+        let player = game.get_current_player();
+        game.draw(player).unwrap();
+        game.time_out(player).unwrap();
+        let read = read_game(4, &write_game(&game)).unwrap();
+        assert_eq!(read.game_id(), 4);
+        assert_eq!(write_game(&read), write_game(&game));
+        assert!(read.log().last().unwrap().timed_out);
+    }
+
+    #[test]
+    fn counts_too_large_are_corrupt() {
+        assert_eq!(corrupt_line(&RULES.replace("players 2", "players 258")), Some(1));
+        assert_eq!(corrupt_line(&RULES.replace("canastas 1", "canastas 257")), Some(2));
+    }
+
+    #[test]
+    fn unplayable_player_count_is_corrupt() {
+        assert_eq!(corrupt_line(&RULES.replace("players 2", "players 5")), Some(1));
+        assert_eq!(corrupt_line(&RULES.replace("players 2", "players 1")), Some(1));
+    }
+
+    #[test]
+    fn partial_last_line_is_ignored() {
+        let game = read_game(0, &format!("{RULES}1 P0 draw\n2 P0 disc")).unwrap();
+        assert_eq!(game.log().len(), 1);
+    }
+
+    #[test]
+    fn entries_out_of_order_are_corrupt() {
+        assert_eq!(corrupt_line(&format!("{RULES}2 P0 draw\n")), Some(7));
+        assert_eq!(corrupt_line(&format!("{RULES}1 P0 draw now\n")), Some(7));
+    }
+}
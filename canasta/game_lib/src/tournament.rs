@@ -0,0 +1,342 @@
+use std::{collections::BTreeMap, fmt, fs, io::ErrorKind, path::{Path, PathBuf}, thread};
+
+use crate::{
+    agents::AgentSpec,
+    simulation::Simulation,
+    errors::tournament_error::TournamentError,
+};
+
+/// Rating given to an agent that has not played before
+pub const INITIAL_RATING: f64 = 1500.0;
+/// Most a rating can change from a single match
+const K_FACTOR: f64 = 24.0;
+
+/// Round robin tournament between agents using duplicate deals
+/// # Overview
+/// Every pair of agents plays a match on each deal. A match is the same seeded deal
+/// played twice with the agents swapping seats, so both agents hold each set of cards.
+/// The agent with the larger total score difference across the two games wins the match.
+///
+/// Standings count match wins, draws and losses along with the total score margin.
+/// Each match also updates the Elo rating of both agents. Ratings can be loaded from and
+/// saved to a file so they carry over between tournaments, agents are identified by
+/// their name. Names include any settings changed from the defaults, see `AgentSpec`.
+/// # Example
+/// ```
+/// use game_lib::{tournament::Tournament, agents::AgentSpec};
+/// let agents = vec![AgentSpec::Random, "heuristic".parse().unwrap(), AgentSpec::Random];
+/// let report = Tournament::new(agents).deals(2).seed(5).run().unwrap();
+/// // 3 pairings with 2 matches each
+/// assert_eq!(report.matches.len(), 6);
+/// assert_eq!(report.standings[0].played, 4);
+/// println!("{report}");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Tournament {
+    agents: Vec<AgentSpec>,
+    deals: u32,
+    seed: u64,
+    threads: usize,
+    canastas: u8,
+    full_game: bool,
+    ratings: Option<PathBuf>,
+}
+
+impl Tournament {
+    pub fn new(agents: Vec<AgentSpec>) -> Self {
+        Self {
+            agents,
+            deals: 50,
+            seed: 0,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            canastas: 1,
+            full_game: false,
+            ratings: None,
+        }
+    }
+
+    /// Number of deals each pair of agents plays
+    pub fn deals(mut self, deals: u32) -> Self {
+        self.deals = deals;
+        self
+    }
+
+    /// Seed of the first deal, every pairing plays the same deals
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    pub fn canastas(mut self, canastas: u8) -> Self {
+        self.canastas = canastas;
+        self
+    }
+
+    /// Play full games to 5000 points rather than single hands
+    pub fn full_game(mut self) -> Self {
+        self.full_game = true;
+        self
+    }
+
+    pub fn hand(mut self) -> Self {
+        self.full_game = false;
+        self
+    }
+
+    /// File ratings are loaded from before the tournament and saved to after it
+    pub fn ratings_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ratings = Some(path.into());
+        self
+    }
+
+    /// Name of each agent, numbered when the same agent is entered more than once
+    fn names(&self) -> Vec<String> {
+        let names: Vec<String> = self.agents.iter().map(|a| a.to_string()).collect();
+        names.iter().enumerate().map(|(i, name)| {
+            let total = names.iter().filter(|n| *n == name).count();
+            let earlier = names[..i].iter().filter(|n| *n == name).count();
+            if total == 1 { name.clone() } else { format!("{name}#{}", earlier + 1) }
+        }).collect()
+    }
+
+    /// Play every match of the tournament
+    /// # Returns
+    /// - `Ok(TournamentReport)` - Standings, match results and updated ratings
+    /// - `Err(TournamentError)` - Fewer than 2 agents, a game failed or the ratings file
+    ///   could not be read or written
+    pub fn run(&self) -> Result<TournamentReport, TournamentError> {
+        if self.agents.len() < 2 { return Err(TournamentError::TooFewAgents) }
+        let names = self.names();
+        let mut ratings = match &self.ratings {
+            Some(path) => Ratings::load(path)?,
+            None => Ratings::new(),
+        };
+        let mut matches = vec![];
+        for first in 0..self.agents.len() {
+            for second in first + 1..self.agents.len() {
+                let simulation = Simulation::new(vec![self.agents[first].clone(), self.agents[second].clone()])
+                    .games(self.deals)
+                    .seed(self.seed)
+                    .threads(self.threads)
+                    .canastas(self.canastas)
+                    .duplicate();
+                let simulation = if self.full_game { simulation.full_game() } else { simulation };
+                let report = simulation.run()?;
+                // each deal is played by two consecutive games with the seats swapped
+                for games in report.results.chunks(2) {
+                    let margin = games.iter().map(|game| {
+                        let seat = game.seats.iter().position(|&a| a == 0).expect("Agent is seated");
+                        game.scores[seat] - game.scores[1 - seat]
+                    }).sum();
+                    let result = MatchResult { agents: (first, second), seed: games[0].seed, margin };
+                    ratings.update(&names[first], &names[second], result.score());
+                    matches.push(result);
+                }
+            }
+        }
+        if let Some(path) = &self.ratings { ratings.save(path)? }
+        Ok(TournamentReport::new(names, matches, ratings))
+    }
+}
+
+/// The result of a pair of agents playing one deal from both seats
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MatchResult {
+    /// Index of the two agents
+    pub agents: (usize, usize),
+    pub seed: u64,
+    /// Total score difference in favour of the first agent
+    pub margin: i32,
+}
+
+impl MatchResult {
+    /// Match score of the first agent, 1 for a win, 0.5 for a draw and 0 for a loss
+    pub fn score(&self) -> f64 {
+        match self.margin {
+            m if m > 0 => 1.0,
+            0 => 0.5,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Tournament record of one agent
+#[derive(Clone, Debug, PartialEq)]
+pub struct Standing {
+    /// Index of the agent
+    pub agent: usize,
+    pub name: String,
+    pub played: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    /// Total score difference over every match
+    pub margin: i64,
+    /// Rating after the tournament
+    pub rating: f64,
+}
+
+impl Standing {
+    /// Match points, 1 for a win and 0.5 for a draw
+    pub fn points(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 * 0.5
+    }
+}
+
+/// Results of a tournament
+#[derive(Clone, Debug, PartialEq)]
+pub struct TournamentReport {
+    /// Agents ordered by points, then margin
+    pub standings: Vec<Standing>,
+    pub matches: Vec<MatchResult>,
+    pub ratings: Ratings,
+}
+
+impl TournamentReport {
+    fn new(names: Vec<String>, matches: Vec<MatchResult>, ratings: Ratings) -> Self {
+        let mut standings: Vec<Standing> = names.into_iter().enumerate().map(|(agent, name)| Standing {
+            agent,
+            rating: ratings.get(&name).rating,
+            name,
+            played: 0,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+            margin: 0,
+        }).collect();
+        for result in &matches {
+            let (first, second) = result.agents;
+            for (agent, margin) in [(first, result.margin), (second, -result.margin)] {
+                let standing = &mut standings[agent];
+                standing.played += 1;
+                standing.margin += margin as i64;
+                match margin.signum() {
+                    1 => standing.wins += 1,
+                    0 => standing.draws += 1,
+                    _ => standing.losses += 1,
+                }
+            }
+        }
+        standings.sort_by(|a, b| b.points().total_cmp(&a.points()).then(b.margin.cmp(&a.margin)));
+        Self { standings, matches, ratings }
+    }
+}
+
+impl fmt::Display for TournamentReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<4} {:<16} {:>6} {:>5} {:>5} {:>5} {:>8} {:>9} {:>8}", "", "agent", "played", "won", "drawn", "lost", "points", "margin", "rating")?;
+        for (place, s) in self.standings.iter().enumerate() {
+            writeln!(
+                f,
+                "{:<4} {:<16} {:>6} {:>5} {:>5} {:>5} {:>8.1} {:>9} {:>8.0}",
+                place + 1, s.name, s.played, s.wins, s.draws, s.losses, s.points(), s.margin, s.rating,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Elo rating of an agent
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rating {
+    pub rating: f64,
+    /// Matches the rating is based on
+    pub matches: u32,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Self { rating: INITIAL_RATING, matches: 0 }
+    }
+}
+
+/// Elo ratings of agents by name
+/// # Overview
+/// Ratings are stored in a text file with one agent per line, giving the name, rating
+/// and number of matches separated by tabs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Ratings {
+    ratings: BTreeMap<String, Rating>,
+}
+
+impl Ratings {
+    pub fn new() -> Self {
+        Self { ratings: BTreeMap::new() }
+    }
+
+    /// Read ratings from a file, a file that does not exist gives no ratings
+    pub fn load(path: &Path) -> Result<Self, TournamentError> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Self::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut ratings = Self::new();
+        for (line_number, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            let invalid = || TournamentError::InvalidRatings(line_number + 1);
+            let mut fields = line.split('\t');
+            let (Some(name), Some(rating), Some(matches), None) = (fields.next(), fields.next(), fields.next(), fields.next()) else {
+                return Err(invalid())
+            };
+            let rating = Rating {
+                rating: rating.parse().map_err(|_| invalid())?,
+                matches: matches.parse().map_err(|_| invalid())?,
+            };
+            ratings.ratings.insert(name.to_string(), rating);
+        }
+        Ok(ratings)
+    }
+
+    /// Write every rating to a file, replacing its contents
+    pub fn save(&self, path: &Path) -> Result<(), TournamentError> {
+        let text: String = self.ratings.iter()
+            .map(|(name, r)| format!("{name}\t{:.2}\t{}\n", r.rating, r.matches))
+            .collect();
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// Rating of an agent, agents that have not played have the initial rating
+    pub fn get(&self, name: &str) -> Rating {
+        self.ratings.get(name).copied().unwrap_or_default()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Rating)> {
+        self.ratings.iter().map(|(name, rating)| (name.as_str(), rating))
+    }
+
+    /// Update the ratings of two agents after a match
+    ///
+    /// `score` is the result for the first agent, 1 for a win, 0.5 for a draw and 0 for a loss
+    pub fn update(&mut self, first: &str, second: &str, score: f64) {
+        let a = self.get(first);
+        let b = self.get(second);
+        let expected = 1.0 / (1.0 + 10f64.powf((b.rating - a.rating) / 400.0));
+        let change = K_FACTOR * (score - expected);
+        self.ratings.insert(first.to_string(), Rating { rating: a.rating + change, matches: a.matches + 1 });
+        self.ratings.insert(second.to_string(), Rating { rating: b.rating - change, matches: b.matches + 1 });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agents::HeuristicWeights;
+
+    use super::*;
+
+    #[test]
+    fn configurations_are_rated_apart() {
+        let tuned = HeuristicWeights { canasta: 900.0, ..Default::default() };
+        let agents = vec![AgentSpec::Heuristic(Default::default()), AgentSpec::Heuristic(tuned)];
+        let report = Tournament::new(agents).deals(1).seed(2).run().unwrap();
+        let names: Vec<&str> = report.standings.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&"heuristic"));
+        assert!(names.iter().all(|n| !n.contains('#')));
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{card::{PlayCard, Rank}, game::TurnPhase};
+
+/// A committed meld as seen on the table
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MeldView {
+    pub rank: Rank,
+    /// Natural cards followed by wild cards
+    pub cards: Vec<PlayCard>,
+    pub natural: bool,
+    pub canasta: bool,
+}
+
+/// What everyone can see about a seat at the table
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SeatView {
+    pub player: u8,
+    pub hand_size: usize,
+    pub melds: Vec<MeldView>,
+    pub red_threes: Vec<PlayCard>,
+    /// Cards in the hand that every player knows about, from taking the discard pile
+    ///
+    /// Cards are removed once they are melded or discarded.
+    pub known_cards: Vec<PlayCard>,
+    pub score: i32,
+}
+
+/// Public information about the game that every viewer can see
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TableView {
+    pub seats: Vec<SeatView>,
+    /// Only the top of the discard pile is shown, the cards under it are squared up
+    pub discard_top: Option<PlayCard>,
+    pub discard_size: usize,
+    pub pile_frozen: bool,
+    pub stock_count: usize,
+    pub current_player: u8,
+    pub phase: TurnPhase,
+    /// Number of hands that have been completed
+    pub hand_number: u32,
+    pub canastas_to_go_out: u8,
+}
+
+/// Everything a seated player is allowed to know
+///
+/// Created with `CanastaGame::player_view`
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlayerView {
+    pub player: u8,
+    pub hand: Vec<PlayCard>,
+    /// Cards the player has staged, by rank
+    pub staged: Vec<(Rank, Vec<PlayCard>)>,
+    pub table: TableView,
+}
+
+/// Everything someone watching the game is allowed to know
+///
+/// Created with `CanastaGame::spectator_view`
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpectatorView {
+    pub table: TableView,
+}
+
+impl PlayerView {
+    /// The seat of the player the view is for
+    pub fn seat(&self) -> &SeatView {
+        &self.table.seats[self.player as usize]
+    }
+
+    /// Seats of every other player
+    pub fn opponents(&self) -> impl Iterator<Item = &SeatView> {
+        self.table.seats.iter().filter(|s| s.player != self.player)
+    }
+
+    /// If it is the viewing players turn
+    pub fn is_turn(&self) -> bool {
+        self.table.current_player == self.player && self.table.phase != TurnPhase::GameOver
+    }
+}
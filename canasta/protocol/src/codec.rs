@@ -0,0 +1,198 @@
+use std::io::{BufRead, Read, Write};
+
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
+
+use crate::errors::protocol_error::ProtocolError;
+
+/// Largest message that will be read, in bytes, counting the newline of a JSON message
+pub const MAX_FRAME: usize = 1 << 20;
+
+/// How messages are written on a connection, see the crate documentation
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    /// One line of JSON per message
+    #[default]
+    Json,
+    /// Length prefixed bincode
+    Binary,
+}
+
+/// Writes a message and flushes the writer
+pub fn write(writer: &mut impl Write, encoding: Encoding, message: &impl Serialize) -> Result<(), ProtocolError> {
+    let bytes = encode(encoding, message)?;
+    writer.write_all(&bytes)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads the next message
+/// # Returns
+/// - `Ok(Some(message))` - The next message
+/// - `Ok(None)` - The connection was closed
+/// - `Err(ProtocolError)` - The message could not be read or decoded
+pub fn read<T: DeserializeOwned>(reader: &mut impl BufRead, encoding: Encoding) -> Result<Option<T>, ProtocolError> {
+    match encoding {
+        Encoding::Json => {
+            let mut line = String::new();
+            loop {
+                line.clear();
+                if (&mut *reader).take(MAX_FRAME as u64).read_line(&mut line)? == 0 { return Ok(None) }
+                if line.len() == MAX_FRAME && !line.ends_with('\n') { return Err(ProtocolError::LineTooLong) }
+                if !line.trim().is_empty() { break }
+            }
+            Ok(Some(serde_json::from_str(&line)?))
+        }
+        Encoding::Binary => {
+            let mut length = [0; 4];
+            if reader.fill_buf()?.is_empty() { return Ok(None) }
+            reader.read_exact(&mut length)?;
+            let length = u32::from_be_bytes(length) as usize;
+            if length > MAX_FRAME { return Err(ProtocolError::TooLarge(length)) }
+            let mut bytes = vec![0; length];
+            reader.read_exact(&mut bytes)?;
+            Ok(Some(bincode::deserialize(&bytes)?))
+        }
+    }
+}
+
+/// A message as the bytes that `write` sends, including the newline or length
+/// # Returns
+/// - `Ok(Vec<u8>)` - The bytes of the message
+/// - `Err(ProtocolError::TooLarge)` - The message is larger than `MAX_FRAME` and would be
+///   refused by `read`
+pub fn encode(encoding: Encoding, message: &impl Serialize) -> Result<Vec<u8>, ProtocolError> {
+    match encoding {
+        Encoding::Json => {
+            let mut bytes = serde_json::to_vec(message)?;
+            bytes.push(b'\n');
+            if bytes.len() > MAX_FRAME { return Err(ProtocolError::TooLarge(bytes.len())) }
+            Ok(bytes)
+        }
+        Encoding::Binary => {
+            let body = bincode::serialize(message)?;
+            if body.len() > MAX_FRAME { return Err(ProtocolError::TooLarge(body.len())) }
+            let mut bytes = (body.len() as u32).to_be_bytes().to_vec();
+            bytes.extend(body);
+            Ok(bytes)
+        }
+    }
+}
+
+/// Reads a message from the bytes of one `encode`d message
+/// # Returns
+/// - `Ok(T)` - The message
+/// - `Err(ProtocolError::LengthMismatch)` - The length prefix of a binary message is not
+///   the length of the rest of the bytes
+/// - `Err(ProtocolError)` - The message is too large or could not be decoded
+pub fn decode<'a, T: Deserialize<'a>>(encoding: Encoding, bytes: &'a [u8]) -> Result<T, ProtocolError> {
+    match encoding {
+        Encoding::Json => {
+            if bytes.len() > MAX_FRAME { return Err(ProtocolError::TooLarge(bytes.len())) }
+            Ok(serde_json::from_slice(bytes)?)
+        }
+        Encoding::Binary => {
+            let (length, body) = bytes.split_first_chunk::<4>().ok_or(ProtocolError::MissingLength)?;
+            let prefix = u32::from_be_bytes(*length) as usize;
+            if prefix > MAX_FRAME { return Err(ProtocolError::TooLarge(prefix)) }
+            if prefix != body.len() { return Err(ProtocolError::LengthMismatch { prefix, body: body.len() }) }
+            Ok(bincode::deserialize(body)?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, BufReader};
+
+    use crate::{ClientMessage, Request};
+
+    use super::*;
+
+    fn view(id: u32) -> ClientMessage {
+        ClientMessage::Request { id, request: Request::View }
+    }
+
+    #[test]
+    fn messages_read_in_order() {
+        for encoding in [Encoding::Json, Encoding::Binary] {
+            let mut bytes = vec![];
+            for id in 0..3 { write(&mut bytes, encoding, &view(id)).unwrap() }
+            let mut reader = bytes.as_slice();
+            for id in 0..3 {
+                assert_eq!(read::<ClientMessage>(&mut reader, encoding).unwrap(), Some(view(id)));
+            }
+            assert!(read::<ClientMessage>(&mut reader, encoding).unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn blank_lines_are_skipped() {
+        let mut bytes = b"\n  \n".to_vec();
+        bytes.extend(encode(Encoding::Json, &view(7)).unwrap());
+        assert_eq!(read::<ClientMessage>(&mut bytes.as_slice(), Encoding::Json).unwrap(), Some(view(7)));
+    }
+
+    #[test]
+    fn endless_line_is_rejected() {
+        // a peer that never sends a newline
+        let mut reader = BufReader::new(io::repeat(b' '));
+        assert!(matches!(read::<ClientMessage>(&mut reader, Encoding::Json), Err(ProtocolError::LineTooLong)));
+    }
+
+    #[test]
+    fn line_at_the_limit_is_read() {
+        let mut bytes = encode(Encoding::Json, &view(1)).unwrap();
+        bytes.splice(0..0, vec![b' '; MAX_FRAME - bytes.len()]);
+        assert_eq!(bytes.len(), MAX_FRAME);
+        assert_eq!(read::<ClientMessage>(&mut bytes.as_slice(), Encoding::Json).unwrap(), Some(view(1)));
+        bytes.insert(0, b' ');
+        assert!(matches!(read::<ClientMessage>(&mut bytes.as_slice(), Encoding::Json), Err(ProtocolError::LineTooLong)));
+    }
+
+    #[test]
+    fn decode_reads_encoded_messages() {
+        for encoding in [Encoding::Json, Encoding::Binary] {
+            let bytes = encode(encoding, &view(5)).unwrap();
+            assert_eq!(decode::<ClientMessage>(encoding, &bytes).unwrap(), view(5));
+        }
+    }
+
+    #[test]
+    fn length_prefix_must_match() {
+        let bytes = encode(Encoding::Binary, &view(5)).unwrap();
+        let body = bytes.len() - 4;
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert!(matches!(
+            decode::<ClientMessage>(Encoding::Binary, &longer),
+            Err(ProtocolError::LengthMismatch { prefix, body: b }) if prefix == body && b == body + 1
+        ));
+        assert!(matches!(
+            decode::<ClientMessage>(Encoding::Binary, &bytes[..bytes.len() - 1]),
+            Err(ProtocolError::LengthMismatch { .. })
+        ));
+        // the body alone is not a message
+        assert!(decode::<ClientMessage>(Encoding::Binary, &bytes[4..]).is_err());
+        assert!(matches!(decode::<ClientMessage>(Encoding::Binary, &bytes[..3]), Err(ProtocolError::MissingLength)));
+        let mut large = ((MAX_FRAME + 1) as u32).to_be_bytes().to_vec();
+        large.extend([0; 16]);
+        assert!(matches!(decode::<ClientMessage>(Encoding::Binary, &large), Err(ProtocolError::TooLarge(_))));
+    }
+
+    #[test]
+    fn large_json_is_not_encoded() {
+        let name = "x".repeat(MAX_FRAME);
+        let request = ClientMessage::Request { id: 1, request: Request::SitDown { table: 1, name, seat: None } };
+        for encoding in [Encoding::Json, Encoding::Binary] {
+            assert!(matches!(encode(encoding, &request), Err(ProtocolError::TooLarge(_))));
+        }
+    }
+
+    #[test]
+    fn large_frame_is_rejected() {
+        let mut bytes = ((MAX_FRAME + 1) as u32).to_be_bytes().to_vec();
+        bytes.extend([0; 16]);
+        assert!(matches!(read::<ClientMessage>(&mut bytes.as_slice(), Encoding::Binary), Err(ProtocolError::TooLarge(_))));
+    }
+}
@@ -0,0 +1,19 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ProtocolError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Invalid JSON message: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid binary message: {0}")]
+    Binary(#[from] bincode::Error),
+    #[error("Message of {0} bytes is larger than the limit")]
+    TooLarge(usize),
+    #[error("JSON message has no line ending within {} bytes", crate::codec::MAX_FRAME)]
+    LineTooLong,
+    #[error("Binary message has a length prefix of {prefix} bytes but a body of {body} bytes")]
+    LengthMismatch { prefix: usize, body: usize },
+    #[error("Binary message is too short to hold its length prefix")]
+    MissingLength,
+}
@@ -0,0 +1,76 @@
+//! Messages sent between Canasta clients and servers
+//!
+//! Both the server and clients use these types, so they always agree on the format.
+//! Game types such as `Action`, `GameEvent` and `PlayerView` are sent as they are
+//! defined in `game_lib`, with cards sent as their ID.
+//!
+//! # Handshake
+//! After connecting the client sends a `Hello` as a single line of JSON, giving the
+//! protocol version it speaks and the encoding it wants for the rest of the connection:
+//! ```text
+//! {"version":4,"encoding":"binary"}
+//! ```
+//! The server answers with one line of JSON, a `Welcome` if it speaks the version or a
+//! `ServerMessage::Error` with the code `unsupported_version` before closing the
+//! connection:
+//! ```text
+//! {"welcome":{"version":4}}
+//! ```
+//! Every later message uses the chosen encoding.
+//!
+//! # Encodings
+//! - `json` - Each message is one line of JSON. Enums are written as an object with the
+//!   variant name as the only key, such as `{"request":{"id":3,"request":"view"}}`
+//! - `binary` - Each message is a 4 byte big endian length followed by that many bytes
+//!   of bincode
+//!
+//! Messages over `MAX_FRAME` bytes are rejected in either encoding.
+//!
+//! # Requests
+//! The client sends a `ClientMessage::Request` with an ID of its choosing. The server
+//! answers each request with a `ServerMessage::Reply` or `ServerMessage::Error` carrying
+//! the same ID, so replies can be matched to requests. Events and views pushed by the
+//! server after any player acts have no ID and may arrive between a request and its
+//! reply.
+//!
+//! # Reconnecting
+//! Events pushed to a seat are numbered. A client that loses its connection connects
+//! again and sends `Request::Join` with the number of the last event it saw, the reply
+//! holds a snapshot of the table and the events it missed. Clients should send
+//! `Request::Ack` from time to time so the server can forget events they have seen.
+//!
+//! # Lobby
+//! Players find games through tables. A client creates a table with `TableRules` or
+//! picks one from `Request::Tables`, sits down and may fill the other seats with bots.
+//! Everyone seated is sent `TableChanged` as seats fill, and once every seat is filled
+//! the game starts and each player is sent `TableStarted` with the token for their seat.
+//!
+//! # Clocks
+//! A server may limit how long turns and games take. Seats are sent
+//! `ServerMessage::Clock` whenever a turn starts, and a player who runs out of time has
+//! their turn played for them.
+//! # Example
+//! ```
+//! use protocol::{Encoding, ClientMessage, Request, codec};
+//! let message = ClientMessage::Request { id: 1, request: Request::View };
+//! for encoding in [Encoding::Json, Encoding::Binary] {
+//!     let mut bytes = vec![];
+//!     codec::write(&mut bytes, encoding, &message).unwrap();
+//!     let read: ClientMessage = codec::read(&mut bytes.as_slice(), encoding).unwrap().unwrap();
+//!     assert_eq!(read, message);
+//! }
+//! ```
+
+pub mod message;
+pub mod lobby;
+pub mod codec;
+pub mod errors;
+
+pub use message::{Hello, ClientMessage, Request, ServerMessage, Reply, SeatStatus, ErrorCode, ErrorMessage};
+pub use codec::{Encoding, MAX_FRAME};
+pub use lobby::{TableRules, Variant, SeatInfo, TableInfo};
+
+/// Version of the protocol defined by this crate
+///
+/// Changed whenever a message changes in a way older peers cannot read.
+pub const PROTOCOL_VERSION: u16 = 4;
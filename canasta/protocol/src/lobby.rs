@@ -0,0 +1,63 @@
+use serde::{Serialize, Deserialize};
+
+use game_lib::{game::CanastaGame, game_builder::GameBuilder};
+
+/// Rule variations a table can be played with
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Variant {
+    /// Standard rules
+    #[default]
+    Classic,
+    /// Players may undo actions that reveal nothing, for casual and teaching games
+    Casual,
+}
+
+/// Rules chosen when creating a table
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableRules {
+    /// Number of seats, 2 to 4
+    pub players: u8,
+    /// Canastas needed to go out
+    pub canastas: u8,
+    /// Play to 5000 points rather than a single hand
+    pub full_game: bool,
+    pub variant: Variant,
+}
+
+impl Default for TableRules {
+    /// Two players, a full game needing 2 canastas to go out
+    fn default() -> Self {
+        Self { players: 2, canastas: 2, full_game: true, variant: Variant::Classic }
+    }
+}
+
+impl TableRules {
+    /// A builder for games with the rules
+    pub fn builder(&self) -> GameBuilder {
+        let builder = CanastaGame::builder().players(self.players).canastas(self.canastas);
+        let builder = if self.full_game { builder.full_game() } else { builder.hand() };
+        match self.variant {
+            Variant::Classic => builder,
+            Variant::Casual => builder.undo(),
+        }
+    }
+}
+
+/// Who is sitting in a seat of a table that has not started
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SeatInfo {
+    Empty,
+    Player { name: String },
+    /// A bot, given as an agent such as `heuristic` or `ismcts:200`
+    Bot { agent: String },
+}
+
+/// A table waiting for players
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableInfo {
+    pub table: u32,
+    pub rules: TableRules,
+    pub seats: Vec<SeatInfo>,
+}
@@ -0,0 +1,201 @@
+use std::fmt;
+
+use serde::{Serialize, Deserialize};
+
+use game_lib::{
+    action_log::{Action, ActionOutcome},
+    events::GameEvent,
+    view::PlayerView,
+};
+
+use crate::{codec::Encoding, lobby::{TableRules, TableInfo}};
+
+/// First message of a connection, always sent as JSON
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hello {
+    /// Protocol version the client speaks, see `PROTOCOL_VERSION`
+    pub version: u16,
+    /// Encoding of every message after the handshake
+    pub encoding: Encoding,
+}
+
+/// A message sent from a client to the server after the handshake
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// A request, answered by a reply or error with the same ID
+    Request { id: u32, request: Request },
+}
+
+/// Something a client asks the server to do
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Request {
+    /// Take the seat of a game that the token was issued for
+    ///
+    /// A client rejoining after losing its connection gives the sequence number of the
+    /// last event it saw to be sent the events it missed.
+    Join { game: u32, token: String, last_seq: Option<u64> },
+    /// Apply an action for the joined seat
+    Act { action: Action },
+    /// Ask for the current view of the table
+    View,
+    /// Every event up to the sequence number has been seen, so the server can forget it
+    Ack { seq: u64 },
+    /// List the tables waiting for players
+    Tables,
+    /// Open a new table, the client is not seated at it. A table no one sits at is
+    /// closed after a while
+    CreateTable { rules: TableRules },
+    /// Sit at a table under a name, in the seat or the first empty seat
+    SitDown { table: u32, name: String, seat: Option<u8> },
+    /// Leave the seat taken with `SitDown`
+    StandUp,
+    /// Put a bot in the seat, or in every empty seat, of the table the client sits at
+    AddBot { agent: String, seat: Option<u8> },
+    /// Leave the seat, the server closes the connection after replying
+    Leave,
+}
+
+/// A message sent from the server to a client
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Answer to a `Hello`, always sent as JSON
+    Welcome { version: u16 },
+    /// The request with the ID succeeded
+    Reply { id: u32, reply: Reply },
+    /// The request with the ID failed, `None` if the message could not be read at all
+    Error { id: Option<u32>, error: ErrorMessage },
+    /// Something happened in the game, redacted for the seat
+    ///
+    /// Events of a seat are numbered from 1 in the order they happened.
+    Event { seq: u64, event: GameEvent },
+    /// The table as the seat sees it, pushed after every action
+    View { view: PlayerView },
+    /// A seat was taken, lost its connection or was handed to a bot
+    Seat { seat: u8, status: SeatStatus },
+    /// Someone sat down or stood up at the table the client sits at
+    TableChanged { table: TableInfo },
+    /// Every seat of the table is filled and its game has started, join it with the token
+    TableStarted { table: u32, game: u32, seat: u8, token: String },
+    /// The turn of a player started in a game with a time control, or the seat joined
+    ///
+    /// Times are the milliseconds left, `None` where the game has no such limit. When
+    /// the turn runs out it is played for the player and `GameEvent::TurnTimedOut` is
+    /// sent.
+    Clock { player: u8, turn_ms: Option<u64>, game_ms: Option<Vec<u64>> },
+}
+
+/// Result of a successful request
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reply {
+    /// The seat was taken, with the table and every event after `last_seq` still held
+    Joined { game: u32, seat: u8, view: PlayerView, events: Vec<(u64, GameEvent)> },
+    Outcome { outcome: ActionOutcome },
+    View { view: PlayerView },
+    Acked,
+    Tables { tables: Vec<TableInfo> },
+    /// A table was created or changed by the request
+    Table { table: TableInfo },
+    Seated { table: u32, seat: u8 },
+    Stood,
+    Left,
+}
+
+/// Who is playing a seat
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SeatStatus {
+    /// A client holds the seat
+    Connected,
+    /// The client of the seat lost its connection and may come back
+    Away,
+    /// A bot is playing the seat until its player comes back
+    Bot,
+}
+
+/// Kind of failure, for clients to act on without reading the message
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The server does not speak the protocol version of the client
+    UnsupportedVersion,
+    /// The message could not be read
+    InvalidMessage,
+    UnknownGame,
+    InvalidToken,
+    /// The request needs a seat, join a game first
+    NotSeated,
+    AlreadySeated,
+    /// The action is not legal in the game
+    IllegalAction,
+    /// The game is waiting for a player to reconnect
+    Paused,
+    UnknownTable,
+    /// The rules or bot asked for are not allowed
+    InvalidRules,
+    /// The seat is taken or the table is full
+    SeatTaken,
+}
+
+/// Why a request failed
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorMessage {
+    pub code: ErrorCode,
+    /// Plain text description of the failure
+    pub message: String,
+}
+
+impl ErrorMessage {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+impl fmt::Display for ErrorMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    /// The JSON a message is written as, and that it reads back to the message
+    fn shape<T: Serialize + serde::de::DeserializeOwned + PartialEq + fmt::Debug>(message: &T) -> Value {
+        let value = serde_json::to_value(message).unwrap();
+        assert_eq!(&serde_json::from_value::<T>(value.clone()).unwrap(), message);
+        value
+    }
+
+    #[test]
+    fn handshake_shapes() {
+        assert_eq!(shape(&ServerMessage::Welcome { version: 4 }), json!({"welcome": {"version": 4}}));
+        assert_eq!(shape(&Hello { version: 4, encoding: Encoding::Binary }), json!({"version": 4, "encoding": "binary"}));
+    }
+
+    #[test]
+    fn request_shapes() {
+        let view = ClientMessage::Request { id: 3, request: Request::View };
+        assert_eq!(shape(&view), json!({"request": {"id": 3, "request": "view"}}));
+        let join = ClientMessage::Request { id: 1, request: Request::Join { game: 2, token: "ab".to_string(), last_seq: None } };
+        assert_eq!(shape(&join), json!({"request": {"id": 1, "request": {"join": {"game": 2, "token": "ab", "last_seq": null}}}}));
+        let act = ClientMessage::Request { id: 4, request: Request::Act { action: Action::Discard(7) } };
+        assert_eq!(shape(&act), json!({"request": {"id": 4, "request": {"act": {"action": {"Discard": 7}}}}}));
+    }
+
+    #[test]
+    fn server_message_shapes() {
+        let error = ServerMessage::Error { id: None, error: ErrorMessage::new(ErrorCode::Paused, "Wait") };
+        assert_eq!(shape(&error), json!({"error": {"id": null, "error": {"code": "paused", "message": "Wait"}}}));
+        let seat = ServerMessage::Seat { seat: 1, status: SeatStatus::Bot };
+        assert_eq!(shape(&seat), json!({"seat": {"seat": 1, "status": "bot"}}));
+        let reply = ServerMessage::Reply { id: 9, reply: Reply::Acked };
+        assert_eq!(shape(&reply), json!({"reply": {"id": 9, "reply": "acked"}}));
+    }
+}
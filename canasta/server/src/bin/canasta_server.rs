@@ -0,0 +1,64 @@
+//! Hosts Canasta games for clients connecting over TCP
+//!
+//! Usage: `canasta-server [--addr ADDR] [--games N] [--players N] [--canastas N] [--full | --hand] [--grace SECONDS] [--pause] [--turn-time SECONDS] [--game-time SECONDS]`
+//!
+//! Creates the games, prints the ID of each game and the token of each seat, then
+//! serves clients until stopped. Messages are described in the `protocol` crate.
+//!
+//! A player who loses their connection is waited for the grace period, 60 seconds by
+//! default, then a bot plays for them until they come back. With `--pause` the game
+//! waits for them however long it takes.
+//!
+//! Clients may also open tables in the lobby and start games once every seat is
+//! filled, with `--games 0` only lobby games are hosted.
+//!
+//! `--turn-time` and `--game-time` limit how long each turn and each players whole
+//! game may take, a player out of time has their turn played for them.
+
+use std::time::Duration;
+
+use args::Args;
+use game_lib::{game::CanastaGame, clock::TimeControl};
+use server::{Server, ReconnectConfig};
+
+const USAGE: &str = "Usage: canasta-server [--addr ADDR] [--games N] [--players N] [--canastas N] [--full | --hand] [--grace SECONDS] [--pause] [--turn-time SECONDS] [--game-time SECONDS]";
+
+fn main() {
+    let mut args = Args::new(USAGE);
+    let mut addr = "127.0.0.1:7878".to_string();
+    let mut games: u32 = 1;
+    let mut reconnect = ReconnectConfig::default();
+    let mut time = TimeControl::new();
+    let mut builder = CanastaGame::builder().players(2).canastas(2).full_game();
+    while let Some(arg) = args.next() {
+        builder = match arg.as_str() {
+            "--addr" => { addr = args.value("an address"); builder }
+            "--games" => { games = args.number(); builder }
+            "--players" => builder.players(args.number()),
+            "--canastas" => builder.canastas(args.number()),
+            "--full" => builder.full_game(),
+            "--hand" => builder.hand(),
+            "--grace" => { reconnect = reconnect.grace(Duration::from_secs(args.number())); builder }
+            "--pause" => { reconnect = reconnect.pause(); builder }
+            "--turn-time" => { time = time.turn(Duration::from_secs(args.number())); builder }
+            "--game-time" => { time = time.game(Duration::from_secs(args.number())); builder }
+            "-h" | "--help" => args.exit(""),
+            other => args.exit(&format!("Unknown argument {other}")),
+        };
+    }
+
+    let server = match Server::bind(&addr) {
+        Ok(server) => server.reconnect(reconnect).clock(time),
+        Err(err) => args.exit(&format!("Could not listen on {addr}: {err}")),
+    };
+    for _ in 0..games {
+        let Some(game) = builder.build() else { args.exit("Could not create the game") };
+        let hosted = server.host(game);
+        println!("Game {}", hosted.game);
+        for (seat, token) in hosted.tokens.iter().enumerate() {
+            println!("  seat {seat}: {token}");
+        }
+    }
+    println!("Listening on {addr}");
+    server.run();
+}
@@ -0,0 +1,206 @@
+use std::{
+    collections::VecDeque,
+    io::BufReader,
+    net::{TcpStream, ToSocketAddrs},
+};
+
+use game_lib::{action_log::{Action, ActionOutcome}, events::GameEvent, view::PlayerView};
+use protocol::{PROTOCOL_VERSION, Encoding, Hello, ClientMessage, Request, ServerMessage, Reply, TableRules, TableInfo, codec};
+
+use crate::errors::server_error::ServerError;
+
+/// A seat taken back after losing a connection, see `Client::rejoin`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rejoined {
+    pub seat: u8,
+    /// The table as it is now
+    pub view: PlayerView,
+    /// Events missed since the sequence number given, that the server still holds
+    pub events: Vec<(u64, GameEvent)>,
+}
+
+/// Blocking connection to a `Server`
+///
+/// Each request is given the next ID and the reply with that ID is waited for.
+/// Messages that arrive while waiting, such as events, are kept and given by later
+/// calls to `recv`. The sequence number of the last event given is kept for `rejoin`.
+pub struct Client {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    encoding: Encoding,
+    next_id: u32,
+    pending: VecDeque<ServerMessage>,
+    last_seq: Option<u64>,
+}
+
+impl Client {
+    /// Connects using JSON messages
+    pub fn connect(addr: impl ToSocketAddrs) -> Result<Self, ServerError> {
+        Self::connect_with(addr, Encoding::Json)
+    }
+
+    /// Connects and completes the handshake, using the encoding for every later message
+    /// # Returns
+    /// - `Ok(Client)` - The server speaks the same protocol version
+    /// - `Err(ServerError::Rejected)` - The server does not speak the version
+    pub fn connect_with(addr: impl ToSocketAddrs, encoding: Encoding) -> Result<Self, ServerError> {
+        let mut writer = TcpStream::connect(addr)?;
+        let mut reader = BufReader::new(writer.try_clone()?);
+        codec::write(&mut writer, Encoding::Json, &Hello { version: PROTOCOL_VERSION, encoding })?;
+        match codec::read(&mut reader, Encoding::Json)? {
+            Some(ServerMessage::Welcome { .. }) => {}
+            Some(ServerMessage::Error { error, .. }) => return Err(ServerError::Rejected(error)),
+            Some(_) => return Err(ServerError::UnexpectedMessage),
+            None => return Err(ServerError::Disconnected),
+        }
+        Ok(Self { reader, writer, encoding, next_id: 1, pending: VecDeque::new(), last_seq: None })
+    }
+
+    /// Sends a request without waiting for the reply
+    /// # Returns
+    /// The ID of the request, which its reply will carry
+    pub fn send(&mut self, request: Request) -> Result<u32, ServerError> {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        codec::write(&mut self.writer, self.encoding, &ClientMessage::Request { id, request })?;
+        Ok(id)
+    }
+
+    /// Waits for the next message from the server
+    /// # Returns
+    /// - `Ok(ServerMessage)` - The next message
+    /// - `Err(ServerError::Disconnected)` - The server closed the connection
+    pub fn recv(&mut self) -> Result<ServerMessage, ServerError> {
+        let message = match self.pending.pop_front() {
+            Some(message) => message,
+            None => codec::read(&mut self.reader, self.encoding)?.ok_or(ServerError::Disconnected)?,
+        };
+        if let ServerMessage::Event { seq, .. } = message { self.last_seq = Some(seq) }
+        Ok(message)
+    }
+
+    /// Sequence number of the last event given by `recv`
+    pub fn last_seq(&self) -> Option<u64> {
+        self.last_seq
+    }
+
+    /// Sends a request and waits for its reply
+    /// # Returns
+    /// - `Ok(Reply)` - The request succeeded
+    /// - `Err(ServerError::Rejected)` - The request failed, with the reason
+    pub fn request(&mut self, request: Request) -> Result<Reply, ServerError> {
+        let id = self.send(request)?;
+        loop {
+            let message = codec::read(&mut self.reader, self.encoding)?.ok_or(ServerError::Disconnected)?;
+            match message {
+                ServerMessage::Reply { id: reply_id, reply } if reply_id == id => return Ok(reply),
+                ServerMessage::Error { id: error_id, error } if error_id.is_none_or(|e| e == id) => {
+                    return Err(ServerError::Rejected(error))
+                }
+                message => self.pending.push_back(message),
+            }
+        }
+    }
+
+    /// Takes a seat of a game
+    /// # Returns
+    /// - `Ok(u8)` - The seat taken
+    /// - `Err(ServerError::Rejected)` - There is no such game or the token is wrong
+    pub fn join(&mut self, game: u32, token: &str) -> Result<u8, ServerError> {
+        self.rejoin(game, token, None).map(|rejoined| rejoined.seat)
+    }
+
+    /// Takes a seat back after losing a connection
+    /// # Parameters
+    /// - `last_seq` - Sequence number of the last event seen, from `last_seq` of the lost
+    ///   client
+    /// # Returns
+    /// - `Ok(Rejoined)` - The seat, the table now and the events missed
+    /// - `Err(ServerError::Rejected)` - There is no such game or the token is wrong
+    pub fn rejoin(&mut self, game: u32, token: &str, last_seq: Option<u64>) -> Result<Rejoined, ServerError> {
+        match self.request(Request::Join { game, token: token.to_string(), last_seq })? {
+            Reply::Joined { seat, view, events, .. } => {
+                self.last_seq = events.last().map(|(seq, _)| *seq).or(last_seq);
+                Ok(Rejoined { seat, view, events })
+            }
+            _ => Err(ServerError::UnexpectedMessage),
+        }
+    }
+
+    /// Tells the server every event given by `recv` has been seen
+    pub fn ack(&mut self) -> Result<(), ServerError> {
+        let Some(seq) = self.last_seq else { return Ok(()) };
+        self.request(Request::Ack { seq }).map(|_| ())
+    }
+
+    /// Applies an action for the seat taken
+    /// # Returns
+    /// - `Ok(ActionOutcome)` - The action was applied
+    /// - `Err(ServerError::Rejected)` - The action is not legal, with the reason
+    pub fn act(&mut self, action: Action) -> Result<ActionOutcome, ServerError> {
+        match self.request(Request::Act { action })? {
+            Reply::Outcome { outcome } => Ok(outcome),
+            _ => Err(ServerError::UnexpectedMessage),
+        }
+    }
+
+    /// The table as the seat taken sees it
+    pub fn view(&mut self) -> Result<PlayerView, ServerError> {
+        match self.request(Request::View)? {
+            Reply::View { view } => Ok(view),
+            _ => Err(ServerError::UnexpectedMessage),
+        }
+    }
+
+    /// Tables in the lobby waiting for players
+    pub fn tables(&mut self) -> Result<Vec<TableInfo>, ServerError> {
+        match self.request(Request::Tables)? {
+            Reply::Tables { tables } => Ok(tables),
+            _ => Err(ServerError::UnexpectedMessage),
+        }
+    }
+
+    /// Opens a lobby table with the rules, without sitting at it
+    pub fn create_table(&mut self, rules: TableRules) -> Result<TableInfo, ServerError> {
+        match self.request(Request::CreateTable { rules })? {
+            Reply::Table { table } => Ok(table),
+            _ => Err(ServerError::UnexpectedMessage),
+        }
+    }
+
+    /// Sits at a lobby table, in the seat or the first empty seat
+    /// # Returns
+    /// - `Ok(u8)` - The seat taken
+    /// - `Err(ServerError::Rejected)` - The table does not exist or the seat is taken
+    pub fn sit_down(&mut self, table: u32, name: &str, seat: Option<u8>) -> Result<u8, ServerError> {
+        match self.request(Request::SitDown { table, name: name.to_string(), seat })? {
+            Reply::Seated { seat, .. } => Ok(seat),
+            _ => Err(ServerError::UnexpectedMessage),
+        }
+    }
+
+    /// Leaves the lobby table sat at
+    pub fn stand_up(&mut self) -> Result<(), ServerError> {
+        self.request(Request::StandUp).map(|_| ())
+    }
+
+    /// Puts a bot such as `heuristic` in a seat, or every empty seat, of the table sat at
+    pub fn add_bot(&mut self, agent: &str, seat: Option<u8>) -> Result<TableInfo, ServerError> {
+        match self.request(Request::AddBot { agent: agent.to_string(), seat })? {
+            Reply::Table { table } => Ok(table),
+            _ => Err(ServerError::UnexpectedMessage),
+        }
+    }
+
+    /// Waits for the lobby table sat at to start, then joins its game
+    /// # Returns
+    /// The ID of the game and the seat joined
+    pub fn join_when_started(&mut self) -> Result<(u32, u8), ServerError> {
+        loop {
+            if let ServerMessage::TableStarted { game, token, .. } = self.recv()? {
+                let seat = self.join(game, &token)?;
+                return Ok((game, seat))
+            }
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum LobbyError {
+    #[error("No table {0}")]
+    UnknownTable(u32),
+    #[error("Invalid rules: {0}")]
+    InvalidRules(String),
+    #[error("Seat {0} is taken")]
+    SeatTaken(u8),
+    #[error("Table has no seat {0}")]
+    InvalidSeat(u8),
+    #[error("Every seat of the table is taken")]
+    TableFull,
+    #[error("Table has empty seats")]
+    NotFull,
+    #[error("No player sits in seat {0}")]
+    NotSeated(u8),
+}
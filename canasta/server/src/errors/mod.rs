@@ -0,0 +1,2 @@
+pub mod server_error;
+pub mod lobby_error;
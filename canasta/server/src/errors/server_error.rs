@@ -0,0 +1,17 @@
+use thiserror::Error;
+
+use protocol::{ErrorMessage, errors::protocol_error::ProtocolError};
+
+#[derive(Error, Debug)]
+pub enum ServerError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Protocol(#[from] ProtocolError),
+    #[error("Server rejected the request: {0}")]
+    Rejected(ErrorMessage),
+    #[error("Server sent an unexpected message")]
+    UnexpectedMessage,
+    #[error("Connection closed")]
+    Disconnected,
+}
@@ -0,0 +1,17 @@
+//! Hosts Canasta games for players connecting over TCP
+//!
+//! A `Server` holds many games, each seat of a game has a secret token that a client
+//! gives to take the seat. Clients send requests such as actions and are sent the
+//! replies, the events of the game and their view of the table, redacted so they only
+//! see what their seat may know. Messages are defined by the `protocol` crate.
+
+pub mod server;
+pub mod client;
+pub mod reconnect;
+pub mod lobby;
+pub mod errors;
+
+pub use server::{Server, ServerHandle, HostedGame};
+pub use client::{Client, Rejoined};
+pub use reconnect::ReconnectConfig;
+pub use lobby::Lobby;
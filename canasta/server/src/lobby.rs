@@ -0,0 +1,302 @@
+use std::{collections::BTreeMap, time::{Duration, Instant}};
+
+use game_lib::{game::{CanastaGame, PLAYERS}, agents::AgentSpec};
+use protocol::{TableRules, SeatInfo, TableInfo};
+
+use crate::errors::lobby_error::LobbyError;
+
+/// Most canastas a table may need to go out
+const MAX_CANASTAS: u8 = 10;
+
+/// Who is sitting in a seat of a lobby table
+#[derive(Clone, Debug, PartialEq)]
+pub enum LobbySeat {
+    Empty,
+    Player(String),
+    Bot(AgentSpec),
+}
+
+/// A table that has filled and been started, see `Lobby::start`
+pub struct StartedTable {
+    pub game: CanastaGame,
+    /// Who sits in each seat, no seat is empty
+    pub seats: Vec<LobbySeat>,
+}
+
+/// Tables waiting for players to fill their seats
+/// # Overview
+/// A table is created with the rules it will be played by. Players sit down and stand
+/// up, and empty seats can be filled with bots. Once every seat is filled the table is
+/// started, which builds the game from the rules and removes the table from the lobby.
+/// A table is removed when its last player stands up, and a table no player has sat
+/// at is removed by `expire` once it has waited long enough.
+/// # Example
+/// ```
+/// use game_lib::agents::AgentSpec;
+/// use protocol::TableRules;
+/// use server::lobby::{Lobby, LobbySeat};
+/// let mut lobby = Lobby::new();
+/// let table = lobby.create(TableRules { players: 3, ..Default::default() }).unwrap();
+/// assert_eq!(lobby.sit(table, "Ada", None).unwrap(), 0);
+/// assert_eq!(lobby.add_bot(table, AgentSpec::Random, None).unwrap(), vec![1, 2]);
+/// assert!(lobby.is_full(table));
+///
+/// let started = lobby.start(table, Some(7)).unwrap();
+/// assert_eq!(started.game.num_players(), 3);
+/// assert_eq!(started.seats[0], LobbySeat::Player("Ada".to_string()));
+/// assert!(lobby.tables().is_empty());
+/// ```
+pub struct Lobby {
+    tables: BTreeMap<u32, LobbyTable>,
+    next_table: u32,
+}
+
+struct LobbyTable {
+    rules: TableRules,
+    seats: Vec<LobbySeat>,
+    /// When the table was opened, until a player sits at it
+    unseated_since: Option<Instant>,
+}
+
+impl Default for Lobby {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lobby {
+    pub fn new() -> Self {
+        Self { tables: BTreeMap::new(), next_table: 1 }
+    }
+
+    /// Opens a table with the rules
+    /// # Returns
+    /// - `Ok(u32)` - ID of the table
+    /// - `Err(LobbyError::InvalidRules)` - The number of players or canastas is not allowed
+    pub fn create(&mut self, rules: TableRules) -> Result<u32, LobbyError> {
+        if !PLAYERS.contains(&rules.players) {
+            let (min, max) = (PLAYERS.start(), PLAYERS.end());
+            return Err(LobbyError::InvalidRules(format!("Canasta is played by {min} to {max} players, not {}", rules.players)))
+        }
+        if !(1..=MAX_CANASTAS).contains(&rules.canastas) {
+            return Err(LobbyError::InvalidRules(format!("Going out needs 1 to {MAX_CANASTAS} canastas, not {}", rules.canastas)))
+        }
+        let id = self.next_table;
+        self.next_table = id + 1;
+        self.tables.insert(id, LobbyTable {
+            rules,
+            seats: vec![LobbySeat::Empty; rules.players as usize],
+            unseated_since: Some(Instant::now()),
+        });
+        Ok(id)
+    }
+
+    /// Seats a player at a table
+    /// # Parameters
+    /// - `seat` - Seat to take, or `None` for the first empty seat
+    /// # Returns
+    /// - `Ok(u8)` - The seat taken
+    /// - `Err(LobbyError)` - The table does not exist, or the seat or table is taken
+    pub fn sit(&mut self, table: u32, name: &str, seat: Option<u8>) -> Result<u8, LobbyError> {
+        let seat = self.empty_seat(table, seat)?;
+        let entry = self.table_mut(table)?;
+        entry.seats[seat as usize] = LobbySeat::Player(name.to_string());
+        entry.unseated_since = None;
+        Ok(seat)
+    }
+
+    /// Removes the player from a seat, removing the table if no players are left
+    pub fn stand(&mut self, table: u32, seat: u8) -> Result<(), LobbyError> {
+        let entry = self.table_mut(table)?;
+        match entry.seats.get_mut(seat as usize) {
+            Some(taken @ LobbySeat::Player(_)) => *taken = LobbySeat::Empty,
+            _ => return Err(LobbyError::NotSeated(seat)),
+        }
+        if !entry.seats.iter().any(|s| matches!(s, LobbySeat::Player(_))) {
+            self.tables.remove(&table);
+        }
+        Ok(())
+    }
+
+    /// Puts a bot in a seat
+    /// # Parameters
+    /// - `seat` - Seat to fill, or `None` to fill every empty seat
+    /// # Returns
+    /// - `Ok(Vec<u8>)` - The seats filled
+    /// - `Err(LobbyError)` - The table does not exist, or the seat or table is taken
+    pub fn add_bot(&mut self, table: u32, bot: AgentSpec, seat: Option<u8>) -> Result<Vec<u8>, LobbyError> {
+        let seats = match seat {
+            Some(seat) => vec![self.empty_seat(table, Some(seat))?],
+            None => {
+                let seats = self.empty_seats(table)?;
+                if seats.is_empty() { return Err(LobbyError::TableFull) }
+                seats
+            }
+        };
+        let entry = self.table_mut(table)?;
+        for seat in &seats {
+            entry.seats[*seat as usize] = LobbySeat::Bot(bot.clone());
+        }
+        Ok(seats)
+    }
+
+    /// Removes the tables no player has sat at since they were opened longer ago than
+    /// `after`
+    /// # Returns
+    /// The IDs of the tables removed
+    pub fn expire(&mut self, after: Duration) -> Vec<u32> {
+        let expired: Vec<u32> = self.tables.iter()
+            .filter(|(_, entry)| entry.unseated_since.is_some_and(|t| t.elapsed() >= after))
+            .map(|(table, _)| *table)
+            .collect();
+        for table in &expired {
+            self.tables.remove(table);
+        }
+        expired
+    }
+
+    /// If every seat of the table is filled
+    pub fn is_full(&self, table: u32) -> bool {
+        self.empty_seats(table).is_ok_and(|s| s.is_empty())
+    }
+
+    /// Builds the game of a full table and removes it from the lobby
+    /// # Parameters
+    /// - `seed` - Seed for the game, or `None` for a random one
+    /// # Returns
+    /// - `Ok(StartedTable)` - The game and who sits in each seat
+    /// - `Err(LobbyError::NotFull)` - The table has empty seats
+    pub fn start(&mut self, table: u32, seed: Option<u64>) -> Result<StartedTable, LobbyError> {
+        if !self.is_full(table) {
+            self.table_mut(table)?;
+            return Err(LobbyError::NotFull)
+        }
+        let entry = self.tables.remove(&table).ok_or(LobbyError::UnknownTable(table))?;
+        let mut builder = entry.rules.builder();
+        if let Some(seed) = seed { builder = builder.seed(seed) }
+        let game = builder.build().ok_or_else(|| LobbyError::InvalidRules("Could not build the game".to_string()))?;
+        Ok(StartedTable { game, seats: entry.seats })
+    }
+
+    /// A table as clients see it
+    pub fn info(&self, table: u32) -> Option<TableInfo> {
+        let entry = self.tables.get(&table)?;
+        let seats = entry.seats.iter()
+            .map(|seat| match seat {
+                LobbySeat::Empty => SeatInfo::Empty,
+                LobbySeat::Player(name) => SeatInfo::Player { name: name.clone() },
+                LobbySeat::Bot(spec) => SeatInfo::Bot { agent: spec.to_string() },
+            })
+            .collect();
+        Some(TableInfo { table, rules: entry.rules, seats })
+    }
+
+    /// Every table waiting for players
+    pub fn tables(&self) -> Vec<TableInfo> {
+        self.tables.keys().filter_map(|t| self.info(*t)).collect()
+    }
+
+    fn table_mut(&mut self, table: u32) -> Result<&mut LobbyTable, LobbyError> {
+        self.tables.get_mut(&table).ok_or(LobbyError::UnknownTable(table))
+    }
+
+    fn empty_seats(&self, table: u32) -> Result<Vec<u8>, LobbyError> {
+        let entry = self.tables.get(&table).ok_or(LobbyError::UnknownTable(table))?;
+        Ok((0..entry.seats.len() as u8).filter(|s| entry.seats[*s as usize] == LobbySeat::Empty).collect())
+    }
+
+    /// The seat asked for if it is empty, or the first empty seat
+    fn empty_seat(&self, table: u32, seat: Option<u8>) -> Result<u8, LobbyError> {
+        let empty = self.empty_seats(table)?;
+        match seat {
+            Some(seat) if empty.contains(&seat) => Ok(seat),
+            Some(seat) if seat as usize >= self.tables[&table].seats.len() => Err(LobbyError::InvalidSeat(seat)),
+            Some(seat) => Err(LobbyError::SeatTaken(seat)),
+            None => empty.first().copied().ok_or(LobbyError::TableFull),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lobby_with_table(players: u8) -> (Lobby, u32) {
+        let mut lobby = Lobby::new();
+        let table = lobby.create(TableRules { players, ..Default::default() }).unwrap();
+        (lobby, table)
+    }
+
+    #[test]
+    fn rules_follow_player_counts() {
+        let mut lobby = Lobby::new();
+        for players in PLAYERS {
+            assert!(lobby.create(TableRules { players, ..Default::default() }).is_ok());
+        }
+        for players in [PLAYERS.start() - 1, PLAYERS.end() + 1] {
+            let rules = TableRules { players, ..Default::default() };
+            assert!(matches!(lobby.create(rules), Err(LobbyError::InvalidRules(_))));
+        }
+        let rules = TableRules { canastas: 0, ..Default::default() };
+        assert!(matches!(lobby.create(rules), Err(LobbyError::InvalidRules(_))));
+    }
+
+    #[test]
+    fn taken_seats_are_refused() {
+        let (mut lobby, table) = lobby_with_table(2);
+        assert_eq!(lobby.sit(table, "Ada", Some(1)), Ok(1));
+        assert_eq!(lobby.sit(table, "Bo", Some(1)), Err(LobbyError::SeatTaken(1)));
+        assert_eq!(lobby.add_bot(table, AgentSpec::Random, Some(1)), Err(LobbyError::SeatTaken(1)));
+        assert_eq!(lobby.sit(table, "Bo", Some(2)), Err(LobbyError::InvalidSeat(2)));
+        assert_eq!(lobby.sit(table, "Bo", None), Ok(0));
+        assert_eq!(lobby.sit(table, "Cy", None), Err(LobbyError::TableFull));
+        assert_eq!(lobby.add_bot(table, AgentSpec::Random, None), Err(LobbyError::TableFull));
+        assert_eq!(lobby.sit(table + 1, "Cy", None), Err(LobbyError::UnknownTable(table + 1)));
+    }
+
+    #[test]
+    fn only_full_tables_start() {
+        let (mut lobby, table) = lobby_with_table(3);
+        lobby.sit(table, "Ada", None).unwrap();
+        assert!(matches!(lobby.start(table, Some(1)), Err(LobbyError::NotFull)));
+        assert_eq!(lobby.tables().len(), 1);
+        lobby.add_bot(table, AgentSpec::Random, Some(2)).unwrap();
+        assert!(matches!(lobby.start(table, Some(1)), Err(LobbyError::NotFull)));
+        lobby.add_bot(table, AgentSpec::Random, None).unwrap();
+        let started = lobby.start(table, Some(1)).unwrap();
+        assert_eq!(started.seats, vec![
+            LobbySeat::Player("Ada".to_string()),
+            LobbySeat::Bot(AgentSpec::Random),
+            LobbySeat::Bot(AgentSpec::Random),
+        ]);
+        assert!(matches!(lobby.start(table, Some(1)), Err(LobbyError::UnknownTable(_))));
+    }
+
+    #[test]
+    fn table_is_removed_when_last_player_stands() {
+        let (mut lobby, table) = lobby_with_table(4);
+        lobby.sit(table, "Ada", Some(0)).unwrap();
+        lobby.sit(table, "Bo", Some(1)).unwrap();
+        lobby.add_bot(table, AgentSpec::Random, Some(2)).unwrap();
+        assert_eq!(lobby.stand(table, 3), Err(LobbyError::NotSeated(3)));
+        assert_eq!(lobby.stand(table, 2), Err(LobbyError::NotSeated(2)));
+        lobby.stand(table, 0).unwrap();
+        assert_eq!(lobby.info(table).unwrap().seats[0], SeatInfo::Empty);
+        // bots alone do not keep the table open
+        lobby.stand(table, 1).unwrap();
+        assert!(lobby.info(table).is_none());
+        assert_eq!(lobby.stand(table, 1), Err(LobbyError::UnknownTable(table)));
+    }
+
+    #[test]
+    fn unseated_tables_expire() {
+        let mut lobby = Lobby::new();
+        let empty = lobby.create(TableRules::default()).unwrap();
+        let seated = lobby.create(TableRules::default()).unwrap();
+        lobby.sit(seated, "Ada", None).unwrap();
+        assert!(lobby.expire(Duration::from_secs(60)).is_empty());
+        assert_eq!(lobby.expire(Duration::ZERO), vec![empty]);
+        assert_eq!(lobby.tables().len(), 1);
+        assert!(lobby.info(seated).is_some());
+    }
+}
@@ -0,0 +1,61 @@
+use std::time::Duration;
+
+use game_lib::agents::AgentSpec;
+
+/// What a game does when a player loses their connection
+/// # Overview
+/// While the player is away the game is paused, no seat may act, for up to the grace
+/// period. If the player has not come back by then a bot plays their seat until they
+/// do, or with no bot the game stays paused. A grace period of zero hands the seat to
+/// the bot straight away.
+///
+/// A player coming back is sent the table and the events they missed, and takes their
+/// seat back from the bot.
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use game_lib::agents::AgentSpec;
+/// use server::ReconnectConfig;
+/// let config = ReconnectConfig::default().grace(Duration::from_secs(30)).bot(AgentSpec::Random);
+/// assert_eq!(config.grace_period(), Duration::from_secs(30));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReconnectConfig {
+    grace: Duration,
+    bot: Option<AgentSpec>,
+}
+
+impl Default for ReconnectConfig {
+    /// Waits a minute then hands the seat to the heuristic bot
+    fn default() -> Self {
+        Self { grace: Duration::from_secs(60), bot: Some(AgentSpec::Heuristic(Default::default())) }
+    }
+}
+
+impl ReconnectConfig {
+    /// How long to pause the game waiting for the player
+    pub fn grace(mut self, grace: Duration) -> Self {
+        self.grace = grace;
+        self
+    }
+
+    /// Bot to play the seat once the grace period is over
+    pub fn bot(mut self, bot: AgentSpec) -> Self {
+        self.bot = Some(bot);
+        self
+    }
+
+    /// Keep the game paused until the player comes back, however long it takes
+    pub fn pause(mut self) -> Self {
+        self.bot = None;
+        self
+    }
+
+    pub fn grace_period(&self) -> Duration {
+        self.grace
+    }
+
+    pub fn takeover_bot(&self) -> Option<&AgentSpec> {
+        self.bot.as_ref()
+    }
+}
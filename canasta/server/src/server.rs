@@ -0,0 +1,1062 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    io::{self, BufReader},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex, MutexGuard, TryLockError, atomic::{AtomicBool, AtomicU64, Ordering}, mpsc::{self, Receiver, SyncSender, TrySendError}},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use game_lib::{
+    game::CanastaGame,
+    events::{GameEvent, Viewer},
+    agents::{AgentSpec, Driver},
+    clock::{TimeControl, GameClock},
+    errors::agent_error::AgentError,
+};
+
+use protocol::{
+    PROTOCOL_VERSION, Encoding, Hello, ClientMessage, Request, ServerMessage, Reply, SeatStatus, ErrorCode, ErrorMessage,
+    codec, errors::protocol_error::ProtocolError,
+};
+
+use crate::{
+    reconnect::ReconnectConfig,
+    lobby::{Lobby, LobbySeat},
+    errors::lobby_error::LobbyError,
+};
+
+/// How often seats that have been away too long and clocks that have run out are
+/// checked for
+const TICK: Duration = Duration::from_millis(50);
+
+/// Messages waiting to be written to a connection before it is dropped as too slow
+const OUTBOX: usize = 1024;
+
+/// Most unacknowledged events kept for a seat, the oldest are dropped first
+const HISTORY: usize = 1024;
+
+/// How long a lobby table waits for its first player before it is removed
+const UNSEATED: Duration = Duration::from_secs(60);
+
+/// A game the server is hosting, with the tokens that let clients take each seat
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HostedGame {
+    /// ID clients use to join the game
+    pub game: u32,
+    /// Secret token for each seat, in seat order
+    pub tokens: Vec<String>,
+}
+
+/// Hosts games for clients connecting over TCP
+/// # Overview
+/// Games are added with `host`, which gives a token for each seat. A client connects,
+/// completes the handshake of the `protocol` crate, sends `Request::Join` with the token
+/// of its seat and can then send actions for that seat. After every action each
+/// connected seat is sent the events of the game and its new view, redacted to what the
+/// seat may know. More than one connection may hold the same seat, such as a player on
+/// two devices.
+///
+/// Players can also find games through the lobby, see `Lobby`. Clients create tables,
+/// sit down and add bots, and once a table is full its game is hosted and each player
+/// at the table is sent the token for their seat.
+///
+/// Events sent to a seat are numbered and kept until the seat acknowledges them, so a
+/// player who loses their connection can rejoin and be sent what they missed. Only the
+/// last `HISTORY` events are kept, a player who missed more is still sent the table.
+/// What happens to the game meanwhile is set by `ReconnectConfig`.
+///
+/// Games can be played against the clock, see `Server::clock`. A player who runs out of
+/// time has their turn played for them by `FallbackAgent`, which draws and discards the
+/// safest card. Clocks stop while a game is paused.
+///
+/// Each connection is served on its own thread. Every game has a lock of its own, and
+/// bots play their turns holding only the lock of their game, so a slow bot never holds
+/// up other games or the lobby. Messages to a client are queued and written by another
+/// thread, so a slow client never holds a lock. A client that falls `OUTBOX` messages
+/// behind is disconnected.
+/// # Example
+/// ```
+/// use game_lib::{game::CanastaGame, action_log::{Action, ActionOutcome}};
+/// use server::{Server, Client};
+/// let server = Server::bind("127.0.0.1:0").unwrap();
+/// let game = CanastaGame::builder().players(2).canastas(1).hand().seed(3).build().unwrap();
+/// let player = game.get_current_player();
+/// let hosted = server.host(game);
+/// let handle = server.spawn().unwrap();
+///
+/// let mut client = Client::connect(handle.local_addr()).unwrap();
+/// assert_eq!(client.join(hosted.game, &hosted.tokens[player as usize]).unwrap(), player);
+/// let outcome = client.act(Action::Draw).unwrap();
+/// assert!(matches!(outcome, ActionOutcome::Drew { .. }));
+/// handle.stop();
+/// ```
+pub struct Server {
+    listener: TcpListener,
+    shared: Arc<Shared>,
+}
+
+/// A server running on its own thread, see `Server::spawn`
+pub struct ServerHandle {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+    thread: JoinHandle<()>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    stopped: AtomicBool,
+    next_connection: AtomicU64,
+    /// Every open connection by ID, including those still in the handshake, so they
+    /// can all be closed when the server stops
+    streams: Mutex<BTreeMap<u64, TcpStream>>,
+}
+
+struct State {
+    games: BTreeMap<u32, Arc<Mutex<Table>>>,
+    next_game: u32,
+    rng: ChaCha8Rng,
+    reconnect: ReconnectConfig,
+    /// Time control of newly hosted games
+    time: TimeControl,
+    lobby: Lobby,
+    /// Connections of the players sitting at lobby tables, by table and seat
+    lobby_seats: BTreeMap<(u32, u8), Connection>,
+}
+
+/// A hosted game and its seats
+struct Table {
+    game: CanastaGame,
+    seats: Vec<Seat>,
+    /// Bots playing seats, every other seat is played by its client
+    driver: Driver,
+    /// `None` if the game has no time control
+    clock: Option<GameClock>,
+    /// Seeds the bots that take over seats
+    rng: ChaCha8Rng,
+}
+
+struct Seat {
+    token: String,
+    events: Receiver<GameEvent>,
+    /// Events sent to the seat that it has not acknowledged, with their sequence number
+    history: VecDeque<(u64, GameEvent)>,
+    last_seq: u64,
+    connections: Vec<Connection>,
+    /// When the last connection of the seat was lost
+    away_since: Option<Instant>,
+    /// If a bot is playing the seat while its player is away
+    bot: bool,
+}
+
+/// A connection could not be sent to, it has closed or was closed for falling behind
+struct Closed;
+
+/// What a connection has joined
+#[derive(Default)]
+struct Session {
+    /// Game and seat being played
+    seat: Option<(u32, u8)>,
+    /// Lobby table and seat sat at
+    sitting: Option<(u32, u8)>,
+}
+
+/// A client holding a seat, messages sent are queued for its writer thread
+#[derive(Clone)]
+struct Connection {
+    id: u64,
+    outbox: SyncSender<ServerMessage>,
+    /// Used to close the connection, only the writer thread writes to it
+    stream: Arc<TcpStream>,
+}
+
+impl Server {
+    /// Listens for clients on the address, use port 0 to pick any free port
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let state = State {
+            games: BTreeMap::new(),
+            next_game: 1,
+            rng: ChaCha8Rng::from_entropy(),
+            reconnect: ReconnectConfig::default(),
+            time: TimeControl::new(),
+            lobby: Lobby::new(),
+            lobby_seats: BTreeMap::new(),
+        };
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            shared: Arc::new(Shared {
+                state: Mutex::new(state),
+                stopped: AtomicBool::new(false),
+                next_connection: AtomicU64::new(0),
+                streams: Mutex::new(BTreeMap::new()),
+            }),
+        })
+    }
+
+    /// Set what games do when a player loses their connection
+    pub fn reconnect(self, config: ReconnectConfig) -> Self {
+        self.shared.lock().reconnect = config;
+        self
+    }
+
+    /// Set how long players have for their turns and games hosted from now on
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use game_lib::{game::CanastaGame, clock::TimeControl, events::GameEvent};
+    /// use protocol::ServerMessage;
+    /// use server::{Server, Client};
+    /// let server = Server::bind("127.0.0.1:0").unwrap().clock(TimeControl::new().turn(Duration::from_millis(100)));
+    /// let game = CanastaGame::builder().players(2).canastas(1).hand().seed(3).build().unwrap();
+    /// let player = game.get_current_player();
+    /// let hosted = server.host(game);
+    /// let handle = server.spawn().unwrap();
+    ///
+    /// let mut client = Client::connect(handle.local_addr()).unwrap();
+    /// client.join(hosted.game, &hosted.tokens[player as usize]).unwrap();
+    /// // waiting too long has the turn played for the player
+    /// loop {
+    ///     if let ServerMessage::Event { event: GameEvent::TurnTimedOut { player: p }, .. } = client.recv().unwrap() {
+    ///         assert_eq!(p, player);
+    ///         break
+    ///     }
+    /// }
+    /// handle.stop();
+    /// ```
+    pub fn clock(self, control: TimeControl) -> Self {
+        self.shared.lock().time = control;
+        self
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Adds a game to the server
+    /// # Returns
+    /// The ID of the game and a new random token for each seat, give each token only
+    /// to the player of that seat
+    pub fn host(&self, game: CanastaGame) -> HostedGame {
+        let humans = vec![None; game.num_players() as usize];
+        self.shared.host(game, humans)
+    }
+
+    /// Adds a game with bots playing some seats
+    /// # Parameters
+    /// - `bots` - Bot for each seat, `None` for seats played by clients
+    /// # Returns
+    /// The ID of the game and a token for each seat, the tokens of bot seats are unused
+    pub fn host_with_bots(&self, game: CanastaGame, bots: Vec<Option<AgentSpec>>) -> HostedGame {
+        self.shared.host(game, bots)
+    }
+
+    /// IDs of every hosted game
+    pub fn games(&self) -> Vec<u32> {
+        self.shared.lock().games.keys().copied().collect()
+    }
+
+    /// Runs a function with a hosted game, such as to read its scores
+    ///
+    /// Waits for any bot turns being played in the game.
+    /// # Returns
+    /// `None` if there is no game with the ID
+    pub fn with_game<T>(&self, game: u32, f: impl FnOnce(&CanastaGame) -> T) -> Option<T> {
+        let table = self.shared.table(game)?;
+        let table = relock(&table);
+        Some(f(&table.game))
+    }
+
+    /// Removes a game, closing the connections of its seats
+    ///
+    /// Waits for requests already being carried out in the game to finish.
+    /// # Returns
+    /// The game, or `None` if there is no game with the ID
+    pub fn remove(&self, game: u32) -> Option<CanastaGame> {
+        let mut table = self.shared.lock().games.remove(&game)?;
+        for connection in relock(&table).seats.iter().flat_map(|s| &s.connections) {
+            connection.close();
+        }
+        loop {
+            match Arc::try_unwrap(table) {
+                Ok(table) => return Some(table.into_inner().unwrap_or_else(|e| e.into_inner()).game),
+                Err(shared) => table = shared,
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Accepts clients until the server is stopped, serving each on its own thread
+    pub fn run(&self) {
+        let shared = self.shared.clone();
+        let ticker = thread::spawn(move || {
+            while !shared.stopped.load(Ordering::SeqCst) {
+                shared.tick();
+                thread::sleep(TICK);
+            }
+        });
+        for stream in self.listener.incoming() {
+            if self.shared.stopped.load(Ordering::SeqCst) { break }
+            let Ok(stream) = stream else { continue };
+            let Ok(closer) = stream.try_clone() else { continue };
+            let id = self.shared.next_connection.fetch_add(1, Ordering::SeqCst);
+            self.shared.streams().insert(id, closer);
+            let shared = self.shared.clone();
+            thread::spawn(move || {
+                serve(&shared, id, stream);
+                shared.streams().remove(&id);
+            });
+        }
+        let _ = ticker.join();
+    }
+
+    /// Runs the server on a new thread, games can still be hosted while it runs
+    pub fn spawn(&self) -> io::Result<ServerHandle> {
+        let server = Server { listener: self.listener.try_clone()?, shared: self.shared.clone() };
+        Ok(ServerHandle {
+            addr: self.local_addr()?,
+            shared: self.shared.clone(),
+            thread: thread::spawn(move || server.run()),
+        })
+    }
+}
+
+impl ServerHandle {
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Stops accepting clients and closes every connection, whether in a game, in the
+    /// lobby or still in the handshake
+    pub fn stop(self) {
+        self.shared.stopped.store(true, Ordering::SeqCst);
+        // wake the thread blocked accepting clients so it sees the server is stopped
+        let _ = TcpStream::connect(self.addr);
+        let _ = self.thread.join();
+        for stream in self.shared.streams().values() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+/// Locks a mutex, carrying on if another thread panicked while holding it
+///
+/// A panic while serving one connection should not take down every game
+fn relock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        relock(&self.state)
+    }
+
+    fn streams(&self) -> MutexGuard<'_, BTreeMap<u64, TcpStream>> {
+        relock(&self.streams)
+    }
+
+    /// The table of a hosted game, to be locked once the state is no longer locked
+    fn table(&self, game: u32) -> Option<Arc<Mutex<Table>>> {
+        self.lock().games.get(&game).cloned()
+    }
+
+    /// Adds a game, see `Server::host_with_bots`
+    ///
+    /// The first bot turns are played before the game is added, without the state locked.
+    fn host(&self, mut game: CanastaGame, bots: Vec<Option<AgentSpec>>) -> HostedGame {
+        let (id, mut rng, time) = {
+            let mut state = self.lock();
+            let id = state.next_game;
+            state.next_game += 1;
+            (id, ChaCha8Rng::seed_from_u64(state.rng.gen()), state.time)
+        };
+        let seats: Vec<Seat> = (0..game.num_players())
+            .map(|player| Seat {
+                token: token(&mut rng),
+                events: game.subscribe(Viewer::Player(player)),
+                history: VecDeque::new(),
+                last_seq: 0,
+                connections: vec![],
+                away_since: None,
+                bot: false,
+            })
+            .collect();
+        let tokens = seats.iter().map(|s| s.token.clone()).collect();
+        let agents = (0..seats.len())
+            .map(|seat| bots.get(seat).cloned().flatten().map(|spec| spec.build(rng.gen())))
+            .collect();
+        let clock = (!time.is_unlimited())
+            .then(|| GameClock::new(time, game.num_players(), game.get_current_player(), Instant::now()));
+        let mut table = Table { game, seats, driver: Driver::new(agents), clock, rng };
+        table.play_bots();
+        table.broadcast();
+        self.lock().games.insert(id, Arc::new(Mutex::new(table)));
+        HostedGame { game: id, tokens }
+    }
+
+    /// Starts the game of a lobby table if every seat is filled
+    fn start_if_full(&self, table: u32) {
+        let (started, connections) = {
+            let mut state = self.lock();
+            if !state.lobby.is_full(table) { return }
+            let Ok(started) = state.lobby.start(table, None) else { return };
+            let connections: Vec<Option<Connection>> = (0..started.seats.len() as u8)
+                .map(|seat| state.lobby_seats.remove(&(table, seat)))
+                .collect();
+            (started, connections)
+        };
+        let bots = started.seats.iter()
+            .map(|seat| match seat {
+                LobbySeat::Bot(spec) => Some(spec.clone()),
+                _ => None,
+            })
+            .collect();
+        let hosted = self.host(started.game, bots);
+        for (seat, (token, connection)) in hosted.tokens.into_iter().zip(connections).enumerate() {
+            let Some(connection) = connection else { continue };
+            let _ = connection.send(&ServerMessage::TableStarted { table, game: hosted.game, seat: seat as u8, token });
+        }
+    }
+
+    /// Hands seats that have been away for the grace period to a bot, and plays the
+    /// turns of players out of time. Lobby tables no one has sat at for too long are
+    /// removed.
+    ///
+    /// A game busy playing bot turns is left for a later tick.
+    fn tick(&self) {
+        let (tables, bot, grace) = {
+            let mut state = self.lock();
+            state.lobby.expire(UNSEATED);
+            let tables: Vec<Arc<Mutex<Table>>> = state.games.values().cloned().collect();
+            (tables, state.reconnect.takeover_bot().cloned(), state.reconnect.grace_period())
+        };
+        let now = Instant::now();
+        for table in &tables {
+            let mut table = match table.try_lock() {
+                Ok(table) => table,
+                Err(TryLockError::Poisoned(err)) => err.into_inner(),
+                Err(TryLockError::WouldBlock) => continue,
+            };
+            table.check_clock(now);
+            if let Some(bot) = &bot { table.take_over(bot, grace) }
+        }
+    }
+}
+
+impl State {
+    /// Tells everyone sitting at a lobby table who is sitting where
+    fn table_changed(&mut self, table: u32) {
+        let Some(info) = self.lobby.info(table) else { return };
+        let message = ServerMessage::TableChanged { table: info };
+        self.lobby_seats.retain(|(t, _), connection| *t != table || connection.send(&message).is_ok());
+    }
+
+    /// The lobby seat the connection sits in, if its table has not started
+    fn sitting(&self, session: &Session, connection: &Connection) -> Option<(u32, u8)> {
+        session.sitting.filter(|key| self.lobby_seats.get(key).is_some_and(|c| c.id == connection.id))
+    }
+
+    /// Frees the lobby seat of the connection, if it sits at a table
+    fn stand(&mut self, session: &mut Session, connection: &Connection) -> Result<(), LobbyError> {
+        let Some((table, seat)) = self.sitting(session, connection) else { return Ok(()) };
+        session.sitting = None;
+        self.lobby_seats.remove(&(table, seat));
+        self.lobby.stand(table, seat)?;
+        self.table_changed(table);
+        Ok(())
+    }
+
+}
+
+impl Table {
+    /// The first seat whose player is away with no bot playing for them
+    fn paused_by(&self) -> Option<u8> {
+        self.seats.iter().position(|s| s.away_since.is_some() && !s.bot).map(|p| p as u8)
+    }
+
+    /// Plays the turns of bot seats unless the game is paused
+    fn play_bots(&mut self) {
+        if self.paused_by().is_some() { return }
+        // an illegal bot action leaves the turn with the seat, to be retried on the next action
+        let _ = self.driver.run(&mut self.game);
+    }
+
+    /// Hands the seats that have been away for the grace period to the bot
+    fn take_over(&mut self, bot: &AgentSpec, grace: Duration) {
+        let expired: Vec<u8> = self.seats.iter().enumerate()
+            .filter(|(_, s)| !s.bot && s.away_since.is_some_and(|t| t.elapsed() >= grace))
+            .map(|(player, _)| player as u8)
+            .collect();
+        for player in &expired {
+            self.seats[*player as usize].bot = true;
+            self.driver.set_seat(*player, Some(bot.build(self.rng.gen())));
+            self.announce(*player, SeatStatus::Bot);
+        }
+        if !expired.is_empty() {
+            self.play_bots();
+            self.broadcast();
+        }
+    }
+
+    /// Stops the clock while the game is paused, and plays the turn of a player who has
+    /// run out of time
+    fn check_clock(&mut self, now: Instant) {
+        let paused = self.paused_by().is_some();
+        let Some(clock) = &mut self.clock else { return };
+        if paused { return clock.pause(now) }
+        clock.resume(now);
+        let player = self.game.get_current_player();
+        if !clock.expired(now) || !self.driver.is_human(player) { return }
+        if self.game.time_out(player).is_ok() {
+            self.play_bots();
+            self.broadcast();
+        }
+    }
+
+    /// Starts the clock for a new turn, telling every seat how long is left
+    ///
+    /// `turn_passed` is set when a turn or hand ended since the last call. Bots may have
+    /// played whole turns in between, so the turn can have come back to the same player.
+    fn next_turn(&mut self, turn_passed: bool) {
+        let player = self.game.get_current_player();
+        let Some(clock) = &mut self.clock else { return };
+        // an undone discard hands the turn back without ending another
+        if !turn_passed && clock.player() == player { return }
+        clock.start_turn(player, Instant::now());
+        let Some(message) = self.clock_message() else { return };
+        for seat in &mut self.seats {
+            seat.connections.retain(|connection| connection.send(&message).is_ok());
+        }
+    }
+
+    /// Time left on the clock, or `None` without a time control
+    fn clock_message(&self) -> Option<ServerMessage> {
+        let now = Instant::now();
+        let millis = |d: Duration| d.as_millis() as u64;
+        let clock = self.clock.as_ref()?;
+        let game_ms = clock.control().game_limit().map(|_| {
+            (0..self.game.num_players()).filter_map(|p| clock.game_remaining(p, now)).map(millis).collect()
+        });
+        Some(ServerMessage::Clock { player: clock.player(), turn_ms: clock.turn_remaining(now).map(millis), game_ms })
+    }
+
+    /// Sends the new events and view of the game to every connection of every seat
+    fn broadcast(&mut self) {
+        let mut turn_passed = false;
+        for (player, seat) in self.seats.iter_mut().enumerate() {
+            let mut messages = vec![];
+            for event in seat.events.try_iter() {
+                turn_passed |= matches!(event, GameEvent::TurnEnded { .. } | GameEvent::HandEnded { .. });
+                seat.last_seq += 1;
+                seat.history.push_back((seat.last_seq, event.clone()));
+                if seat.history.len() > HISTORY { seat.history.pop_front(); }
+                messages.push(ServerMessage::Event { seq: seat.last_seq, event });
+            }
+            if let Ok(view) = self.game.player_view(player as u8) {
+                messages.push(ServerMessage::View { view });
+            }
+            seat.connections.retain(|connection| messages.iter().all(|message| connection.send(message).is_ok()));
+        }
+        self.next_turn(turn_passed);
+    }
+
+    /// Tells every connected seat who is now playing a seat
+    fn announce(&mut self, player: u8, status: SeatStatus) {
+        let message = ServerMessage::Seat { seat: player, status };
+        for seat in &mut self.seats {
+            seat.connections.retain(|connection| connection.send(&message).is_ok());
+        }
+    }
+}
+
+impl Connection {
+    /// Starts a thread writing the messages sent to the connection to the stream
+    fn new(id: u64, stream: TcpStream, encoding: Encoding) -> io::Result<Self> {
+        let (outbox, messages) = mpsc::sync_channel(OUTBOX);
+        let writer = stream.try_clone()?;
+        thread::spawn(move || {
+            // ends once every sender is dropped, after writing what was queued
+            for message in messages {
+                if codec::write(&mut &writer, encoding, &message).is_err() { break }
+            }
+            let _ = writer.shutdown(Shutdown::Both);
+        });
+        Ok(Self { id, outbox, stream: Arc::new(stream) })
+    }
+
+    /// Queues a message, without waiting for it to be written
+    /// # Returns
+    /// An error if the connection is closed, or too far behind and now closed
+    fn send(&self, message: &ServerMessage) -> Result<(), Closed> {
+        match self.outbox.try_send(message.clone()) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => {
+                self.close();
+                Err(Closed)
+            }
+            Err(TrySendError::Disconnected(_)) => Err(Closed),
+        }
+    }
+
+    fn close(&self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+/// Serves one client until it leaves or disconnects
+fn serve(shared: &Shared, id: u64, stream: TcpStream) {
+    let Ok(writer) = stream.try_clone() else { return };
+    let mut reader = BufReader::new(stream);
+    let Some(encoding) = handshake(&mut reader, &writer) else { return };
+    let Ok(connection) = Connection::new(id, writer, encoding) else { return };
+    let mut session = Session::default();
+    loop {
+        let (id, request) = match codec::read::<ClientMessage>(&mut reader, encoding) {
+            Ok(Some(ClientMessage::Request { id, request })) => (id, request),
+            Ok(None) | Err(ProtocolError::Io(_)) => break,
+            Err(err) => {
+                let error = ErrorMessage::new(ErrorCode::InvalidMessage, err.to_string());
+                if connection.send(&ServerMessage::Error { id: None, error }).is_err() { break }
+                continue
+            }
+        };
+        let leave = request == Request::Leave;
+        if let Err(error) = handle(shared, &connection, &mut session, id, request) {
+            if connection.send(&ServerMessage::Error { id: Some(id), error }).is_err() { break }
+        }
+        if leave { break }
+    }
+    let _ = shared.lock().stand(&mut session, &connection);
+    let Some((game, player)) = session.seat else { return };
+    let Some(table) = shared.table(game) else { return };
+    let mut table = relock(&table);
+    let seat = &mut table.seats[player as usize];
+    seat.connections.retain(|c| c.id != connection.id);
+    if seat.connections.is_empty() && !seat.bot {
+        seat.away_since = Some(Instant::now());
+        table.announce(player, SeatStatus::Away);
+    }
+}
+
+/// Reads the `Hello` of a client and answers it
+/// # Returns
+/// The encoding the client chose, or `None` if the connection should be closed
+fn handshake(reader: &mut BufReader<TcpStream>, mut writer: &TcpStream) -> Option<Encoding> {
+    let reply = match codec::read::<Hello>(reader, Encoding::Json) {
+        Ok(Some(hello)) if hello.version == PROTOCOL_VERSION => {
+            let welcome = ServerMessage::Welcome { version: PROTOCOL_VERSION };
+            return codec::write(&mut writer, Encoding::Json, &welcome).ok().map(|_| hello.encoding)
+        }
+        Ok(Some(hello)) => ErrorMessage::new(
+            ErrorCode::UnsupportedVersion,
+            format!("Server speaks protocol version {PROTOCOL_VERSION}, not {}", hello.version),
+        ),
+        Ok(None) | Err(ProtocolError::Io(_)) => return None,
+        Err(err) => ErrorMessage::new(ErrorCode::InvalidMessage, err.to_string()),
+    };
+    let _ = codec::write(&mut writer, Encoding::Json, &ServerMessage::Error { id: None, error: reply });
+    None
+}
+
+/// Carries out a request from a client
+///
+/// Replies are queued while the game is locked so they keep their order with events
+/// from other seats. Game requests lock only their game, lobby requests only the
+/// state. Returns the error to send the client on failure.
+fn handle(shared: &Shared, connection: &Connection, session: &mut Session, id: u32, request: Request) -> Result<(), ErrorMessage> {
+    let seat = &mut session.seat;
+    let reply = |reply: Reply| {
+        // a failed write means the client is gone, which the next read will find
+        let _ = connection.send(&ServerMessage::Reply { id, reply });
+    };
+    let no_game = |game: u32| ErrorMessage::new(ErrorCode::UnknownGame, format!("No game {game}"));
+    let not_seated = || ErrorMessage::new(ErrorCode::NotSeated, "Join a game first");
+    let hosted = |game: u32| shared.table(game).ok_or_else(|| no_game(game));
+    match request {
+        Request::Join { game, token: given, last_seq } => {
+            if seat.is_some() { return Err(ErrorMessage::new(ErrorCode::AlreadySeated, "Already seated")) }
+            let table = hosted(game)?;
+            let table = &mut *relock(&table);
+            let player = table.seats.iter().position(|s| s.token == given)
+                .ok_or_else(|| ErrorMessage::new(ErrorCode::InvalidToken, "Invalid token"))? as u8;
+            let view = table.game.player_view(player).map_err(|e| ErrorMessage::new(ErrorCode::InvalidToken, e.to_string()))?;
+            let taken = &mut table.seats[player as usize];
+            let events = match last_seq {
+                Some(last) => taken.history.iter().filter(|(seq, _)| *seq > last).cloned().collect(),
+                None => vec![],
+            };
+            let returning = taken.connections.is_empty();
+            taken.connections.push(connection.clone());
+            taken.away_since = None;
+            if taken.bot {
+                taken.bot = false;
+                table.driver.set_seat(player, None);
+            }
+            *seat = Some((game, player));
+            reply(Reply::Joined { game, seat: player, view, events });
+            if let Some(message) = table.clock_message() { let _ = connection.send(&message); }
+            if returning { table.announce(player, SeatStatus::Connected) }
+            // the game may have been paused waiting for this player
+            table.play_bots();
+            table.broadcast();
+        }
+        Request::Act { action } => {
+            let (game, player) = seat.ok_or_else(not_seated)?;
+            let table = hosted(game)?;
+            let mut table = relock(&table);
+            if let Some(away) = table.paused_by() {
+                return Err(ErrorMessage::new(ErrorCode::Paused, format!("Waiting for player {away} to reconnect")))
+            }
+            let outcome = table.game.apply(player, action)
+                .map_err(|e| ErrorMessage::new(ErrorCode::IllegalAction, e.to_string()))?;
+            reply(Reply::Outcome { outcome });
+            table.play_bots();
+            table.broadcast();
+        }
+        Request::View => {
+            let (game, player) = seat.ok_or_else(not_seated)?;
+            let table = hosted(game)?;
+            let view = relock(&table).game.player_view(player).map_err(|e| ErrorMessage::new(ErrorCode::NotSeated, e.to_string()))?;
+            reply(Reply::View { view });
+        }
+        Request::Ack { seq } => {
+            let (game, player) = seat.ok_or_else(not_seated)?;
+            let table = hosted(game)?;
+            relock(&table).seats[player as usize].history.retain(|(s, _)| *s > seq);
+            reply(Reply::Acked);
+        }
+        Request::Tables => reply(Reply::Tables { tables: shared.lock().lobby.tables() }),
+        Request::CreateTable { rules } => {
+            let mut state = shared.lock();
+            let table = state.lobby.create(rules).map_err(lobby_error)?;
+            reply(Reply::Table { table: state.lobby.info(table).expect("Table was just created") });
+        }
+        Request::SitDown { table, name, seat } => {
+            let mut state = shared.lock();
+            if state.sitting(session, connection).is_some() {
+                return Err(ErrorMessage::new(ErrorCode::AlreadySeated, "Already sitting at a table"))
+            }
+            let seat = state.lobby.sit(table, &name, seat).map_err(lobby_error)?;
+            state.lobby_seats.insert((table, seat), connection.clone());
+            session.sitting = Some((table, seat));
+            reply(Reply::Seated { table, seat });
+            state.table_changed(table);
+            drop(state);
+            shared.start_if_full(table);
+        }
+        Request::StandUp => {
+            let mut state = shared.lock();
+            if state.sitting(session, connection).is_none() {
+                return Err(ErrorMessage::new(ErrorCode::NotSeated, "Not sitting at a table"))
+            }
+            state.stand(session, connection).map_err(lobby_error)?;
+            reply(Reply::Stood);
+        }
+        Request::AddBot { agent, seat } => {
+            let mut state = shared.lock();
+            let (table, _) = state.sitting(session, connection)
+                .ok_or_else(|| ErrorMessage::new(ErrorCode::NotSeated, "Sit at a table first"))?;
+            let spec: AgentSpec = agent.parse().map_err(|e: AgentError| ErrorMessage::new(ErrorCode::InvalidRules, e.to_string()))?;
+            state.lobby.add_bot(table, spec, seat).map_err(lobby_error)?;
+            let info = state.lobby.info(table).expect("Table has a player");
+            reply(Reply::Table { table: info });
+            state.table_changed(table);
+            drop(state);
+            shared.start_if_full(table);
+        }
+        Request::Leave => reply(Reply::Left),
+    }
+    Ok(())
+}
+
+fn lobby_error(err: LobbyError) -> ErrorMessage {
+    let code = match err {
+        LobbyError::UnknownTable(_) => ErrorCode::UnknownTable,
+        LobbyError::InvalidRules(_) => ErrorCode::InvalidRules,
+        LobbyError::SeatTaken(_) | LobbyError::InvalidSeat(_) | LobbyError::TableFull | LobbyError::NotFull => ErrorCode::SeatTaken,
+        LobbyError::NotSeated(_) => ErrorCode::NotSeated,
+    };
+    ErrorMessage::new(code, err.to_string())
+}
+
+/// Random token of 32 hex digits
+fn token(rng: &mut ChaCha8Rng) -> String {
+    format!("{:032x}", rng.gen::<u128>())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use game_lib::{game::{CanastaGame, TurnPhase}, action_log::Action, agents::AgentSpec, clock::TimeControl};
+
+    use protocol::{TableRules, SeatInfo};
+
+    use crate::{Client, errors::server_error::ServerError};
+
+    use super::*;
+
+    /// A two seat game with a random bot in seat 1, the human in seat 0 is to play first
+    fn human_vs_bot(control: TimeControl) -> (Server, HostedGame) {
+        let server = Server::bind("127.0.0.1:0").unwrap().clock(control);
+        let game = CanastaGame::builder().players(2).canastas(1).hand().seed(3).build().unwrap();
+        assert_eq!(game.get_current_player(), 0);
+        let hosted = server.host_with_bots(game, vec![None, Some(AgentSpec::Random)]);
+        (server, hosted)
+    }
+
+    /// A two seat game with both seats played by clients, seat 0 is to play first
+    fn two_humans(reconnect: ReconnectConfig) -> (Server, HostedGame) {
+        let server = Server::bind("127.0.0.1:0").unwrap().reconnect(reconnect);
+        let game = CanastaGame::builder().players(2).canastas(1).hand().seed(3).build().unwrap();
+        assert_eq!(game.get_current_player(), 0);
+        let hosted = server.host(game);
+        (server, hosted)
+    }
+
+    /// Reads messages until the seat is announced with the status
+    fn wait_for_seat(client: &mut Client, seat: u8, status: SeatStatus) {
+        loop {
+            if let ServerMessage::Seat { seat: s, status: t } = client.recv().unwrap() {
+                if s == seat && t == status { return }
+            }
+        }
+    }
+
+    /// Draws then discards the first card of the hand
+    fn play_turn(client: &mut Client) {
+        client.act(Action::Draw).unwrap();
+        let card = client.view().unwrap().hand[0].id();
+        client.act(Action::Discard(card)).unwrap();
+    }
+
+    #[test]
+    fn dropped_seat_pauses_game() {
+        let reconnect = ReconnectConfig::default().grace(Duration::from_secs(60)).bot(AgentSpec::Random);
+        let (server, hosted) = two_humans(reconnect);
+        let handle = server.spawn().unwrap();
+        let mut first = Client::connect(handle.local_addr()).unwrap();
+        first.join(hosted.game, &hosted.tokens[0]).unwrap();
+        let mut second = Client::connect(handle.local_addr()).unwrap();
+        second.join(hosted.game, &hosted.tokens[1]).unwrap();
+
+        drop(second);
+        wait_for_seat(&mut first, 1, SeatStatus::Away);
+        match first.act(Action::Draw) {
+            Err(ServerError::Rejected(error)) => assert_eq!(error.code, ErrorCode::Paused),
+            other => panic!("expected the game to be paused, got {other:?}"),
+        }
+        // the game carries on once the seat is taken back
+        let mut second = Client::connect(handle.local_addr()).unwrap();
+        second.join(hosted.game, &hosted.tokens[1]).unwrap();
+        wait_for_seat(&mut first, 1, SeatStatus::Connected);
+        first.act(Action::Draw).unwrap();
+        handle.stop();
+    }
+
+    #[test]
+    fn bot_takes_over_after_grace() {
+        let reconnect = ReconnectConfig::default().grace(Duration::from_millis(100)).bot(AgentSpec::Random);
+        let (server, hosted) = two_humans(reconnect);
+        let handle = server.spawn().unwrap();
+        let mut first = Client::connect(handle.local_addr()).unwrap();
+        first.join(hosted.game, &hosted.tokens[0]).unwrap();
+        let mut second = Client::connect(handle.local_addr()).unwrap();
+        second.join(hosted.game, &hosted.tokens[1]).unwrap();
+
+        drop(second);
+        wait_for_seat(&mut first, 1, SeatStatus::Away);
+        wait_for_seat(&mut first, 1, SeatStatus::Bot);
+        play_turn(&mut first);
+        // the bot has played the turn of the seat by the time it comes back around
+        assert!(first.view().unwrap().is_turn());
+        let played = server.with_game(hosted.game, |g| g.log().entries().iter().any(|e| e.player == 1)).unwrap();
+        assert!(played);
+        handle.stop();
+    }
+
+    #[test]
+    fn rejoin_gives_missed_events_and_takes_seat_from_bot() {
+        let reconnect = ReconnectConfig::default().grace(Duration::from_millis(100)).bot(AgentSpec::Random);
+        let (server, hosted) = two_humans(reconnect);
+        let handle = server.spawn().unwrap();
+        let mut first = Client::connect(handle.local_addr()).unwrap();
+        first.join(hosted.game, &hosted.tokens[0]).unwrap();
+        let mut second = Client::connect(handle.local_addr()).unwrap();
+        second.join(hosted.game, &hosted.tokens[1]).unwrap();
+
+        first.act(Action::Draw).unwrap();
+        while !matches!(second.recv().unwrap(), ServerMessage::Event { .. }) {}
+        let seen = second.last_seq().unwrap();
+        drop(second);
+        wait_for_seat(&mut first, 1, SeatStatus::Bot);
+        let card = first.view().unwrap().hand[0].id();
+        first.act(Action::Discard(card)).unwrap();
+
+        let missed: Vec<(u64, GameEvent)> = {
+            let table = server.shared.table(hosted.game).unwrap();
+            let table = relock(&table);
+            table.seats[1].history.iter().filter(|(seq, _)| *seq > seen).cloned().collect()
+        };
+        let mut second = Client::connect(handle.local_addr()).unwrap();
+        let rejoined = second.rejoin(hosted.game, &hosted.tokens[1], Some(seen)).unwrap();
+        assert_eq!(rejoined.seat, 1);
+        assert_eq!(rejoined.events, missed);
+        assert_eq!(rejoined.events[0].0, seen + 1);
+        assert!(rejoined.events.iter().any(|(_, e)| matches!(e, GameEvent::CardDiscarded { player: 1, .. })));
+        assert_eq!(second.last_seq(), rejoined.events.last().map(|(seq, _)| *seq));
+
+        wait_for_seat(&mut first, 1, SeatStatus::Connected);
+        {
+            let table = server.shared.table(hosted.game).unwrap();
+            let table = relock(&table);
+            assert!(!table.seats[1].bot);
+            assert!(table.driver.is_human(1));
+        }
+        // the turn is left for the player now, not the bot
+        play_turn(&mut first);
+        assert!(second.view().unwrap().is_turn());
+        handle.stop();
+    }
+
+    #[test]
+    fn lobby_table_starts_and_is_joined() {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let handle = server.spawn().unwrap();
+        let mut client = Client::connect(handle.local_addr()).unwrap();
+        let rules = TableRules { players: 3, canastas: 1, full_game: false, ..Default::default() };
+        let table = client.create_table(rules).unwrap().table;
+        assert_eq!(client.sit_down(table, "Ada", Some(1)).unwrap(), 1);
+        let info = client.add_bot("random", None).unwrap();
+        assert!(info.seats.iter().all(|s| *s != SeatInfo::Empty));
+
+        let (game, seat) = client.join_when_started().unwrap();
+        assert_eq!(seat, 1);
+        assert!(client.tables().unwrap().is_empty());
+        assert_eq!(server.with_game(game, |g| g.num_players()), Some(3));
+        // the bots have played up to the turn of the player
+        let view = client.view().unwrap();
+        assert!(view.is_turn() || view.table.phase == TurnPhase::GameOver);
+        handle.stop();
+    }
+
+    #[test]
+    fn clock_restarts_after_bot_turns() {
+        let limit = Duration::from_millis(400);
+        let (server, hosted) = human_vs_bot(TimeControl::new().turn(limit));
+        let handle = server.spawn().unwrap();
+        let mut client = Client::connect(handle.local_addr()).unwrap();
+        client.join(hosted.game, &hosted.tokens[0]).unwrap();
+        // every turn is played well within the limit, but together they take longer
+        for _ in 0..5 {
+            let view = client.view().unwrap();
+            if view.table.phase == TurnPhase::GameOver { break }
+            assert!(view.is_turn());
+            thread::sleep(limit / 3);
+            client.act(Action::Draw).unwrap();
+            let card = client.view().unwrap().hand[0].id();
+            client.act(Action::Discard(card)).unwrap();
+        }
+        let timed_out = server.with_game(hosted.game, |g| g.log().entries().iter().any(|e| e.timed_out)).unwrap();
+        assert!(!timed_out);
+        handle.stop();
+    }
+
+    #[test]
+    fn stalled_client_does_not_block() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let _stalled = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let connection = Connection::new(0, stream, Encoding::Json).unwrap();
+        let error = ErrorMessage::new(ErrorCode::InvalidMessage, "x".repeat(64 * 1024));
+        let message = ServerMessage::Error { id: None, error };
+        let start = Instant::now();
+        // the client never reads, so its socket fills then its outbox
+        let sent = (0..100_000).take_while(|_| connection.send(&message).is_ok()).count();
+        assert!((OUTBOX..100_000).contains(&sent), "{sent} messages sent");
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(connection.send(&message).is_err());
+    }
+
+    #[test]
+    fn history_is_capped() {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        // bots are seeded from the server
+        server.shared.lock().rng = ChaCha8Rng::seed_from_u64(0);
+        let game = CanastaGame::builder().players(4).canastas(2).full_game().seed(5).build().unwrap();
+        let hosted = server.host_with_bots(game, vec![Some(AgentSpec::Random); 4]);
+        let table = server.shared.table(hosted.game).unwrap();
+        let table = relock(&table);
+        let seat = &table.seats[0];
+        assert!(seat.last_seq > HISTORY as u64, "only {} events", seat.last_seq);
+        assert_eq!(seat.history.len(), HISTORY);
+        assert_eq!(seat.history.back().unwrap().0, seat.last_seq);
+    }
+
+    #[test]
+    fn idle_human_times_out() {
+        let (server, hosted) = human_vs_bot(TimeControl::new().turn(Duration::from_millis(100)));
+        let handle = server.spawn().unwrap();
+        thread::sleep(Duration::from_millis(400));
+        let log = server.with_game(hosted.game, |g| g.log().entries().to_vec()).unwrap();
+        assert!(log.iter().any(|e| e.player == 0 && e.timed_out));
+        // the bot turns in between are never played on timeout
+        assert!(log.iter().all(|e| e.player == 0 || !e.timed_out));
+        handle.stop();
+    }
+
+    #[test]
+    fn busy_game_does_not_block_other_games() {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let game = |seed| CanastaGame::builder().players(2).canastas(1).hand().seed(seed).build().unwrap();
+        let busy = server.host(game(1));
+        let free = server.host(game(3));
+        let handle = server.spawn().unwrap();
+        // holding the lock of one game stands in for a bot taking its time over a turn
+        let table = server.shared.table(busy.game).unwrap();
+        let held = relock(&table);
+
+        let addr = handle.local_addr();
+        let player = server.with_game(free.game, |g| g.get_current_player()).unwrap();
+        let token = free.tokens[player as usize].clone();
+        let (done, finished) = mpsc::channel();
+        thread::spawn(move || {
+            let mut client = Client::connect(addr).unwrap();
+            client.join(free.game, &token).unwrap();
+            client.act(Action::Draw).unwrap();
+            client.tables().unwrap();
+            done.send(()).unwrap();
+        });
+        assert!(finished.recv_timeout(Duration::from_secs(5)).is_ok());
+        drop(held);
+        handle.stop();
+    }
+
+    #[test]
+    fn stop_closes_every_connection() {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let handle = server.spawn().unwrap();
+        let addr = handle.local_addr();
+        // one connection still in the handshake, one in the lobby and one joined to nothing
+        let handshaking = TcpStream::connect(addr).unwrap();
+        let mut lobby = Client::connect(addr).unwrap();
+        lobby.tables().unwrap();
+        let mut idle = Client::connect(addr).unwrap();
+        idle.tables().unwrap();
+        while server.shared.streams().len() < 3 { thread::sleep(Duration::from_millis(10)) }
+
+        let (closed, results) = mpsc::channel();
+        for mut client in [lobby, idle] {
+            let closed = closed.clone();
+            thread::spawn(move || closed.send(client.recv().is_err()).unwrap());
+        }
+        handle.stop();
+        handshaking.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        assert_eq!(io::Read::read(&mut &handshaking, &mut [0; 16]).unwrap(), 0);
+        for _ in 0..2 {
+            assert_eq!(results.recv_timeout(Duration::from_secs(5)), Ok(true));
+        }
+    }
+}